@@ -0,0 +1,245 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 3, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate num_cpus;
+
+use std::f64;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::las::LasFile;
+use lidar::point_data::PointData;
+
+/// The extent and point count of a single LAS tile, as read from its header.
+#[derive(Default, Clone, Debug)]
+pub struct LasTileInfo {
+    pub file_name: String,
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+    pub min_z: f64,
+    pub max_z: f64,
+    pub number_of_points: usize,
+}
+
+impl LasTileInfo {
+    /// Returns `true` if the tile's extent, expanded by `buffer`, overlaps the
+    /// extent of `other`.
+    pub fn overlaps(&self, other: &LasTileInfo, buffer: f64) -> bool {
+        !(other.max_x < self.min_x - buffer || other.min_x > self.max_x + buffer ||
+          other.max_y < self.min_y - buffer || other.min_y > self.max_y + buffer)
+    }
+
+    /// Returns `true` if the point (x, y) falls within the tile's extent.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+}
+
+/// A catalog of the LAS tiles contained within a directory. Only the file
+/// headers are read when the catalog is created; point data are read on
+/// demand, one (buffered) tile at a time.
+#[derive(Default, Clone, Debug)]
+pub struct LasCatalog {
+    pub tiles: Vec<LasTileInfo>,
+}
+
+impl LasCatalog {
+    /// Creates a catalog of all of the LAS files (*.las, *.las.zip) in a directory.
+    pub fn new<'a>(directory: &'a str) -> Result<LasCatalog, Error> {
+        let mut files = vec![];
+        for entry in fs::read_dir(directory)? {
+            let p = entry?.path();
+            let s = format!("{}", p.display());
+            let lc = s.to_lowercase();
+            if lc.ends_with(".las") || lc.ends_with(".las.zip") {
+                files.push(s);
+            }
+        }
+        files.sort();
+        LasCatalog::from_files(files)
+    }
+
+    /// Creates a catalog from a list of LAS files.
+    pub fn from_files(files: Vec<String>) -> Result<LasCatalog, Error> {
+        let mut tiles = Vec::with_capacity(files.len());
+        for file_name in files {
+            let lf = LasFile::new(&file_name, "rh")?;
+            tiles.push(LasTileInfo {
+                file_name: file_name.clone(),
+                min_x: lf.header.min_x,
+                max_x: lf.header.max_x,
+                min_y: lf.header.min_y,
+                max_y: lf.header.max_y,
+                min_z: lf.header.min_z,
+                max_z: lf.header.max_z,
+                number_of_points: lf.header.number_of_points as usize,
+            });
+        }
+        Ok(LasCatalog { tiles: tiles })
+    }
+
+    /// Returns the number of tiles in the catalog.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns `true` if the catalog contains no tiles.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Returns the indices of the tiles that fall within `buffer` of the
+    /// extent of tile `index`, excluding the tile itself.
+    pub fn get_neighbours(&self, index: usize, buffer: f64) -> Vec<usize> {
+        let mut ret = vec![];
+        for j in 0..self.tiles.len() {
+            if j != index && self.tiles[index].overlaps(&self.tiles[j], buffer) {
+                ret.push(j);
+            }
+        }
+        ret
+    }
+
+    /// Reads tile `index` along with all of the points from adjacent tiles that
+    /// fall within `buffer` of the tile's extent.
+    pub fn read_buffered_tile(&self, index: usize, buffer: f64) -> Result<BufferedTile, Error> {
+        if index >= self.tiles.len() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tile index is outside of the catalog."));
+        }
+        let info = self.tiles[index].clone();
+        let las = LasFile::new(&info.file_name, "r")?;
+        let (min_x, max_x) = (info.min_x - buffer, info.max_x + buffer);
+        let (min_y, max_y) = (info.min_y - buffer, info.max_y + buffer);
+        let mut buffer_points = vec![];
+        if buffer > 0f64 {
+            for j in self.get_neighbours(index, buffer) {
+                let neighbour = LasFile::new(&self.tiles[j].file_name, "r")?;
+                for i in 0..neighbour.header.number_of_points as usize {
+                    let p: PointData = neighbour[i];
+                    if p.x >= min_x && p.x <= max_x && p.y >= min_y && p.y <= max_y {
+                        buffer_points.push(p);
+                    }
+                }
+            }
+        }
+        Ok(BufferedTile {
+            index: index,
+            info: info,
+            buffer: buffer,
+            num_core_points: las.header.number_of_points as usize,
+            las: las,
+            buffer_points: buffer_points,
+        })
+    }
+
+    /// Applies `process` to every tile in the catalog, with each tile buffered by
+    /// `buffer` map units of points from its neighbours. Tiles are processed in
+    /// parallel and the function returns the first error that is encountered.
+    pub fn process_tiles<F>(&self, buffer: f64, verbose: bool, process: F) -> Result<(), Error>
+        where F: Fn(Arc<BufferedTile>) -> Result<(), Error> + Send + Sync + 'static
+    {
+        let num_tiles = self.tiles.len();
+        if num_tiles == 0 {
+            return Ok(());
+        }
+        let catalog = Arc::new(self.clone());
+        let process = Arc::new(process);
+        let num_procs = num_cpus::get().min(num_tiles);
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let catalog = catalog.clone();
+            let process = process.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for index in (0..num_tiles).filter(|index| index % num_procs == tid) {
+                    let ret = match catalog.read_buffered_tile(index, buffer) {
+                        Ok(tile) => process(Arc::new(tile)),
+                        Err(e) => Err(e),
+                    };
+                    tx.send((index, ret)).unwrap();
+                }
+            });
+        }
+
+        let mut first_error: Option<Error> = None;
+        for k in 0..num_tiles {
+            let (index, ret) = rx.recv().unwrap();
+            match ret {
+                Ok(_) => {
+                    if verbose {
+                        let short_name = self.tiles[index].file_name
+                            .split(path::MAIN_SEPARATOR).last().unwrap_or("").to_string();
+                        println!("Processed tile {} of {} ({:.2}%) {}", k+1, num_tiles,
+                            (k+1) as f64 / num_tiles as f64 * 100f64, short_name);
+                    }
+                },
+                Err(e) => {
+                    if verbose {
+                        println!("Error processing {}: {}", self.tiles[index].file_name, e);
+                    }
+                    if first_error.is_none() {
+                        first_error = Some(e);
+                    }
+                },
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A LAS tile along with a buffer of points drawn from its neighbouring tiles.
+/// Points are indexed such that the tile's own (core) points come first,
+/// followed by the buffer points.
+pub struct BufferedTile {
+    pub index: usize,
+    pub info: LasTileInfo,
+    pub buffer: f64,
+    pub las: LasFile,
+    pub buffer_points: Vec<PointData>,
+    num_core_points: usize,
+}
+
+impl BufferedTile {
+    /// Returns the total number of points, including buffer points.
+    pub fn len(&self) -> usize {
+        self.num_core_points + self.buffer_points.len()
+    }
+
+    /// Returns the number of points belonging to the tile itself.
+    pub fn num_core_points(&self) -> usize {
+        self.num_core_points
+    }
+
+    /// Returns `true` if point `index` belongs to the tile rather than the buffer.
+    pub fn is_core_point(&self, index: usize) -> bool {
+        index < self.num_core_points
+    }
+
+    /// Returns the point data for point `index`.
+    pub fn get_point_info(&self, index: usize) -> PointData {
+        if index < self.num_core_points {
+            self.las.get_point_info(index)
+        } else {
+            self.buffer_points[index - self.num_core_points]
+        }
+    }
+
+    /// Returns the core extent of the tile as (min_x, max_x, min_y, max_y), i.e.
+    /// the extent that outputs should be trimmed to.
+    pub fn core_extent(&self) -> (f64, f64, f64, f64) {
+        (self.info.min_x, self.info.max_x, self.info.min_y, self.info.max_y)
+    }
+}
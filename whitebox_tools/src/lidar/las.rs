@@ -35,6 +35,8 @@ pub struct LasFile {
     gps_data: Vec<f64>,
     rgb_data: Vec<RgbData>,
     waveform_data: Vec<WaveformPacket>,
+    extra_bytes: Vec<u8>,
+    extra_bytes_length: usize,
     pub geokeys: GeoKeys,
     // starting_point: usize,
     header_is_set: bool,
@@ -82,9 +84,12 @@ impl LasFile {
 
         output.add_header(input.header.clone());
 
-        // Copy the VLRs
+        // Copy the VLRs. The extra bytes of the input points are not carried over
+        // to the output, so neither is their description.
         for i in 0..(input.header.number_of_vlrs as usize) {
-            output.add_vlr(input.vlr_data[i].clone());
+            if !is_extra_bytes_vlr(&input.vlr_data[i]) {
+                output.add_vlr(input.vlr_data[i].clone());
+            }
         }
 
         output
@@ -128,6 +133,62 @@ impl LasFile {
         self.header.number_of_vlrs += 1;
     }
 
+    /// Describes a new 'extra bytes' point attribute, following the LAS 1.4
+    /// Extra Bytes VLR. The values of the attribute must then be supplied for
+    /// every point, in the order that attributes were added, using add_extra_bytes().
+    /// The name and description must be ASCII strings of no more than 32 bytes.
+    pub fn add_extra_bytes_attribute(&mut self, name: &str, description: &str, data_type: ExtraBytesDataType) -> Result<(), Error> {
+        if self.file_mode == "r" { return Ok(()); }
+        for s in [name, description].iter() {
+            if !s.is_ascii() || s.len() > 32 {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    format!("The extra bytes attribute name or description '{}' must be an ASCII string of no more than 32 bytes.", s)));
+            }
+        }
+        if !self.header_is_set {
+            panic!("The header of a LAS file must be added before any VLRs. Please see add_header().");
+        }
+        let mut descriptor = vec![0u8; 192];
+        descriptor[2] = data_type as u8;
+        descriptor[4..36].copy_from_slice(fixed_length_string(name, 32).as_bytes());
+        descriptor[160..192].copy_from_slice(fixed_length_string(description, 32).as_bytes());
+
+        match self.vlr_data.iter().position(|vlr| is_extra_bytes_vlr(vlr)) {
+            Some(idx) => {
+                self.vlr_data[idx].binary_data.extend(descriptor);
+                self.vlr_data[idx].record_length_after_header += 192;
+            },
+            None => {
+                self.add_vlr(Vlr {
+                    reserved: 0u16,
+                    user_id: "LASF_Spec".to_string(),
+                    record_id: 4u16,
+                    record_length_after_header: 192u16,
+                    description: "Extra bytes".to_string(),
+                    binary_data: descriptor,
+                });
+            },
+        }
+        self.extra_bytes_length += data_type.size();
+        Ok(())
+    }
+
+    /// Adds the extra bytes of the most recently added point record.
+    pub fn add_extra_bytes(&mut self, bytes: &[u8]) {
+        if self.file_mode == "r" { return; }
+        self.extra_bytes.extend_from_slice(bytes);
+    }
+
+    /// Returns the extra bytes (i.e. those beyond the standard point record) of a point.
+    pub fn get_extra_bytes(&self, index: usize) -> &[u8] {
+        &self.extra_bytes[index * self.extra_bytes_length..(index + 1) * self.extra_bytes_length]
+    }
+
+    /// Returns the number of extra bytes stored with each point record.
+    pub fn get_extra_bytes_length(&self) -> usize {
+        self.extra_bytes_length
+    }
+
     pub fn add_point_record(&mut self, point: LidarPointRecord) {
         if self.file_mode == "r" { return; }
         if !self.header_is_set {
@@ -435,6 +496,20 @@ impl LasFile {
                 } else if self.header.point_record_length == rec_lengths[self.header.point_format as usize][3] {
                     self.use_point_intensity = false;
                     self.use_point_userdata = false;
+                } else if self.header.point_record_length > rec_lengths[self.header.point_format as usize][0] {
+                    // the point records contain extra bytes
+                    self.use_point_intensity = true;
+                    self.use_point_userdata = true;
+                    self.extra_bytes_length = (self.header.point_record_length - rec_lengths[self.header.point_format as usize][0]) as usize;
+                }
+
+                if self.extra_bytes_length > 0 {
+                    let standard_length = (self.header.point_record_length as usize) - self.extra_bytes_length;
+                    self.extra_bytes.reserve(self.header.number_of_points as usize * self.extra_bytes_length);
+                    for i in 0..self.header.number_of_points as usize {
+                        offset = self.header.offset_to_points as usize + i * (self.header.point_record_length as usize) + standard_length;
+                        self.extra_bytes.extend_from_slice(&buffer[offset..offset + self.extra_bytes_length]);
+                    }
                 }
 
                 for i in 0..self.header.number_of_points {
@@ -524,6 +599,10 @@ impl LasFile {
             return Err(Error::new(ErrorKind::Other, "The header of a LAS file must be added before any point records. Please see add_header()."));
         }
 
+        if self.extra_bytes.len() != self.header.number_of_points as usize * self.extra_bytes_length {
+            return Err(Error::new(ErrorKind::Other, "The number of extra bytes does not match the number of point records. Please see add_extra_bytes()."));
+        }
+
         self.header.x_offset = self.header.min_x;
         self.header.y_offset = self.header.min_y;
         self.header.z_offset = self.header.min_z;
@@ -660,6 +739,7 @@ impl LasFile {
         } else { //if !self.use_point_intensity && !self.use_point_userdata {
             self.header.point_record_length = rec_lengths[self.header.point_format as usize][3];
         }
+        self.header.point_record_length += self.extra_bytes_length as u16;
 
         u16_bytes = unsafe { mem::transmute(self.header.point_record_length) };
         writer.write_all(&u16_bytes)?;
@@ -776,6 +856,10 @@ impl LasFile {
 
                     u16_bytes = unsafe { mem::transmute(self.point_data[i].point_source_id) };
                     writer.write_all(&u16_bytes)?;
+
+                    if self.extra_bytes_length > 0 {
+                        writer.write_all(self.get_extra_bytes(i))?;
+                    }
                 }
             },
             1 => {
@@ -816,6 +900,10 @@ impl LasFile {
                     
                     u64_bytes = unsafe { mem::transmute(self.gps_data[i]) };
                     writer.write_all(&u64_bytes)?;
+
+                    if self.extra_bytes_length > 0 {
+                        writer.write_all(self.get_extra_bytes(i))?;
+                    }
                 }
             },
             2 => {
@@ -862,6 +950,10 @@ impl LasFile {
                     
                     u16_bytes = unsafe { mem::transmute(self.rgb_data[i].blue) };
                     writer.write_all(&u16_bytes)?;
+
+                    if self.extra_bytes_length > 0 {
+                        writer.write_all(self.get_extra_bytes(i))?;
+                    }
                 }
             },
             3 => {
@@ -911,6 +1003,10 @@ impl LasFile {
                     
                     u16_bytes = unsafe { mem::transmute(self.rgb_data[i].blue) };
                     writer.write_all(&u16_bytes)?;
+
+                    if self.extra_bytes_length > 0 {
+                        writer.write_all(self.get_extra_bytes(i))?;
+                    }
                 }
             },
             _ => {
@@ -1007,6 +1103,33 @@ pub enum GpsTimeType {
     SatelliteGpsTime,
 }
 
+/// Data types of 'extra bytes' point attributes, as defined by the LAS 1.4 specification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtraBytesDataType {
+    U8 = 1,
+    I8 = 2,
+    U16 = 3,
+    I16 = 4,
+    U32 = 5,
+    I32 = 6,
+    U64 = 7,
+    I64 = 8,
+    F32 = 9,
+    F64 = 10,
+}
+
+impl ExtraBytesDataType {
+    /// Returns the number of bytes occupied by a value of this type.
+    pub fn size(&self) -> usize {
+        match *self {
+            ExtraBytesDataType::U8 | ExtraBytesDataType::I8 => 1,
+            ExtraBytesDataType::U16 | ExtraBytesDataType::I16 => 2,
+            ExtraBytesDataType::U32 | ExtraBytesDataType::I32 | ExtraBytesDataType::F32 => 4,
+            ExtraBytesDataType::U64 | ExtraBytesDataType::I64 | ExtraBytesDataType::F64 => 8,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum CoordinateReferenceSystem {
     WellKnownText,
@@ -1096,6 +1219,10 @@ impl PointRecord5 {
     }
 }
 
+fn is_extra_bytes_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id.starts_with("LASF_Spec") && vlr.record_id == 4u16
+}
+
 fn fixed_length_string(s: &str, len: usize) -> String {
     //let array: &[u8: 32];
     let l = s.len();
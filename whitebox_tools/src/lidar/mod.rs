@@ -4,13 +4,19 @@
 // pub mod vlr;
 
 // private sub-module defined in other files
+mod catalog;
 mod header;
 mod las;
 mod point_data;
+mod surface;
 mod vlr;
 
 // exports identifiers from private sub-modules in the current module namespace
+pub use self::catalog::BufferedTile;
+pub use self::catalog::LasCatalog;
+pub use self::catalog::LasTileInfo;
 pub use self::las::CoordinateReferenceSystem;
+pub use self::las::ExtraBytesDataType;
 pub use self::las::GlobalEncodingField;
 pub use self::las::GpsTimeType;
 pub use self::header::LasHeader;
@@ -28,4 +34,9 @@ pub use self::point_data::PointData;
 pub use self::point_data::RgbData;
 pub use self::point_data::WaveformPacket;
 pub use self::point_data::convert_class_val_to_class_string;
+pub use self::point_data::get_interp_value;
+pub use self::surface::interpolate_raster_value;
+pub use self::surface::tin_interpolation;
+pub use self::surface::GroundTin;
+pub use self::surface::TinMerge;
 pub use self::vlr::Vlr;
@@ -132,6 +132,18 @@ impl fmt::Display for ClassificationBitField {
     }
 }
 
+/// Returns the value of a point's interpolation parameter, i.e. one of 'elevation' (or 'z'),
+/// 'intensity', 'scan angle', 'class', or otherwise the point's user data.
+pub fn get_interp_value(p: &PointData, interp_parameter: &str) -> f64 {
+    match interp_parameter {
+        "elevation" | "z" => p.z,
+        "intensity" => p.intensity as f64,
+        "scan angle" => p.scan_angle as f64,
+        "class" => p.classification() as f64,
+        _ => p.user_data as f64, // user data
+    }
+}
+
 /// Returns a string represenation of a classiciation numeric value.
 pub fn convert_class_val_to_class_string(value: u8) -> String {
     match value {
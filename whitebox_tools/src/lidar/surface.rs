@@ -0,0 +1,208 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 22, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+use std::f64;
+use raster::Raster;
+use structures::{triangulate, KdTree, Point2D, Triangulation};
+use structures::kd_tree::squared_euclidean;
+
+/// How the values interpolated from a TIN are merged with the existing values of a raster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TinMerge {
+    /// The interpolated value replaces the existing value.
+    Replace,
+    /// The larger of the interpolated and existing values is retained.
+    Max,
+}
+
+/// Triangulates the points and linearly interpolates the value of each grid cell
+/// whose centre falls within a triangle, skipping triangles with an edge longer than
+/// `max_edge_length`. The interpolated values are merged with the existing cell values
+/// according to `merge`. Returns `false` if the points could not be triangulated.
+pub fn tin_interpolation(points: &[Point2D], values: &[f64], max_edge_length: f64, merge: TinMerge, output: &mut Raster) -> bool {
+    let tin = match triangulate(points) {
+        Some(t) => t,
+        None => return false,
+    };
+    let max_edge_sqr = max_edge_length * max_edge_length;
+    let rows = output.configs.rows as isize;
+    let columns = output.configs.columns as isize;
+    let north = output.configs.north;
+    let west = output.configs.west;
+    let res_x = output.configs.resolution_x;
+    let res_y = output.configs.resolution_y;
+    let nodata = output.configs.nodata;
+    let (mut x, mut y, mut z, mut z_existing): (f64, f64, f64, f64);
+    let (mut w0, mut w1, mut w2): (f64, f64, f64);
+    for t in 0..tin.len() {
+        let v = tin.triangle_vertices(t);
+        let (p0, p1, p2) = (points[v[0]], points[v[1]], points[v[2]]);
+        if p0.distance_squared(&p1) > max_edge_sqr || p1.distance_squared(&p2) > max_edge_sqr ||
+            p2.distance_squared(&p0) > max_edge_sqr {
+            continue;
+        }
+        let det = (p1.y - p2.y) * (p0.x - p2.x) + (p2.x - p1.x) * (p0.y - p2.y);
+        if det == 0f64 {
+            continue;
+        }
+
+        // the range of cells whose centres may fall within the triangle's bounding box
+        let min_x = p0.x.min(p1.x).min(p2.x);
+        let max_x = p0.x.max(p1.x).max(p2.x);
+        let min_y = p0.y.min(p1.y).min(p2.y);
+        let max_y = p0.y.max(p1.y).max(p2.y);
+        let start_col = (((min_x - west) / res_x - 0.5).ceil() as isize).max(0);
+        let end_col = (((max_x - west) / res_x - 0.5).floor() as isize).min(columns - 1);
+        let start_row = (((north - max_y) / res_y - 0.5).ceil() as isize).max(0);
+        let end_row = (((north - min_y) / res_y - 0.5).floor() as isize).min(rows - 1);
+        for row in start_row..end_row + 1 {
+            y = output.get_y_from_row_centre(row);
+            for col in start_col..end_col + 1 {
+                x = output.get_x_from_column_centre(col);
+                // barycentric co-ordinates of the cell centre
+                w0 = ((p1.y - p2.y) * (x - p2.x) + (p2.x - p1.x) * (y - p2.y)) / det;
+                w1 = ((p2.y - p0.y) * (x - p2.x) + (p0.x - p2.x) * (y - p2.y)) / det;
+                w2 = 1f64 - w0 - w1;
+                if w0 >= 0f64 && w1 >= 0f64 && w2 >= 0f64 {
+                    z = w0 * values[v[0]] + w1 * values[v[1]] + w2 * values[v[2]];
+                    if merge == TinMerge::Max {
+                        z_existing = output.get_value(row, col);
+                        if z_existing != nodata && z_existing >= z {
+                            continue;
+                        }
+                    }
+                    output.set_value(row, col, z);
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Bilinearly interpolates a raster at a location, falling back on the value of
+/// the containing cell where any of the four surrounding cell centres are nodata.
+pub fn interpolate_raster_value(dtm: &Raster, x: f64, y: f64) -> f64 {
+    let nodata = dtm.configs.nodata;
+    let row = dtm.get_row_from_y(y);
+    let col = dtm.get_column_from_x(x);
+    if row < 0 || col < 0 || row >= dtm.configs.rows as isize || col >= dtm.configs.columns as isize {
+        return f64::NEG_INFINITY;
+    }
+    let cell_z = dtm.get_value(row, col);
+    if cell_z == nodata {
+        return f64::NEG_INFINITY;
+    }
+
+    // fractional row and column positions relative to the cell centres
+    let c = (x - dtm.configs.west) / dtm.configs.resolution_x - 0.5;
+    let r = (dtm.configs.north - y) / dtm.configs.resolution_y - 0.5;
+    let (c0, r0) = (c.floor() as isize, r.floor() as isize);
+    let (fc, fr) = (c - c0 as f64, r - r0 as f64);
+    let z00 = dtm.get_value(r0, c0);
+    let z01 = dtm.get_value(r0, c0 + 1);
+    let z10 = dtm.get_value(r0 + 1, c0);
+    let z11 = dtm.get_value(r0 + 1, c0 + 1);
+    if z00 == nodata || z01 == nodata || z10 == nodata || z11 == nodata {
+        return cell_z;
+    }
+    (z00 * (1f64 - fc) + z01 * fc) * (1f64 - fr) + (z10 * (1f64 - fc) + z11 * fc) * fr
+}
+
+/// A TIN ground surface, with triangles bucketed into a coarse grid for point location.
+pub struct GroundTin {
+    points: Vec<Point2D>,
+    z_values: Vec<f64>,
+    tin: Triangulation,
+    buckets: Vec<Vec<usize>>,
+    west: f64,
+    north: f64,
+    bucket_size: f64,
+    rows: isize,
+    columns: isize,
+    tree: KdTree<f64, [f64; 2]>,
+}
+
+impl GroundTin {
+    /// Triangulates the ground points, returning `None` if they cannot be triangulated.
+    pub fn new(points: Vec<Point2D>, z_values: Vec<f64>) -> Option<GroundTin> {
+        let tin = match triangulate(&points) {
+            Some(t) => t,
+            None => return None,
+        };
+        let (mut west, mut east, mut south, mut north) = (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY);
+        for p in &points {
+            west = west.min(p.x);
+            east = east.max(p.x);
+            south = south.min(p.y);
+            north = north.max(p.y);
+        }
+        // roughly a few points per bucket
+        let bucket_size = (((east - west) * (north - south) / points.len() as f64).sqrt() * 2f64).max(1e-6);
+        let rows = ((north - south) / bucket_size).floor() as isize + 1;
+        let columns = ((east - west) / bucket_size).floor() as isize + 1;
+        let mut buckets = vec![vec![]; (rows * columns) as usize];
+        for t in 0..tin.len() {
+            let v = tin.triangle_vertices(t);
+            let (p0, p1, p2) = (points[v[0]], points[v[1]], points[v[2]]);
+            let start_col = ((p0.x.min(p1.x).min(p2.x) - west) / bucket_size).floor() as isize;
+            let end_col = ((p0.x.max(p1.x).max(p2.x) - west) / bucket_size).floor() as isize;
+            let start_row = ((north - p0.y.max(p1.y).max(p2.y)) / bucket_size).floor() as isize;
+            let end_row = ((north - p0.y.min(p1.y).min(p2.y)) / bucket_size).floor() as isize;
+            for row in start_row..end_row + 1 {
+                for col in start_col..end_col + 1 {
+                    buckets[(row * columns + col) as usize].push(t);
+                }
+            }
+        }
+
+        let mut tree = KdTree::new_with_capacity(2, 64);
+        for i in 0..points.len() {
+            tree.add([points[i].x, points[i].y], z_values[i]).unwrap();
+        }
+
+        Some(GroundTin {
+            points: points,
+            z_values: z_values,
+            tin: tin,
+            buckets: buckets,
+            west: west,
+            north: north,
+            bucket_size: bucket_size,
+            rows: rows,
+            columns: columns,
+            tree: tree,
+        })
+    }
+
+    /// Returns the TIN surface elevation at a location, or the elevation of the
+    /// nearest ground point for locations outside of the TIN's convex hull.
+    pub fn get_elevation(&self, x: f64, y: f64) -> f64 {
+        let row = ((self.north - y) / self.bucket_size).floor() as isize;
+        let col = ((x - self.west) / self.bucket_size).floor() as isize;
+        if row >= 0 && col >= 0 && row < self.rows && col < self.columns {
+            for &t in &self.buckets[(row * self.columns + col) as usize] {
+                let v = self.tin.triangle_vertices(t);
+                let (p0, p1, p2) = (self.points[v[0]], self.points[v[1]], self.points[v[2]]);
+                let det = (p1.y - p2.y) * (p0.x - p2.x) + (p2.x - p1.x) * (p0.y - p2.y);
+                if det == 0f64 {
+                    continue;
+                }
+                // barycentric co-ordinates of the location
+                let w0 = ((p1.y - p2.y) * (x - p2.x) + (p2.x - p1.x) * (y - p2.y)) / det;
+                let w1 = ((p2.y - p0.y) * (x - p2.x) + (p0.x - p2.x) * (y - p2.y)) / det;
+                let w2 = 1f64 - w0 - w1;
+                if w0 >= 0f64 && w1 >= 0f64 && w2 >= 0f64 {
+                    return w0 * self.z_values[v[0]] + w1 * self.z_values[v[1]] + w2 * self.z_values[v[2]];
+                }
+            }
+        }
+        match self.tree.nearest(&[x, y], 1, &squared_euclidean) {
+            Ok(ret) => if ret.len() > 0 { *ret[0].1 } else { f64::NEG_INFINITY },
+            Err(_) => f64::NEG_INFINITY,
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub mod raster;
 pub mod rendering;
 pub mod tools;
 pub mod structures;
+pub mod vector;
 
 use std::io::Error;
 use std::env;
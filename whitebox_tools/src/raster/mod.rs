@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 2, 2017
-Last Modified: July 7, 2017
+Last Modified: February 22, 2018
 License: MIT
 */
 
@@ -432,6 +432,16 @@ impl Raster {
         row as f64 * self.configs.resolution_y
     }
 
+    /// Returns the x coordinate of the centre of the cells in a column.
+    pub fn get_x_from_column_centre(&self, column: isize) -> f64 {
+        self.configs.west + (column as f64 + 0.5) * self.configs.resolution_x
+    }
+
+    /// Returns the y coordinate of the centre of the cells in a row.
+    pub fn get_y_from_row_centre(&self, row: isize) -> f64 {
+        self.configs.north - (row as f64 + 0.5) * self.configs.resolution_y
+    }
+
     pub fn get_column_from_x(&self, x: f64) -> isize {
         ((x - self.configs.west) / self.configs.resolution_x).floor() as isize
     }
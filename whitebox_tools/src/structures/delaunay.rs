@@ -0,0 +1,422 @@
+/////////////////////////////////////////////////////////////////
+// A 2D Delaunay triangulation, using the sweep-hull algorithm
+// described by Sinclair (2010) and implemented in the
+// Delaunator library by V. Agafonkin.
+/////////////////////////////////////////////////////////////////
+use std::cmp::Ordering;
+use std::f64;
+use structures::Point2D;
+
+/// Marks a half-edge with no adjacent half-edge, i.e. an edge on the convex hull.
+pub const EMPTY: usize = usize::max_value();
+
+const EPSILON: f64 = f64::EPSILON * 2.0;
+
+/// The result of a Delaunay triangulation.
+///
+/// * `triangles[3 * t..3 * t + 3]` are the indices of the vertices of triangle `t`, in
+///   counter-clockwise order.
+/// * `halfedges[e]` is the index of the twin of half-edge `e` in the adjacent triangle,
+///   or `EMPTY` if the half-edge lies on the convex hull.
+/// * `hull` holds the indices of the points on the convex hull, counter-clockwise.
+#[derive(Default, Clone, Debug)]
+pub struct Triangulation {
+    pub triangles: Vec<usize>,
+    pub halfedges: Vec<usize>,
+    pub hull: Vec<usize>,
+}
+
+impl Triangulation {
+    fn new(n: usize) -> Triangulation {
+        let max_triangles = if n > 2 { 2 * n - 5 } else { 0 };
+        Triangulation {
+            triangles: Vec::with_capacity(max_triangles * 3),
+            halfedges: Vec::with_capacity(max_triangles * 3),
+            hull: vec![],
+        }
+    }
+
+    /// Returns the number of triangles.
+    pub fn len(&self) -> usize {
+        self.triangles.len() / 3
+    }
+
+    /// Returns `true` if no triangles could be formed (e.g. fewer than three
+    /// points, or all points are collinear).
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Returns the indices of the three vertices of triangle `t`.
+    pub fn triangle_vertices(&self, t: usize) -> [usize; 3] {
+        [self.triangles[3 * t], self.triangles[3 * t + 1], self.triangles[3 * t + 2]]
+    }
+
+    fn add_triangle(&mut self, i0: usize, i1: usize, i2: usize, a: usize, b: usize, c: usize) -> usize {
+        let t = self.triangles.len();
+        self.triangles.push(i0);
+        self.triangles.push(i1);
+        self.triangles.push(i2);
+        self.halfedges.push(EMPTY);
+        self.halfedges.push(EMPTY);
+        self.halfedges.push(EMPTY);
+        self.link(t, a);
+        self.link(t + 1, b);
+        self.link(t + 2, c);
+        t
+    }
+
+    fn link(&mut self, a: usize, b: usize) {
+        self.halfedges[a] = b;
+        if b != EMPTY {
+            self.halfedges[b] = a;
+        }
+    }
+
+    // Flips triangles until the Delaunay condition is satisfied, using an explicit
+    // stack rather than recursion.
+    fn legalize(&mut self, a: usize, points: &[Point2D], hull: &mut Hull) -> usize {
+        let mut a = a;
+        let mut stack: Vec<usize> = vec![];
+        let mut ar;
+        loop {
+            let b = self.halfedges[a];
+            let a0 = a - a % 3;
+            ar = a0 + (a + 2) % 3;
+            if b == EMPTY {
+                match stack.pop() {
+                    Some(v) => { a = v; continue; },
+                    None => break,
+                }
+            }
+            let b0 = b - b % 3;
+            let al = a0 + (a + 1) % 3;
+            let bl = b0 + (b + 2) % 3;
+            let p0 = self.triangles[ar];
+            let pr = self.triangles[a];
+            let pl = self.triangles[al];
+            let p1 = self.triangles[bl];
+            if in_circle(&points[p0], &points[pr], &points[pl], &points[p1]) {
+                self.triangles[a] = p1;
+                self.triangles[b] = p0;
+                let hbl = self.halfedges[bl];
+                // the edge was swapped on the other side of the hull (rare); fix the reference
+                if hbl == EMPTY {
+                    let mut e = hull.start;
+                    loop {
+                        if hull.tri[e] == bl {
+                            hull.tri[e] = a;
+                            break;
+                        }
+                        e = hull.prev[e];
+                        if e == hull.start {
+                            break;
+                        }
+                    }
+                }
+                self.link(a, hbl);
+                let har = self.halfedges[ar];
+                self.link(b, har);
+                self.link(ar, bl);
+                stack.push(b0 + (b + 1) % 3);
+            } else {
+                match stack.pop() {
+                    Some(v) => a = v,
+                    None => break,
+                }
+            }
+        }
+        ar
+    }
+}
+
+// The advancing convex hull used during the sweep.
+struct Hull {
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    tri: Vec<usize>,
+    hash: Vec<usize>,
+    start: usize,
+    center: Point2D,
+}
+
+impl Hull {
+    fn hash_key(&self, p: &Point2D) -> usize {
+        let dx = p.x - self.center.x;
+        let dy = p.y - self.center.y;
+        let q = dx / (dx.abs() + dy.abs());
+        let a = (if dy > 0.0 { 3.0 - q } else { 1.0 + q }) / 4.0; // pseudo-angle in [0, 1]
+        let len = self.hash.len();
+        ((a * len as f64).floor() as usize) % len
+    }
+}
+
+/// Triangulates a set of points, returning `None` if fewer than three non-collinear
+/// points are supplied. Duplicate points are ignored.
+pub fn triangulate(points: &[Point2D]) -> Option<Triangulation> {
+    let n = points.len();
+    if n < 3 {
+        return None;
+    }
+
+    // find the bounding box centre
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for p in points {
+        if p.x < min_x { min_x = p.x; }
+        if p.y < min_y { min_y = p.y; }
+        if p.x > max_x { max_x = p.x; }
+        if p.y > max_y { max_y = p.y; }
+    }
+    let center = Point2D::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+    // pick a seed point close to the centre
+    let mut i0 = 0;
+    let mut min_dist = f64::INFINITY;
+    for i in 0..n {
+        let d = center.distance_squared(&points[i]);
+        if d < min_dist {
+            i0 = i;
+            min_dist = d;
+        }
+    }
+
+    // find the point closest to the seed
+    let mut i1 = EMPTY;
+    min_dist = f64::INFINITY;
+    for i in 0..n {
+        if i != i0 {
+            let d = points[i0].distance_squared(&points[i]);
+            if d < min_dist && d > 0.0 {
+                i1 = i;
+                min_dist = d;
+            }
+        }
+    }
+    if i1 == EMPTY {
+        return None;
+    }
+
+    // find the third point which forms the smallest circumcircle with the first two
+    let mut i2 = EMPTY;
+    let mut min_radius = f64::INFINITY;
+    for i in 0..n {
+        if i != i0 && i != i1 {
+            let r = circumradius(&points[i0], &points[i1], &points[i]);
+            if r < min_radius {
+                i2 = i;
+                min_radius = r;
+            }
+        }
+    }
+    if i2 == EMPTY || min_radius == f64::INFINITY {
+        // all of the points are collinear
+        return None;
+    }
+
+    // orient the seed triangle counter-clockwise
+    if orient(&points[i0], &points[i1], &points[i2]) {
+        let tmp = i1;
+        i1 = i2;
+        i2 = tmp;
+    }
+
+    let center = circumcenter(&points[i0], &points[i1], &points[i2]);
+
+    // sort the points by distance from the seed triangle circumcentre
+    let mut dists: Vec<f64> = Vec::with_capacity(n);
+    for i in 0..n {
+        dists.push(points[i].distance_squared(&center));
+    }
+    let mut ids: Vec<usize> = (0..n).collect();
+    ids.sort_by(|&a, &b| dists[a].partial_cmp(&dists[b]).unwrap_or(Ordering::Equal));
+
+    // set up the seed triangle as the starting hull
+    let hash_size = (n as f64).sqrt().ceil() as usize;
+    let mut hull = Hull {
+        prev: vec![0; n],
+        next: vec![0; n],
+        tri: vec![0; n],
+        hash: vec![EMPTY; hash_size],
+        start: i0,
+        center: center,
+    };
+    hull.next[i0] = i1;
+    hull.prev[i2] = i1;
+    hull.next[i1] = i2;
+    hull.prev[i0] = i2;
+    hull.next[i2] = i0;
+    hull.prev[i1] = i0;
+    hull.tri[i0] = 0;
+    hull.tri[i1] = 1;
+    hull.tri[i2] = 2;
+    let key = hull.hash_key(&points[i0]);
+    hull.hash[key] = i0;
+    let key = hull.hash_key(&points[i1]);
+    hull.hash[key] = i1;
+    let key = hull.hash_key(&points[i2]);
+    hull.hash[key] = i2;
+    let mut hull_size = 3;
+
+    let mut triangulation = Triangulation::new(n);
+    triangulation.add_triangle(i0, i1, i2, EMPTY, EMPTY, EMPTY);
+
+    let mut pp = Point2D::new(f64::NAN, f64::NAN);
+    for k in 0..n {
+        let i = ids[k];
+        let p = points[i];
+
+        // skip near-duplicate points
+        if k > 0 && (p.x - pp.x).abs() <= EPSILON && (p.y - pp.y).abs() <= EPSILON {
+            continue;
+        }
+        pp = p;
+
+        // skip the seed triangle points
+        if i == i0 || i == i1 || i == i2 {
+            continue;
+        }
+
+        // find a visible edge on the convex hull using the edge hash
+        let mut start = 0;
+        let key = hull.hash_key(&p);
+        for j in 0..hash_size {
+            start = hull.hash[(key + j) % hash_size];
+            if start != EMPTY && start != hull.next[start] {
+                break;
+            }
+        }
+        start = hull.prev[start];
+        let mut e = start;
+        loop {
+            let q = hull.next[e];
+            if orient(&p, &points[e], &points[q]) {
+                break;
+            }
+            e = q;
+            if e == start {
+                e = EMPTY;
+                break;
+            }
+        }
+        if e == EMPTY {
+            // likely a near-duplicate point; skip it
+            continue;
+        }
+
+        // add the first triangle from the point
+        let next_e = hull.next[e];
+        let tri_e = hull.tri[e];
+        let mut t = triangulation.add_triangle(e, i, next_e, EMPTY, EMPTY, tri_e);
+
+        // flip triangles from the point until they satisfy the Delaunay condition
+        hull.tri[i] = triangulation.legalize(t + 2, points, &mut hull);
+        hull.tri[e] = t; // keep track of boundary triangles on the hull
+        hull_size += 1;
+
+        // walk forward through the hull, adding more triangles and flipping
+        let mut nx = hull.next[e];
+        loop {
+            let q = hull.next[nx];
+            if !orient(&p, &points[nx], &points[q]) {
+                break;
+            }
+            let (tri_i, tri_n) = (hull.tri[i], hull.tri[nx]);
+            t = triangulation.add_triangle(nx, i, q, tri_i, EMPTY, tri_n);
+            hull.tri[i] = triangulation.legalize(t + 2, points, &mut hull);
+            hull.next[nx] = nx; // mark as removed
+            hull_size -= 1;
+            nx = q;
+        }
+
+        // walk backward from the other side, adding more triangles and flipping
+        if e == start {
+            loop {
+                let q = hull.prev[e];
+                if !orient(&p, &points[q], &points[e]) {
+                    break;
+                }
+                let (tri_e, tri_q) = (hull.tri[e], hull.tri[q]);
+                t = triangulation.add_triangle(q, i, e, EMPTY, tri_e, tri_q);
+                triangulation.legalize(t + 2, points, &mut hull);
+                hull.tri[q] = t;
+                hull.next[e] = e; // mark as removed
+                hull_size -= 1;
+                e = q;
+            }
+        }
+
+        // update the hull indices
+        hull.start = e;
+        hull.prev[i] = e;
+        hull.next[e] = i;
+        hull.prev[nx] = i;
+        hull.next[i] = nx;
+
+        // save the two new edges in the hash table
+        let key = hull.hash_key(&p);
+        hull.hash[key] = i;
+        let key = hull.hash_key(&points[e]);
+        hull.hash[key] = e;
+    }
+
+    let mut e = hull.start;
+    for _ in 0..hull_size {
+        triangulation.hull.push(e);
+        e = hull.next[e];
+    }
+
+    Some(triangulation)
+}
+
+/// Returns the next half-edge within the same triangle.
+pub fn next_halfedge(e: usize) -> usize {
+    if e % 3 == 2 { e - 2 } else { e + 1 }
+}
+
+/// Returns the previous half-edge within the same triangle.
+pub fn prev_halfedge(e: usize) -> usize {
+    if e % 3 == 0 { e + 2 } else { e - 1 }
+}
+
+// Returns `true` if the points p, q, r are in clockwise order.
+fn orient(p: &Point2D, q: &Point2D, r: &Point2D) -> bool {
+    (q.y - p.y) * (r.x - q.x) - (q.x - p.x) * (r.y - q.y) < 0.0
+}
+
+fn circumdelta(a: &Point2D, b: &Point2D, c: &Point2D) -> (f64, f64) {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let ex = c.x - a.x;
+    let ey = c.y - a.y;
+    let bl = dx * dx + dy * dy;
+    let cl = ex * ex + ey * ey;
+    let d = 0.5 / (dx * ey - dy * ex);
+    ((ey * bl - dy * cl) * d, (dx * cl - ex * bl) * d)
+}
+
+// Returns the squared radius of the circle passing through a, b, and c.
+fn circumradius(a: &Point2D, b: &Point2D, c: &Point2D) -> f64 {
+    let (x, y) = circumdelta(a, b, c);
+    let r = x * x + y * y;
+    if r.is_finite() { r } else { f64::INFINITY }
+}
+
+fn circumcenter(a: &Point2D, b: &Point2D, c: &Point2D) -> Point2D {
+    let (x, y) = circumdelta(a, b, c);
+    Point2D::new(a.x + x, a.y + y)
+}
+
+// Returns `true` if p lies within the circumcircle of a, b, and c.
+fn in_circle(a: &Point2D, b: &Point2D, c: &Point2D, p: &Point2D) -> bool {
+    let dx = a.x - p.x;
+    let dy = a.y - p.y;
+    let ex = b.x - p.x;
+    let ey = b.y - p.y;
+    let fx = c.x - p.x;
+    let fy = c.y - p.y;
+    let ap = dx * dx + dy * dy;
+    let bp = ex * ex + ey * ey;
+    let cp = fx * fx + fy * fy;
+    dx * (ey * cp - bp * fy) - dy * (ex * cp - bp * fx) + ap * (ex * fy - ey * fx) < 0.0
+}
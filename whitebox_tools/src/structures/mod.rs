@@ -1,10 +1,15 @@
 // private sub-module defined in other files
 mod array2d;
+pub mod delaunay;
 mod fixed_radius_search;
 pub mod kd_tree;
+mod point2d;
 
 // exports identifiers from private sub-modules in the current module namespace
 pub use self::array2d::Array2D;
+pub use self::delaunay::triangulate;
+pub use self::delaunay::Triangulation;
 pub use self::fixed_radius_search::FixedRadiusSearch2D;
 pub use self::fixed_radius_search::FixedRadiusSearch3D;
 pub use self::kd_tree::KdTree;
+pub use self::point2d::Point2D;
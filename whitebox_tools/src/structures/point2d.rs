@@ -0,0 +1,32 @@
+/////////////////////////////////////
+// A simple 2-dimensional point type
+/////////////////////////////////////
+use std::fmt;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct Point2D {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point2D {
+    pub fn new(x: f64, y: f64) -> Point2D {
+        Point2D { x: x, y: y }
+    }
+
+    /// Returns the squared Euclidean distance to another point.
+    pub fn distance_squared(&self, other: &Point2D) -> f64 {
+        (self.x - other.x) * (self.x - other.x) + (self.y - other.y) * (self.y - other.y)
+    }
+
+    /// Returns the Euclidean distance to another point.
+    pub fn distance(&self, other: &Point2D) -> f64 {
+        self.distance_squared(other).sqrt()
+    }
+}
+
+impl fmt::Display for Point2D {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
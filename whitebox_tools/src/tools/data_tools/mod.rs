@@ -2,10 +2,14 @@
 mod convert_nodata_to_zero;
 mod convert_raster_format;
 mod new_raster;
+mod raster_to_vector_polygons;
 mod set_nodata_value;
+mod vector_to_raster;
 
 // exports identifiers from private sub-modules in the current module namespace
 pub use self::convert_nodata_to_zero::ConvertNodataToZero;
 pub use self::convert_raster_format::ConvertRasterFormat;
 pub use self::new_raster::NewRasterFromBase;
-pub use self::set_nodata_value::SetNodataValue;
\ No newline at end of file
+pub use self::raster_to_vector_polygons::RasterToVectorPolygons;
+pub use self::set_nodata_value::SetNodataValue;
+pub use self::vector_to_raster::VectorToRaster;
\ No newline at end of file
@@ -0,0 +1,495 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 8, 2018
+Last Modified: February 8, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::collections::HashMap;
+use std::path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use structures::{Array2D, Point2D};
+use tools::*;
+use vector::*;
+
+/// Converts the patches of a raster, i.e. regions of connected cells of the same value, into
+/// polygons. This is useful for vectorizing the output of tools such as `Clump`, `Watershed`,
+/// `Subbasins`, `Isobasins` and `KMeansClustering`. Regions are made of cells that are
+/// connected in the four cardinal directions, and nodata cells are not vectorized. Polygons
+/// follow the cell boundaries and contain a hole for each enclosed region of a different value.
+/// The output polygons have FID, VALUE (the cell value) and AREA (the total area of the
+/// region's cells) attributes.
+///
+/// The stair-stepped boundaries may optionally be simplified, using the Douglas-Peucker
+/// algorithm with a tolerance in map units (`--simplify`), and smoothed, using a number of
+/// iterations of Chaikin's corner-cutting algorithm (`--smooth`). Note that the boundaries of
+/// each polygon are simplified and smoothed independently, so that neighbouring polygons may
+/// no longer share their boundaries exactly.
+pub struct RasterToVectorPolygons {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl RasterToVectorPolygons {
+    pub fn new() -> RasterToVectorPolygons {
+        // public constructor
+        let name = "RasterToVectorPolygons".to_string();
+        let toolbox = "Data Tools".to_string();
+        let description = "Converts the connected regions of a raster into vector polygons.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output polygon vector file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Simplification Tolerance".to_owned(),
+            flags: vec!["--simplify".to_owned()],
+            description: "Optional Douglas-Peucker simplification tolerance, in map units; zero for no simplification.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Smoothing Iterations".to_owned(),
+            flags: vec!["--smooth".to_owned()],
+            description: "Optional number of iterations of boundary smoothing; zero for no smoothing.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=watersheds.tif -o=watersheds.shp
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=clusters.tif -o=clusters.shp --simplify=5.0 --smooth=2", short_exe, name).replace("*", &sep);
+
+        RasterToVectorPolygons {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for RasterToVectorPolygons {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file = String::new();
+        let mut output_file = String::new();
+        let mut tolerance = 0f64;
+        let mut smooth_iterations = 0usize;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-simplify" {
+                tolerance = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-smooth" {
+                smooth_iterations = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading data...") };
+        let input = Raster::new(&input_file, "r")?;
+
+        let start = time::now();
+
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+        let cell_area = (input.configs.resolution_x * input.configs.resolution_y).abs();
+        let d_x = [1, 0, -1, 0];
+        let d_y = [0, 1, 0, -1];
+
+        // the region label of each cell; zero for unlabelled cells and -1 for nodata cells
+        let mut labels: Array2D<i32> = Array2D::new(rows, columns, 0, -1)?;
+        let mut regions: Vec<(f64, Vec<Vec<Point2D>>, usize)> = vec![]; // (value, rings, num. cells)
+        let mut stack = vec![];
+        let mut region_cells = vec![];
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for row in 0..rows {
+            for col in 0..columns {
+                if labels.get_value(row, col) != 0 {
+                    continue;
+                }
+                let value = input.get_value(row, col);
+                if value == nodata {
+                    labels.set_value(row, col, -1);
+                    continue;
+                }
+
+                // flood fill the region
+                let label = regions.len() as i32 + 1;
+                labels.set_value(row, col, label);
+                stack.push((row, col));
+                region_cells.clear();
+                while let Some((r, c)) = stack.pop() {
+                    region_cells.push((r, c));
+                    for n in 0..4 {
+                        let (rn, cn) = (r + d_y[n], c + d_x[n]);
+                        if rn >= 0 && rn < rows && cn >= 0 && cn < columns && labels.get_value(rn, cn) == 0 && input.get_value(rn, cn) == value {
+                            labels.set_value(rn, cn, label);
+                            stack.push((rn, cn));
+                        }
+                    }
+                }
+
+                let rings = trace_region_boundary(&input, &labels, label, &region_cells);
+                regions.push((value, rings, region_cells.len()));
+            }
+            if verbose {
+                progress = (100.0_f64 * row as f64 / (rows - 1).max(1) as f64) as usize;
+                if progress != old_progress {
+                    println!("Tracing regions: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // create the output file
+        let integer_values = regions.iter().all(|r| r.0 == r.0.trunc() && r.0.abs() < 2147483647f64);
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::Polygon;
+        output.projection = input.configs.coordinate_ref_system_wkt.clone();
+        output.attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 10, 0));
+        if integer_values {
+            output.attributes.add_field(&AttributeField::new("VALUE", FieldDataType::Int, 11, 0));
+        } else {
+            output.attributes.add_field(&AttributeField::new("VALUE", FieldDataType::Real, 19, 6));
+        }
+        output.attributes.add_field(&AttributeField::new("AREA", FieldDataType::Real, 19, 3));
+
+        let num_regions = regions.len();
+        for (i, (value, rings, num_cells)) in regions.into_iter().enumerate() {
+            let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+            for ring in rings {
+                let mut ring = ring;
+                if tolerance > 0f64 {
+                    ring = simplify_ring(&ring, tolerance);
+                }
+                for _ in 0..smooth_iterations {
+                    ring = smooth_ring(&ring);
+                }
+                sfg.add_part(&ring);
+            }
+            output.add_record(sfg);
+            let value = if integer_values { FieldData::Int(value as i32) } else { FieldData::Real(value) };
+            output.attributes.add_record(vec![FieldData::Int(i as i32 + 1), value, FieldData::Real(num_cells as f64 * cell_area)]);
+
+            if verbose {
+                progress = (100.0_f64 * (i + 1) as f64 / num_regions as f64) as usize;
+                if progress != old_progress {
+                    println!("Creating polygons: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { println!("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{} polygons were created.", num_regions);
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+/// Traces the boundary of a region along the cell edges, returning closed rings in map
+/// coordinates. The boundary edges are directed such that the region is on the right,
+/// which orders the outer rings clockwise and the holes counter-clockwise, as required
+/// in a Shapefile.
+fn trace_region_boundary(input: &Raster, labels: &Array2D<i32>, label: i32, cells: &[(isize, isize)]) -> Vec<Vec<Point2D>> {
+    let columns = input.configs.columns as isize;
+    // directions are east, south, west and north, in grid terms; (d + 1) % 4 is a right turn
+    let d_x = [1, 0, -1, 0];
+    let d_y = [0, 1, 0, -1];
+    let vertex_id = |r: isize, c: isize| (r * (columns + 1) + c) as usize;
+
+    // the outgoing boundary edges at each cell corner, as (direction, used)
+    let mut edges: HashMap<usize, Vec<(usize, bool)>> = HashMap::new();
+    for &(r, c) in cells {
+        // for each side: (neighbour offset, start corner offset, direction)
+        let sides = [((-1, 0), (0, 0), 0), ((0, 1), (0, 1), 1), ((1, 0), (1, 1), 2), ((0, -1), (1, 0), 3)];
+        for &((dr, dc), (sr, sc), dir) in sides.iter() {
+            if labels.get_value(r + dr, c + dc) != label {
+                edges.entry(vertex_id(r + sr, c + sc)).or_insert(vec![]).push((dir, false));
+            }
+        }
+    }
+
+    let mut closed_rings = vec![];
+    let mut starts: Vec<usize> = edges.keys().cloned().collect();
+    starts.sort();
+    for start in starts {
+        loop {
+            // find an unused edge leaving the start vertex
+            let initial_dir = match edges[&start].iter().find(|e| !e.1) {
+                Some(e) => e.0,
+                None => break,
+            };
+            mark_edge_used(&mut edges, start, initial_dir);
+            let (mut r, mut c) = (start as isize / (columns + 1), start as isize % (columns + 1));
+            let mut dir = initial_dir;
+            let mut ring = vec![];
+            loop {
+                r += d_y[dir];
+                c += d_x[dir];
+                let id = vertex_id(r, c);
+                // prefer a right turn, then straight on, then a left turn, which keeps
+                // diagonally-touching cells in separate rings
+                let out = &edges[&id];
+                let next = [(dir + 1) % 4, dir, (dir + 3) % 4].iter().cloned().find(|d| {
+                    out.iter().any(|e| e.0 == *d && (!e.1 || (id == start && *d == initial_dir)))
+                });
+                let next = match next {
+                    Some(d) => d,
+                    None => break, // this shouldn't happen for a valid region
+                };
+                if next != dir {
+                    // only the corners are retained
+                    ring.push(id);
+                }
+                if id == start && next == initial_dir {
+                    break;
+                }
+                mark_edge_used(&mut edges, id, next);
+                dir = next;
+            }
+            // a ring passing twice through a vertex, where two cells outside of the region touch
+            // diagonally, is split into two rings
+            let mut path: Vec<usize> = vec![];
+            let mut position: HashMap<usize, usize> = HashMap::new();
+            for id in ring {
+                if let Some(&k) = position.get(&id) {
+                    let mut sub_ring: Vec<usize> = path.drain(k + 1..).collect();
+                    for v in &sub_ring {
+                        position.remove(v);
+                    }
+                    sub_ring.insert(0, id);
+                    closed_rings.push(sub_ring);
+                } else {
+                    position.insert(id, path.len());
+                    path.push(id);
+                }
+            }
+            closed_rings.push(path);
+        }
+    }
+
+    let mut rings = vec![];
+    for ring in closed_rings {
+        if ring.len() > 2 {
+            let mut points: Vec<Point2D> = ring.iter().map(|&id| {
+                let (r, c) = (id as isize / (columns + 1), id as isize % (columns + 1));
+                Point2D::new(input.configs.west + c as f64 * input.configs.resolution_x, input.configs.north - r as f64 * input.configs.resolution_y)
+            }).collect();
+            let first = points[0];
+            points.push(first);
+            rings.push(points);
+        }
+    }
+    rings
+}
+
+fn mark_edge_used(edges: &mut HashMap<usize, Vec<(usize, bool)>>, vertex: usize, dir: usize) {
+    if let Some(e) = edges.get_mut(&vertex).unwrap().iter_mut().find(|e| e.0 == dir && !e.1) {
+        e.1 = true;
+    }
+}
+
+/// Simplifies a closed ring using the Douglas-Peucker algorithm. The ring is split at its
+/// first point and the point farthest from it, and the two halves simplified separately.
+fn simplify_ring(ring: &[Point2D], tolerance: f64) -> Vec<Point2D> {
+    let n = ring.len();
+    if n < 5 {
+        return ring.to_vec();
+    }
+    let mut far = 0;
+    let mut max_dist = 0f64;
+    for i in 1..n - 1 {
+        let d = ring[0].distance(&ring[i]);
+        if d > max_dist {
+            max_dist = d;
+            far = i;
+        }
+    }
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[far] = true;
+    keep[n - 1] = true;
+    douglas_peucker(ring, 0, far, tolerance, &mut keep);
+    douglas_peucker(ring, far, n - 1, tolerance, &mut keep);
+    let simplified: Vec<Point2D> = (0..n).filter(|i| keep[*i]).map(|i| ring[i]).collect();
+    if simplified.len() < 4 {
+        // too few points remain to form a polygon
+        return ring.to_vec();
+    }
+    simplified
+}
+
+fn douglas_peucker(points: &[Point2D], first: usize, last: usize, tolerance: f64, keep: &mut Vec<bool>) {
+    if last <= first + 1 {
+        return;
+    }
+    let (a, b) = (points[first], points[last]);
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    let mut max_dist = 0f64;
+    let mut index = first;
+    for i in first + 1..last {
+        let p = points[i];
+        let d = if len > 0f64 {
+            (dy * p.x - dx * p.y + b.x * a.y - b.y * a.x).abs() / len
+        } else {
+            p.distance(&a)
+        };
+        if d > max_dist {
+            max_dist = d;
+            index = i;
+        }
+    }
+    if max_dist > tolerance {
+        keep[index] = true;
+        douglas_peucker(points, first, index, tolerance, keep);
+        douglas_peucker(points, index, last, tolerance, keep);
+    }
+}
+
+/// Smooths a closed ring with one iteration of Chaikin's corner-cutting algorithm.
+fn smooth_ring(ring: &[Point2D]) -> Vec<Point2D> {
+    let n = ring.len();
+    if n < 4 {
+        return ring.to_vec();
+    }
+    let mut smoothed = Vec::with_capacity(2 * n);
+    for i in 0..n - 1 {
+        let (p, q) = (ring[i], ring[i + 1]);
+        smoothed.push(Point2D::new(0.75 * p.x + 0.25 * q.x, 0.75 * p.y + 0.25 * q.y));
+        smoothed.push(Point2D::new(0.25 * p.x + 0.75 * q.x, 0.25 * p.y + 0.75 * q.y));
+    }
+    let first = smoothed[0];
+    smoothed.push(first);
+    smoothed
+}
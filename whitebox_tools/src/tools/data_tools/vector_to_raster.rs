@@ -0,0 +1,368 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 6, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use tools::*;
+use vector::*;
+
+/// Converts the points, lines or polygons of a vector file (Shapefile or GeoJSON) to a raster.
+/// The output grid is either that of an existing base raster, in which case it is created
+/// using `Raster::initialize_using_file`, or it is based on the extent of the vector features
+/// and a specified cell size. Each cell overlapping a feature is assigned the value of a
+/// numeric attribute field or, by default, the feature ID (FID, starting at 1). Where features
+/// overlap, later features overwrite earlier ones.
+///
+/// By default, a polygon is assigned to the cells with centres that fall within it, and lines
+/// are traced from cell to cell as 8-connected (diagonally linked) paths without gaps. With the
+/// `--all_touched` flag, every cell that is touched by a polygon or a line is assigned, which
+/// for lines gives 4-connected paths. Cells not overlapping any feature are assigned nodata,
+/// or zero where `--zero_background` is specified.
+pub struct VectorToRaster {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl VectorToRaster {
+    pub fn new() -> VectorToRaster {
+        // public constructor
+        let name = "VectorToRaster".to_string();
+        let toolbox = "Data Tools".to_string();
+        let description = "Converts vector points, lines or polygons to a raster grid.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Vector File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input vector file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Field Name".to_owned(),
+            flags: vec!["--field".to_owned()],
+            description: "Numeric attribute field containing the values to assign to cells; 'FID' for the feature ID.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("FID".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input Base File".to_owned(),
+            flags: vec!["--base".to_owned()],
+            description: "Optional input base raster file; defines the output grid.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Cell Size".to_owned(),
+            flags: vec!["--cell_size".to_owned()],
+            description: "Output raster cell size; required if a base raster is not specified.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Assign all touched cells?".to_owned(),
+            flags: vec!["--all_touched".to_owned()],
+            description: "Optional boolean flag indicating that all cells touched by a line or polygon are assigned, rather than cells with centres in polygons.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Background value is zero?".to_owned(),
+            flags: vec!["--zero_background".to_owned()],
+            description: "Optional boolean flag indicating that cells not overlapping features are assigned zero rather than nodata.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=lakes.shp --field=ELEV -o=lakes.tif --base=dem.tif
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=streams.geojson -o=streams.tif --cell_size=10.0 --all_touched --zero_background", short_exe, name).replace("*", &sep);
+
+        VectorToRaster {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for VectorToRaster {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file = String::new();
+        let mut field_name = String::from("FID");
+        let mut output_file = String::new();
+        let mut base_file = String::new();
+        let mut cell_size = 0f64;
+        let mut all_touched = false;
+        let mut zero_background = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-field" {
+                field_name = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-base" {
+                base_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-cell_size" {
+                cell_size = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-all_touched" {
+                all_touched = true;
+            } else if flag_val == "-zero_background" {
+                zero_background = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !base_file.is_empty() && !base_file.contains(sep) {
+            base_file = format!("{}{}", working_directory, base_file);
+        }
+        if base_file.is_empty() && cell_size <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Either a base raster or a positive cell size must be specified."));
+        }
+
+        if verbose { println!("Reading vector data..."); }
+        let vector_data = Shapefile::new(&input_file, "r")?;
+
+        // the value burned for each feature; None for features with no value
+        let field = vector_data.attributes.get_field_or_fid(&field_name, true)?;
+        let integer_values = field.map_or(true, |f| vector_data.attributes.fields[f].get_data_type() != FieldDataType::Real);
+        let values: Vec<Option<f64>> = (0..vector_data.num_records).map(|i| match field {
+            Some(f) => vector_data.attributes.get_value(i, f).as_f64(),
+            None => Some((i + 1) as f64),
+        }).collect();
+
+        let start = time::now();
+
+        let mut output = if !base_file.is_empty() {
+            let base = Raster::new(&base_file, "r")?;
+            Raster::initialize_using_file(&output_file, &base)
+        } else {
+            let x_min = vector_data.header.x_min;
+            let y_max = vector_data.header.y_max;
+            // features lying on the eastern and southern edges fall within the last column and row
+            let columns = ((vector_data.header.x_max - x_min) / cell_size).floor() as usize + 1;
+            let rows = ((y_max - vector_data.header.y_min) / cell_size).floor() as usize + 1;
+            let mut configs = RasterConfigs { ..Default::default() };
+            configs.rows = rows;
+            configs.columns = columns;
+            configs.north = y_max;
+            configs.south = y_max - rows as f64 * cell_size;
+            configs.east = x_min + columns as f64 * cell_size;
+            configs.west = x_min;
+            configs.resolution_x = cell_size;
+            configs.resolution_y = cell_size;
+            configs.nodata = -32768f64;
+            configs.photometric_interp = PhotometricInterpretation::Continuous;
+            configs.coordinate_ref_system_wkt = vector_data.projection.clone();
+            Raster::initialize_using_config(&output_file, &configs)
+        };
+        let nodata = output.configs.nodata;
+        output.configs.data_type = if integer_values { DataType::I32 } else { DataType::F32 };
+        output.reinitialize_values(if zero_background { 0f64 } else { nodata });
+
+        let grid = Grid::new(&output.configs);
+
+        let num_records = vector_data.num_records;
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for record_num in 0..num_records {
+            let record = vector_data.get_record(record_num);
+            let value = match values[record_num] {
+                Some(v) => v,
+                None => continue,
+            };
+            match record.shape_type.base_shape_type() {
+                ShapeType::Point | ShapeType::MultiPoint => {
+                    for p in &record.points {
+                        output.set_value(grid.get_row(p.y).floor() as isize, grid.get_column(p.x).floor() as isize, value);
+                    }
+                },
+                ShapeType::PolyLine => {
+                    for part in 0..record.num_parts as usize {
+                        let (start, end) = record.part_range(part);
+                        for i in start + 1..end {
+                            trace_segment(&grid, &record.points[i - 1], &record.points[i], all_touched, &mut |row, col| {
+                                output.set_value(row, col, value);
+                            });
+                        }
+                        if end - start == 1 {
+                            let p = record.points[start];
+                            output.set_value(grid.get_row(p.y).floor() as isize, grid.get_column(p.x).floor() as isize, value);
+                        }
+                    }
+                },
+                ShapeType::Polygon => {
+                    fill_polygon(&grid, record, &mut |row, col| {
+                        output.set_value(row, col, value);
+                    });
+                    if all_touched {
+                        // the cells touched by the polygon's boundary
+                        for part in 0..record.num_parts as usize {
+                            let (start, end) = record.part_range(part);
+                            for i in start + 1..end {
+                                trace_segment(&grid, &record.points[i - 1], &record.points[i], true, &mut |row, col| {
+                                    output.set_value(row, col, value);
+                                });
+                            }
+                        }
+                    }
+                },
+                _ => {},
+            }
+            if verbose {
+                progress = (100.0_f64 * (record_num + 1) as f64 / num_records as f64) as usize;
+                if progress != old_progress {
+                    println!("Rasterizing features: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+        output.add_metadata_entry(format!("Input file: {}", input_file));
+        output.add_metadata_entry(format!("Field: {}", field_name));
+        if !base_file.is_empty() {
+            output.add_metadata_entry(format!("Base raster file: {}", base_file));
+        }
+        output.add_metadata_entry(format!("All touched: {}", all_touched));
+        output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+        if verbose { println!("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { println!("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
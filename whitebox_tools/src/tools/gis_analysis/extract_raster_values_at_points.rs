@@ -0,0 +1,415 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 18, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path;
+use std::path::Path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use tools::*;
+use vector::*;
+
+/// Extracts the values of one or more rasters at the locations of a set of points, e.g. to
+/// sample elevation, slope and wetness index at field plots. The values are either taken from
+/// the cell containing each point (`--interp=nearest`) or bilinearly interpolated from the four
+/// cell centres surrounding it (`--interp=bilinear`). Where some of those four cells are nodata,
+/// or the point lies within half a cell of the raster's edge, the bilinear weights are
+/// re-normalized over the valid cells. Points outside of a raster, or in nodata cells, have null
+/// values. The rasters need not share the same grid.
+///
+/// Each raster's values are named after its file name, e.g. SLOPE for slope.tif, and are
+/// appended as new attribute fields to a copy of the points vector (`--output`) and/or written
+/// to a CSV table (`--out_table`) with the coordinates of the points, in which each point is
+/// identified by the value of an attribute field (`--field`), or by default its feature ID (FID).
+pub struct ExtractRasterValuesAtPoints {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ExtractRasterValuesAtPoints {
+    pub fn new() -> ExtractRasterValuesAtPoints {
+        // public constructor
+        let name = "ExtractRasterValuesAtPoints".to_string();
+        let toolbox = "GIS Analysis".to_string();
+        let description = "Extracts the values of one or more rasters at the locations of a set of points.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Raster Files".to_owned(),
+            flags: vec!["-i".to_owned(), "--inputs".to_owned()],
+            description: "Input raster files.".to_owned(),
+            parameter_type: ParameterType::FileList(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input Points File".to_owned(),
+            flags: vec!["--points".to_owned()],
+            description: "Input point vector file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Vector File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output point vector file, a copy of the points with the raster values as attributes.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output CSV Table File".to_owned(),
+            flags: vec!["--out_table".to_owned()],
+            description: "Output CSV table file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Text),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Point ID Field".to_owned(),
+            flags: vec!["--field".to_owned()],
+            description: "Attribute field identifying the points in the CSV table; 'FID' for the feature ID.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("FID".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Interpolation Method".to_owned(),
+            flags: vec!["--interp".to_owned()],
+            description: "Interpolation method; one of 'nearest' and 'bilinear'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["nearest".to_owned(), "bilinear".to_owned()]),
+            default_value: Some("nearest".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i='dem.tif;slope.tif;twi.tif' --points=plots.shp -o=plots_sampled.shp --interp=bilinear
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=dem.tif --points=plots.geojson --out_table=plot_elevations.csv --field=PLOT_ID", short_exe, name).replace("*", &sep);
+
+        ExtractRasterValuesAtPoints {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ExtractRasterValuesAtPoints {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_files = String::new();
+        let mut points_file = String::new();
+        let mut output_file = String::new();
+        let mut table_file = String::new();
+        let mut field_name = String::from("FID");
+        let mut bilinear = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-inputs" {
+                input_files = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-points" {
+                points_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-out_table" {
+                table_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-field" {
+                field_name = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-interp" {
+                let method = if keyval {
+                    vec[1].to_string().to_lowercase()
+                } else {
+                    args[i + 1].to_string().to_lowercase()
+                };
+                bilinear = method.contains("bilinear");
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !points_file.contains(sep) {
+            points_file = format!("{}{}", working_directory, points_file);
+        }
+        if !output_file.is_empty() && !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !table_file.is_empty() && !table_file.contains(sep) {
+            table_file = format!("{}{}", working_directory, table_file);
+        }
+        if output_file.is_empty() && table_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "At least one of --output or --out_table must be specified."));
+        }
+
+        let mut cmd = input_files.split(";");
+        let mut files = cmd.collect::<Vec<&str>>();
+        if files.len() == 1 {
+            cmd = input_files.split(",");
+            files = cmd.collect::<Vec<&str>>();
+        }
+        let files: Vec<String> = files.iter().filter(|f| !f.trim().is_empty()).map(|f| {
+            let f = f.trim().to_string();
+            if !f.contains(sep) { format!("{}{}", working_directory, f) } else { f }
+        }).collect();
+        if files.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "At least one input raster file must be specified."));
+        }
+
+        if verbose { println!("Reading points data...") };
+        let points = Shapefile::new(&points_file, "r")?;
+        if points.header.shape_type.base_shape_type() != ShapeType::Point {
+            return Err(Error::new(ErrorKind::InvalidInput, "The input points vector must contain point features."));
+        }
+        let num_points = points.num_records;
+
+        let id_field = if table_file.is_empty() {
+            None
+        } else {
+            points.attributes.get_field_or_fid(&field_name, false)?
+        };
+
+        // the name of each raster's field, which must be unique among the output fields
+        let mut value_names: Vec<String> = vec![];
+        for file in &files {
+            let stem = Path::new(file).file_stem().and_then(|s| s.to_str()).unwrap_or("VALUE");
+            let mut base: String = stem.to_uppercase().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+            if base.is_empty() {
+                base = "VALUE".to_string();
+            }
+            let name = points.attributes.get_unique_field_name(&base, &value_names);
+            value_names.push(name);
+        }
+
+        let start = time::now();
+
+        let mut values: Vec<Vec<Option<f64>>> = Vec::with_capacity(files.len()); // [raster][point]
+        for (i, file) in files.iter().enumerate() {
+            if verbose { println!("Reading data ({} of {})...", i + 1, files.len()) };
+            let input = Raster::new(file, "r")?;
+            let rows = input.configs.rows as isize;
+            let columns = input.configs.columns as isize;
+            let nodata = input.configs.nodata;
+            let get_valid_value = |row: isize, col: isize| -> Option<f64> {
+                if row < 0 || row >= rows || col < 0 || col >= columns {
+                    return None;
+                }
+                let z = input.get_value(row, col);
+                if z != nodata { Some(z) } else { None }
+            };
+
+            let mut raster_values = Vec::with_capacity(num_points);
+            for record_num in 0..num_points {
+                let record = points.get_record(record_num);
+                if record.shape_type == ShapeType::Null || record.num_points == 0 {
+                    raster_values.push(None);
+                    continue;
+                }
+                let p = record.points[0];
+                let (row, col) = (input.get_row_from_y(p.y), input.get_column_from_x(p.x));
+                if row < 0 || row >= rows || col < 0 || col >= columns {
+                    // the point is outside of the raster
+                    raster_values.push(None);
+                    continue;
+                }
+                if !bilinear || get_valid_value(row, col).is_none() {
+                    raster_values.push(get_valid_value(row, col));
+                    continue;
+                }
+
+                // the position of the point relative to the centres of the surrounding cells
+                let col_f = (p.x - input.configs.west) / input.configs.resolution_x - 0.5;
+                let row_f = (input.configs.north - p.y) / input.configs.resolution_y - 0.5;
+                let (c0, r0) = (col_f.floor() as isize, row_f.floor() as isize);
+                let (dx, dy) = (col_f - c0 as f64, row_f - r0 as f64);
+                let neighbours = [
+                    (r0, c0, (1f64 - dx) * (1f64 - dy)),
+                    (r0, c0 + 1, dx * (1f64 - dy)),
+                    (r0 + 1, c0, (1f64 - dx) * dy),
+                    (r0 + 1, c0 + 1, dx * dy),
+                ];
+                let mut sum = 0f64;
+                let mut sum_weights = 0f64;
+                for &(r, c, w) in neighbours.iter() {
+                    if let Some(z) = get_valid_value(r, c) {
+                        sum += w * z;
+                        sum_weights += w;
+                    }
+                }
+                raster_values.push(if sum_weights > 0f64 { Some(sum / sum_weights) } else { None });
+            }
+            values.push(raster_values);
+        }
+
+        if !output_file.is_empty() {
+            let mut output = Shapefile::initialize_using_file(&output_file, &points, points.header.shape_type, true)?;
+            for name in &value_names {
+                output.attributes.add_field(&AttributeField::new(name, FieldDataType::Real, 19, 6));
+            }
+            for record_num in 0..num_points {
+                output.add_record(points.get_record(record_num).clone());
+                let mut rec = points.attributes.get_record(record_num).clone();
+                for raster_values in &values {
+                    rec.push(match raster_values[record_num] {
+                        Some(z) => FieldData::Real(z),
+                        None => FieldData::Null,
+                    });
+                }
+                output.attributes.add_record(rec);
+            }
+
+            if verbose { println!("Saving data...") };
+            let _ = match output.write() {
+                Ok(_) => if verbose { println!("Output file written") },
+                Err(e) => return Err(e),
+            };
+        }
+
+        if !table_file.is_empty() {
+            let f = File::create(&table_file)?;
+            let mut writer = BufWriter::new(f);
+            let id_name = match id_field {
+                Some(f) => points.attributes.fields[f].name.clone(),
+                None => "FID".to_string(),
+            };
+            let mut header = format!("{},X,Y", csv_value(&id_name));
+            for name in &value_names {
+                header.push_str(&format!(",{}", name));
+            }
+            writer.write_all(format!("{}\n", header).as_bytes())?;
+            for record_num in 0..num_points {
+                let id = match id_field {
+                    Some(f) => match *points.attributes.get_value(record_num, f) {
+                        FieldData::Null => String::new(),
+                        ref v => csv_value(&format!("{}", v)),
+                    },
+                    None => format!("{}", record_num + 1),
+                };
+                let record = points.get_record(record_num);
+                let mut line = if record.num_points > 0 {
+                    format!("{},{},{}", id, record.points[0].x, record.points[0].y)
+                } else {
+                    format!("{},,", id)
+                };
+                for raster_values in &values {
+                    match raster_values[record_num] {
+                        Some(z) => line.push_str(&format!(",{}", z)),
+                        None => line.push_str(","),
+                    }
+                }
+                writer.write_all(format!("{}\n", line).as_bytes())?;
+            }
+            writer.flush()?;
+            if verbose { println!("Table written") };
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ mod create_plane;
 mod edge_proportion;
 mod euclidean_allocation;
 mod euclidean_distance;
+mod extract_raster_values_at_points;
 mod find_patch_edge_cells;
 mod highest_pos;
 mod lowest_pos;
@@ -42,6 +43,7 @@ pub use self::create_plane::CreatePlane;
 pub use self::edge_proportion::EdgeProportion;
 pub use self::euclidean_allocation::EuclideanAllocation;
 pub use self::euclidean_distance::EuclideanDistance;
+pub use self::extract_raster_values_at_points::ExtractRasterValuesAtPoints;
 pub use self::find_patch_edge_cells::FindPatchOrClassEdgeCells;
 pub use self::highest_pos::HighestPosition;
 pub use self::lowest_pos::LowestPosition;
@@ -28,6 +28,7 @@ mod isobasins;
 mod jenson_snap_pour_points;
 mod max_upslope_flowpath;
 mod num_inflowing_neighbours;
+mod pour_points;
 mod rho8_pointer;
 mod sink;
 mod snap_pour_points;
@@ -0,0 +1,86 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 22, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::f64;
+use std::io::{Error, ErrorKind};
+use raster::Raster;
+use structures::Point2D;
+use vector::*;
+
+/// Snaps the points in a pour points vector and writes them to a vector output, shared by the
+/// SnapPourPoints and JensonSnapPourPoints tools. Each point is moved to the centre of the cell
+/// returned by `find_snap_cell` for the (row, column) of the grid cell containing the point;
+/// points outside of the grid aren't moved. The output has the attributes of the input, plus
+/// the distance each point moved (SNAP_DIST, with a numbered suffix where the input already
+/// has a field of that name).
+pub fn snap_vector_pour_points<F>(pourpts_file: &str, output_file: &str, grid: &Raster, find_snap_cell: F, verbose: bool) -> Result<(), Error>
+    where F: Fn(isize, isize) -> (isize, isize) {
+    if !is_vector_file(output_file) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                            "The output file must be a vector (Shapefile or GeoJSON) when the pour points are a vector."));
+    }
+    let pourpts = Shapefile::new(pourpts_file, "r")?;
+    if pourpts.header.shape_type.base_shape_type() != ShapeType::Point {
+        return Err(Error::new(ErrorKind::InvalidInput, "The input pour points vector must contain point features."));
+    }
+
+    let start = time::now();
+
+    let rows = grid.configs.rows as isize;
+    let columns = grid.configs.columns as isize;
+
+    let mut output = Shapefile::initialize_using_file(output_file, &pourpts, pourpts.header.shape_type, true)?;
+    // a unique name, in case the points have already been snapped
+    let snap_dist_name = pourpts.attributes.get_unique_field_name("SNAP_DIST", &[]);
+    output.attributes.add_field(&AttributeField::new(&snap_dist_name, FieldDataType::Real, 19, 6));
+    for i in 0..pourpts.num_records {
+        let record = pourpts.get_record(i);
+        let mut rec = pourpts.attributes.get_record(i).clone();
+        if record.shape_type == ShapeType::Null || record.num_points == 0 {
+            output.add_record(record.clone());
+            rec.push(FieldData::Null);
+            output.attributes.add_record(rec);
+            continue;
+        }
+        let p = record.points[0];
+        let row = grid.get_row_from_y(p.y);
+        let col = grid.get_column_from_x(p.x);
+        let snapped = if row >= 0 && row < rows && col >= 0 && col < columns {
+            let (yn, xn) = find_snap_cell(row, col);
+            Point2D::new(grid.get_x_from_column_centre(xn), grid.get_y_from_row_centre(yn))
+        } else {
+            p // points outside of the grid aren't moved
+        };
+        let mut sfg = ShapefileGeometry::new(record.shape_type);
+        if record.shape_type.has_m() {
+            let z = record.z_array.get(0).cloned().unwrap_or(0f64);
+            let m = record.m_array.get(0).cloned().unwrap_or(f64::NAN);
+            sfg.add_point_zm(snapped, z, m);
+        } else {
+            sfg.add_point(snapped);
+        }
+        output.add_record(sfg);
+        rec.push(FieldData::Real(((snapped.x - p.x) * (snapped.x - p.x) + (snapped.y - p.y) * (snapped.y - p.y)).sqrt()));
+        output.attributes.add_record(rec);
+    }
+
+    let end = time::now();
+    let elapsed_time = end - start;
+
+    if verbose { println!("Saving data...") };
+    let _ = match output.write() {
+        Ok(_) => if verbose { println!("Output file written") },
+        Err(e) => return Err(e),
+    };
+
+    println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+
+    Ok(())
+}
@@ -2,10 +2,8 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 27, 2017
-Last Modified: Dec. 14, 2017
+Last Modified: February 22, 2018
 License: MIT
-
-NOTES: This tool should be updated to take vector pour points as inputs/outputs when vector support has been added.
 */
 extern crate time;
 
@@ -15,7 +13,14 @@ use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
 use tools::*;
+use super::pour_points::snap_vector_pour_points;
+use vector::*;
 
+/// Moves outlet points to the cell with the highest flow accumulation within a search window
+/// around each point, sized by the maximum snap distance. The pour points may be either a raster,
+/// in which case the output is a raster of the snapped outlet IDs, or a point vector (Shapefile or
+/// GeoJSON). Snapped vector points are placed at the centre of their snapped cells and written to
+/// a vector output with the original attributes, plus the distance each point moved (SNAP_DIST).
 pub struct SnapPourPoints {
     name: String,
     description: String,
@@ -34,8 +39,8 @@ impl SnapPourPoints {
         parameters.push(ToolParameter{
             name: "Input Pour Points (Outlet) File".to_owned(), 
             flags: vec!["--pour_pts".to_owned()], 
-            description: "Input raster pour points (outlet) file.".to_owned(),
-            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            description: "Input raster or vector pour points (outlet) file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Any),
             default_value: None,
             optional: false
         });
@@ -52,8 +57,8 @@ impl SnapPourPoints {
         parameters.push(ToolParameter{
             name: "Output File".to_owned(), 
             flags: vec!["-o".to_owned(), "--output".to_owned()], 
-            description: "Output raster file.".to_owned(),
-            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            description: "Output file; a raster for raster pour points or a vector for vector pour points.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Any),
             default_value: None,
             optional: false
         });
@@ -74,7 +79,8 @@ impl SnapPourPoints {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --pour_pts='pour_pts.dep' --flow_accum='d8accum.dep' -o='output.dep' --snap_dist=15.0", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --pour_pts='pour_pts.dep' --flow_accum='d8accum.dep' -o='output.dep' --snap_dist=15.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --pour_pts='gauges.shp' --flow_accum='d8accum.dep' -o='snapped_gauges.shp' --snap_dist=15.0", short_exe, name).replace("*", &sep);
     
         SnapPourPoints { 
             name: name, 
@@ -183,15 +189,40 @@ impl WhiteboxTool for SnapPourPoints {
 
         if verbose { println!("Reading data...") };
 
-        let pourpts = Raster::new(&pourpts_file, "r")?;
         let flow_accum = Raster::new(&flow_accum_file, "r")?;
+        let fa_nodata = flow_accum.configs.nodata;
+        let snap_dist_int: isize = ((snap_dist / flow_accum.configs.resolution_x) / 2.0).floor() as isize;
+
+        // finds the cell with the highest flow accumulation within the snap window of a cell
+        let find_snap_cell = |row: isize, col: isize| -> (isize, isize) {
+            let mut max_accum = 0.0;
+            let mut xn = col;
+            let mut yn = row;
+            let mut zn: f64;
+            for x in (col - snap_dist_int)..(col + snap_dist_int + 1) {
+                for y in (row - snap_dist_int)..(row + snap_dist_int + 1) {
+                    zn = flow_accum[(y, x)];
+                    if zn > max_accum && zn != fa_nodata {
+                        max_accum = zn;
+                        xn = x;
+                        yn = y;
+                    }
+                }
+            }
+            (yn, xn)
+        };
+
+        if is_vector_file(&pourpts_file) {
+            return snap_vector_pour_points(&pourpts_file, &output_file, &flow_accum, find_snap_cell, verbose);
+        }
+
+        let pourpts = Raster::new(&pourpts_file, "r")?;
         
         let start = time::now();
 
         let rows = pourpts.configs.rows as isize;
         let columns = pourpts.configs.columns as isize;
         let nodata = pourpts.configs.nodata;
-        let fa_nodata = flow_accum.configs.nodata;
         
         // make sure the input files have the same size
         if pourpts.configs.rows != flow_accum.configs.rows || pourpts.configs.columns != flow_accum.configs.columns {
@@ -199,32 +230,14 @@ impl WhiteboxTool for SnapPourPoints {
                                 "The input files must have the same number of rows and columns and spatial extent."));
         }
 
-        let snap_dist_int: isize = ((snap_dist / pourpts.configs.resolution_x) / 2.0).floor() as isize;
-
         let mut output = Raster::initialize_using_file(&output_file, &pourpts);
         
         let mut outlet_id: f64;
-        let mut max_accum: f64;
-        let mut zn: f64;
-        let mut xn: isize;
-        let mut yn: isize;
         for row in 0..rows {
             for col in 0..columns {
                 outlet_id = pourpts[(row, col)];
                 if outlet_id > 0.0 && outlet_id != nodata {
-                    max_accum = 0.0;
-                    xn = col;
-                    yn = row;
-                    for x in (col - snap_dist_int)..(col + snap_dist_int + 1) {
-                        for y in (row - snap_dist_int)..(row + snap_dist_int + 1) {
-                            zn = flow_accum[(y, x)];
-                            if zn > max_accum && zn != fa_nodata {
-                                max_accum = zn;
-                                xn = x;
-                                yn = y;
-                            }
-                        }
-                    }
+                    let (yn, xn) = find_snap_cell(row, col);
                     output[(yn, xn)] = outlet_id;
                 }
             }
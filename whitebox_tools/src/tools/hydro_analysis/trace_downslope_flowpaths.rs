@@ -2,20 +2,35 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 4, 2017
-Last Modified: Dec. 14, 2017
+Last Modified: February 22, 2018
 License: MIT
-
-NOTES: Add support for vector seed points.
 */
 extern crate time;
 
+use std::collections::HashSet;
 use std::env;
 use std::path;
 use std::f64;
 use raster::*;
 use std::io::{Error, ErrorKind};
+use structures::Point2D;
 use tools::*;
+use vector::*;
 
+/// Traces the downslope flowpaths from one or more seed points by following a D8 (`--d8_pntr`)
+/// or D-infinity (`--dinf_pntr`) flow pointer until a cell without a downslope neighbour, or the
+/// edge of the data, is reached. D-infinity flow is routed to the neighbour receiving the greater
+/// proportion of the flow, as in the threshold mode of DInfFlowAccumulation. The seed points may
+/// be either a raster, in which every cell with a positive value is a seed, or a point vector.
+///
+/// Where the output is a raster, each cell's value is the number of flowpaths that pass through
+/// it. Where the output is a vector (Shapefile or GeoJSON), each seed point results in a polyline
+/// that connects the centres of the cells along its flowpath, with attributes for its LENGTH in
+/// map units and the number of cells (NUM_CELLS) along it. The polylines copy the attributes of
+/// vector seed points, or are identified by an FID where the seeds are a raster. If a DEM is
+/// specified, the polylines are 3D, with the elevations of the cells as the z values and the
+/// distance along the flowpath as the measures, and the average SLOPE of the flowpath is also
+/// output, as a percent.
 pub struct TraceDownslopeFlowpaths {
     name: String,
     description: String,
@@ -34,8 +49,8 @@ impl TraceDownslopeFlowpaths {
         parameters.push(ToolParameter{
             name: "Input Seed Points File".to_owned(), 
             flags: vec!["--seed_pts".to_owned()], 
-            description: "Input raster seed points file.".to_owned(),
-            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            description: "Input seed points file (raster or vector).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Any),
             default_value: None,
             optional: false
         });
@@ -46,14 +61,32 @@ impl TraceDownslopeFlowpaths {
             description: "Input D8 pointer raster file.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
             default_value: None,
-            optional: false
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input D-infinity Pointer File".to_owned(), 
+            flags: vec!["--dinf_pntr".to_owned()], 
+            description: "Input D-infinity pointer raster file, used instead of a D8 pointer.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input DEM File".to_owned(), 
+            flags: vec!["--dem".to_owned()], 
+            description: "Input raster DEM file, used for the elevations of vector flowpaths.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
         });
 
         parameters.push(ToolParameter{
             name: "Output File".to_owned(), 
             flags: vec!["-o".to_owned(), "--output".to_owned()], 
-            description: "Output raster file.".to_owned(),
-            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            description: "Output raster or vector file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Any),
             default_value: None,
             optional: false
         });
@@ -83,7 +116,8 @@ impl TraceDownslopeFlowpaths {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --seed_pts=seeds.dep --flow_dir=flow_directions.dep --output=flow_paths.dep", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --seed_pts=seeds.dep --flow_dir=flow_directions.dep --output=flow_paths.dep
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --seed_pts=seeds.shp --dinf_pntr=dinf.dep --dem=dem.dep --output=flow_paths.shp", short_exe, name).replace("*", &sep);
     
         TraceDownslopeFlowpaths { 
             name: name, 
@@ -126,6 +160,8 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
     fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
         let mut seed_file = String::new();
         let mut flowdir_file = String::new();
+        let mut dinf_file = String::new();
+        let mut dem_file = String::new();
         let mut output_file = String::new();
         let mut esri_style = false;
         let mut background_val = f64::NEG_INFINITY;
@@ -155,6 +191,18 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
                 } else {
                     flowdir_file = args[i+1].to_string();
                 }
+            } else if vec[0].to_lowercase() == "-dinf_pntr" || vec[0].to_lowercase() == "--dinf_pntr" {
+                if keyval {
+                    dinf_file = vec[1].to_string();
+                } else {
+                    dinf_file = args[i+1].to_string();
+                }
+            } else if vec[0].to_lowercase() == "-dem" || vec[0].to_lowercase() == "--dem" {
+                if keyval {
+                    dem_file = vec[1].to_string();
+                } else {
+                    dem_file = args[i+1].to_string();
+                }
             } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
                 if keyval {
                     output_file = vec[1].to_string();
@@ -179,38 +227,86 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
         let mut progress: usize;
         let mut old_progress: usize = 1;
 
+        if flowdir_file.is_empty() == dinf_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "Either a D8 pointer (--d8_pntr) or a D-infinity pointer (--dinf_pntr) file must be specified."));
+        }
+        let dinf = !dinf_file.is_empty();
+        if dinf {
+            flowdir_file = dinf_file;
+        }
+
         if !seed_file.contains(&sep) {
             seed_file = format!("{}{}", working_directory, seed_file);
         }
         if !flowdir_file.contains(&sep) {
             flowdir_file = format!("{}{}", working_directory, flowdir_file);
         }
+        if !dem_file.is_empty() && !dem_file.contains(&sep) {
+            dem_file = format!("{}{}", working_directory, dem_file);
+        }
         if !output_file.contains(&sep) {
             output_file = format!("{}{}", working_directory, output_file);
         }
-        
-        if verbose { println!("Reading destination data...") };
-        let seeds = Raster::new(&seed_file, "r")?;
+        let vector_output = is_vector_file(&output_file);
 
         if verbose { println!("Reading backlink data...") };
         let flowdir = Raster::new(&flowdir_file, "r")?;
+        let rows = flowdir.configs.rows as isize;
+        let columns = flowdir.configs.columns as isize;
+        let nodata = flowdir.configs.nodata;
 
-        // make sure the input files have the same size
-        if seeds.configs.rows != flowdir.configs.rows || seeds.configs.columns != flowdir.configs.columns {
-            return Err(Error::new(ErrorKind::InvalidInput,
-                                "The input files must have the same number of rows and columns and spatial extent."));
+        // each seed is a starting cell and, for vector seeds, the index of its record
+        if verbose { println!("Reading destination data...") };
+        let mut seed_cells: Vec<(isize, isize, usize)> = vec![];
+        let mut seed_vector: Option<Shapefile> = None;
+        if is_vector_file(&seed_file) {
+            let seeds = Shapefile::new(&seed_file, "r")?;
+            if seeds.header.shape_type.base_shape_type() != ShapeType::Point {
+                return Err(Error::new(ErrorKind::InvalidInput, "The input seed points vector must contain point features."));
+            }
+            for i in 0..seeds.num_records {
+                let record = seeds.get_record(i);
+                if record.shape_type == ShapeType::Null || record.num_points == 0 {
+                    continue;
+                }
+                let row = flowdir.get_row_from_y(record.points[0].y);
+                let col = flowdir.get_column_from_x(record.points[0].x);
+                if row >= 0 && row < rows && col >= 0 && col < columns && flowdir[(row, col)] != nodata {
+                    seed_cells.push((row, col, i));
+                }
+            }
+            seed_vector = Some(seeds);
+        } else {
+            let seeds = Raster::new(&seed_file, "r")?;
+
+            // make sure the input files have the same size
+            if seeds.configs.rows != flowdir.configs.rows || seeds.configs.columns != flowdir.configs.columns {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                    "The input files must have the same number of rows and columns and spatial extent."));
+            }
+            for row in 0..rows {
+                for col in 0..columns {
+                    if seeds[(row, col)] > 0.0 && seeds[(row, col)] != seeds.configs.nodata && flowdir[(row, col)] != nodata {
+                        seed_cells.push((row, col, seed_cells.len()));
+                    }
+                }
+            }
         }
 
+        let dem = if !dem_file.is_empty() {
+            if verbose { println!("Reading DEM data...") };
+            let dem = Raster::new(&dem_file, "r")?;
+            if dem.configs.rows != flowdir.configs.rows || dem.configs.columns != flowdir.configs.columns {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                    "The input files must have the same number of rows and columns and spatial extent."));
+            }
+            Some(dem)
+        } else {
+            None
+        };
+
         let start = time::now();
-        let rows = seeds.configs.rows as isize;
-        let columns = seeds.configs.columns as isize;
-        let nodata = flowdir.configs.nodata;
-        if background_val == f64::NEG_INFINITY {
-            background_val = nodata;
-        }
-        
-        let mut output = Raster::initialize_using_file(&output_file, &seeds);
-        output.reinitialize_values(background_val);
         
         let dx = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
         let dy = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
@@ -238,37 +334,180 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
             pntr_matches[64] = 7usize;
             pntr_matches[128] = 0usize;
         }
-        let (mut x, mut y): (isize, isize);
-        let mut flag: bool;
-        let mut dir: f64;
-        for row in 0..rows {
-            for col in 0..columns {
-                if seeds[(row, col)] > 0.0 && flowdir[(row, col)] != nodata {
-                    flag = false;
-                    x = col;
-                    y = row;
-                    while !flag {
-                        if output[(y, x)] == background_val {
-                            output[(y, x)] = 1.0;
-                        } else {
-                            output.increment(y, x, 1.0);
+        // D-infinity directions are in degrees clockwise from north, with the
+        // flow going to the neighbour that receives the greater proportion
+        let dinf_dx = [ 0, 1, 1, 1, 0, -1, -1, -1 ];
+        let dinf_dy = [ -1, -1, 0, 1, 1, 1, 0, -1 ];
+
+        // finds the cells along the flowpath from a seed cell
+        let trace_flowpath = |row: isize, col: isize| -> Vec<(isize, isize)> {
+            let mut cells = vec![(row, col)];
+            let mut visited = HashSet::new();
+            visited.insert((row, col));
+            let (mut x, mut y) = (col, row);
+            loop {
+                // find its downslope neighbour
+                let dir = flowdir[(y, x)];
+                if dir == nodata {
+                    break;
+                }
+                if dinf && dir >= 0.0 {
+                    let i = ((dir / 45.0 - 0.5).ceil().max(0.0) as usize) % 8;
+                    x += dinf_dx[i];
+                    y += dinf_dy[i];
+                } else if !dinf && dir > 0.0 {
+                    x += dx[pntr_matches[dir as usize]];
+                    y += dy[pntr_matches[dir as usize]];
+                } else {
+                    break;
+                }
+                if y < 0 || y >= rows || x < 0 || x >= columns || flowdir[(y, x)] == nodata {
+                    break;
+                }
+                if !visited.insert((y, x)) {
+                    // the pointer contains a loop
+                    break;
+                }
+                cells.push((y, x));
+            }
+            cells
+        };
+
+        if vector_output {
+            let shape_type = if dem.is_some() { ShapeType::PolyLineZ } else { ShapeType::PolyLine };
+            let mut output = match seed_vector {
+                Some(ref seeds) => Shapefile::initialize_using_file(&output_file, seeds, shape_type, true)?,
+                None => {
+                    let mut output = Shapefile::new(&output_file, "w")?;
+                    output.header.shape_type = shape_type;
+                    output.projection = flowdir.configs.coordinate_ref_system_wkt.clone();
+                    output.attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 10, 0));
+                    output
+                }
+            };
+            output.attributes.add_field(&AttributeField::new("LENGTH", FieldDataType::Real, 19, 3));
+            output.attributes.add_field(&AttributeField::new("NUM_CELLS", FieldDataType::Int, 10, 0));
+            if dem.is_some() {
+                output.attributes.add_field(&AttributeField::new("SLOPE", FieldDataType::Real, 19, 6));
+            }
+
+            // vector seeds without a flowpath are output as null shapes
+            let num_records = match seed_vector {
+                Some(ref seeds) => seeds.num_records,
+                None => seed_cells.len(),
+            };
+            let mut flowpaths: Vec<Option<Vec<(isize, isize)>>> = vec![None; num_records];
+            for (n, &(row, col, i)) in seed_cells.iter().enumerate() {
+                flowpaths[i] = Some(trace_flowpath(row, col));
+                if verbose {
+                    progress = (100.0_f64 * (n + 1) as f64 / seed_cells.len() as f64) as usize;
+                    if progress != old_progress {
+                        println!("Progress: {}%", progress);
+                        old_progress = progress;
+                    }
+                }
+            }
+
+            for i in 0..num_records {
+                let mut rec = match seed_vector {
+                    Some(ref seeds) => seeds.attributes.get_record(i).clone(),
+                    None => vec![FieldData::Int(i as i32 + 1)],
+                };
+                let cells = match flowpaths[i] {
+                    Some(ref cells) => cells,
+                    None => {
+                        output.add_record(ShapefileGeometry::new(ShapeType::Null));
+                        rec.push(FieldData::Null);
+                        rec.push(FieldData::Null);
+                        if dem.is_some() {
+                            rec.push(FieldData::Null);
                         }
-                        // find its downslope neighbour
-                        dir = flowdir[(y, x)];
-                        if dir != nodata && dir > 0.0 {
-                            // move x and y accordingly
-                            x += dx[pntr_matches[dir as usize]];
-                            y += dy[pntr_matches[dir as usize]];
-                        } else {
-                            flag = true;
+                        output.attributes.add_record(rec);
+                        continue;
+                    }
+                };
+
+                let mut points: Vec<Point2D> = cells.iter().map(|&(r, c)| {
+                    Point2D::new(flowdir.get_x_from_column_centre(c), flowdir.get_y_from_row_centre(r))
+                }).collect();
+                let mut distances = vec![0f64];
+                for j in 1..points.len() {
+                    let d = points[j - 1].distance(&points[j]);
+                    distances.push(distances[j - 1] + d);
+                }
+                let length = distances[distances.len() - 1];
+                if points.len() == 1 {
+                    // a seed that has no downslope neighbour
+                    points.push(points[0]);
+                    distances.push(0f64);
+                }
+
+                let mut sfg = ShapefileGeometry::new(shape_type);
+                match dem {
+                    Some(ref dem) => {
+                        let mut z_values: Vec<f64> = cells.iter().map(|&(r, c)| dem.get_value(r, c)).collect();
+                        if z_values.len() == 1 {
+                            z_values.push(z_values[0]);
                         }
+                        sfg.add_part_zm(&points, &z_values, &distances);
+                    },
+                    None => sfg.add_part(&points),
+                }
+                output.add_record(sfg);
+
+                rec.push(FieldData::Real(length));
+                rec.push(FieldData::Int(cells.len() as i32));
+                if let Some(ref dem) = dem {
+                    let (r1, c1) = cells[0];
+                    let (r2, c2) = cells[cells.len() - 1];
+                    let (z1, z2) = (dem.get_value(r1, c1), dem.get_value(r2, c2));
+                    if length > 0f64 && z1 != dem.configs.nodata && z2 != dem.configs.nodata {
+                        rec.push(FieldData::Real((z1 - z2) / length * 100f64));
+                    } else {
+                        rec.push(FieldData::Null);
                     }
-                } else if flowdir[(row, col)] == nodata {
+                }
+                output.attributes.add_record(rec);
+            }
+
+            let end = time::now();
+            let elapsed_time = end - start;
+
+            if verbose { println!("Saving data...") };
+            let _ = match output.write() {
+                Ok(_) => if verbose { println!("Output file written") },
+                Err(e) => return Err(e),
+            };
+
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+            return Ok(());
+        }
+
+        if background_val == f64::NEG_INFINITY {
+            background_val = nodata;
+        }
+        
+        let mut output = Raster::initialize_using_file(&output_file, &flowdir);
+        output.reinitialize_values(background_val);
+        for row in 0..rows {
+            for col in 0..columns {
+                if flowdir[(row, col)] == nodata {
                     output[(row, col)] = nodata;
                 }
             }
+        }
+        
+        for (n, &(row, col, _)) in seed_cells.iter().enumerate() {
+            for (y, x) in trace_flowpath(row, col) {
+                if output[(y, x)] == background_val {
+                    output[(y, x)] = 1.0;
+                } else {
+                    output.increment(y, x, 1.0);
+                }
+            }
             if verbose {
-                progress = (100.0_f64 * row as f64 / (rows - 1) as f64) as usize;
+                progress = (100.0_f64 * (n + 1) as f64 / seed_cells.len() as f64) as usize;
                 if progress != old_progress {
                     println!("Progress: {}%", progress);
                     old_progress = progress;
@@ -283,8 +522,12 @@ impl WhiteboxTool for TraceDownslopeFlowpaths {
         output.configs.data_type = DataType::F32;
         output.configs.photometric_interp = PhotometricInterpretation::Continuous;
         output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
-        output.add_metadata_entry(format!("Seed points raster file: {}", seed_file));
-        output.add_metadata_entry(format!("D8 flow direction (pointer) raster: {}", flowdir_file));
+        output.add_metadata_entry(format!("Seed points file: {}", seed_file));
+        if dinf {
+            output.add_metadata_entry(format!("D-infinity flow direction (pointer) raster: {}", flowdir_file));
+        } else {
+            output.add_metadata_entry(format!("D8 flow direction (pointer) raster: {}", flowdir_file));
+        }
         output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
 
         if verbose { println!("Saving data...") };
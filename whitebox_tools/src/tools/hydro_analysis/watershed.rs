@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 22, 2017
-Last Modified: Dec. 14, 2017
+Last Modified: February 22, 2018
 License: MIT
 */
 extern crate time;
@@ -14,7 +14,14 @@ use raster::*;
 use std::io::{Error, ErrorKind};
 use structures::Array2D;
 use tools::*;
+use vector::*;
 
+/// Identifies the watershed, or drainage basin, draining to each of a set of outlets. The pour
+/// points (outlets) may be either a raster, in which case each watershed is assigned the value of
+/// its outlet cell, or a point vector (Shapefile or GeoJSON). Vector pour points are located
+/// within the grid of the D8 pointer and each watershed is assigned the value of its outlet
+/// point's ID field (`--field`), or by default the feature ID (FID, starting at 1). Outlet IDs
+/// must be greater than zero.
 pub struct Watershed {
     name: String,
     description: String,
@@ -42,12 +49,21 @@ impl Watershed {
         parameters.push(ToolParameter{
             name: "Input Pour Points (Outlet) File".to_owned(), 
             flags: vec!["--pour_pts".to_owned()], 
-            description: "Input raster pour points (outlet) file.".to_owned(),
-            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            description: "Input raster or vector pour points (outlet) file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Any),
             default_value: None,
             optional: false
         });
 
+        parameters.push(ToolParameter{
+            name: "Outlet ID Field".to_owned(), 
+            flags: vec!["--field".to_owned()], 
+            description: "Numeric attribute field of vector pour points containing the outlet IDs; 'FID' for the feature ID.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("FID".to_owned()),
+            optional: true
+        });
+
         parameters.push(ToolParameter{
             name: "Output File".to_owned(), 
             flags: vec!["-o".to_owned(), "--output".to_owned()], 
@@ -73,7 +89,8 @@ impl Watershed {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr='d8pntr.dep' --pour_pts='pour_pts.dep' -o='output.dep'", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr='d8pntr.dep' --pour_pts='pour_pts.dep' -o='output.dep'
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --d8_pntr='d8pntr.dep' --pour_pts='gauges.shp' --field=GAUGE_ID -o='output.dep'", short_exe, name).replace("*", &sep);
     
         Watershed { 
             name: name, 
@@ -117,6 +134,7 @@ impl WhiteboxTool for Watershed {
         let mut d8_file = String::new();
         let mut pourpts_file = String::new();
         let mut output_file = String::new();
+        let mut field_name = String::from("FID");
         let mut esri_style = false;
         
         if args.len() == 0 {
@@ -144,6 +162,12 @@ impl WhiteboxTool for Watershed {
                 } else {
                     pourpts_file = args[i+1].to_string();
                 }
+            } else if vec[0].to_lowercase() == "-field" || vec[0].to_lowercase() == "--field" {
+                if keyval {
+                    field_name = vec[1].to_string();
+                } else {
+                    field_name = args[i+1].to_string();
+                }
             } else if vec[0].to_lowercase() == "-o" || vec[0].to_lowercase() == "--output" {
                 if keyval {
                     output_file = vec[1].to_string();
@@ -179,7 +203,12 @@ impl WhiteboxTool for Watershed {
         if verbose { println!("Reading data...") };
 
         let pntr = Raster::new(&d8_file, "r")?;
-        let pourpts = Raster::new(&pourpts_file, "r")?;
+        let pourpts = if is_vector_file(&pourpts_file) {
+            let points = Shapefile::new(&pourpts_file, "r")?;
+            rasterize_pour_points(&points, &field_name, &pntr, &output_file)?
+        } else {
+            Raster::new(&pourpts_file, "r")?
+        };
 
         let start = time::now();
 
@@ -340,4 +369,45 @@ impl WhiteboxTool for Watershed {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Creates a raster of outlet IDs, on the grid of the D8 pointer, from a pour points vector.
+fn rasterize_pour_points(pourpts: &Shapefile, field_name: &str, pntr: &Raster, file_name: &str) -> Result<Raster, Error> {
+    if pourpts.header.shape_type.base_shape_type() != ShapeType::Point && pourpts.header.shape_type.base_shape_type() != ShapeType::MultiPoint {
+        return Err(Error::new(ErrorKind::InvalidInput, "The input pour points vector must contain point features."));
+    }
+
+    let field = pourpts.attributes.get_field_or_fid(field_name, true)?;
+    let integer_ids = field.map_or(true, |f| pourpts.attributes.fields[f].get_data_type() != FieldDataType::Real);
+    let ids: Vec<Option<f64>> = (0..pourpts.num_records).map(|i| match field {
+        Some(f) => pourpts.attributes.get_value(i, f).as_f64(),
+        None => Some((i + 1) as f64),
+    }).collect();
+
+    let mut output = Raster::initialize_using_file(file_name, pntr);
+    let nodata = output.configs.nodata;
+    output.configs.data_type = if integer_ids { DataType::I32 } else { DataType::F32 };
+    output.configs.palette = "qual.plt".to_string();
+    output.configs.photometric_interp = PhotometricInterpretation::Categorical;
+    output.reinitialize_values(nodata);
+
+    let rows = output.configs.rows as isize;
+    let columns = output.configs.columns as isize;
+    for i in 0..pourpts.num_records {
+        let id = match ids[i] {
+            Some(v) => v,
+            None => continue,
+        };
+        if id <= 0f64 || id == nodata {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("The outlet ID of pour point {} must be greater than zero.", i + 1)));
+        }
+        for p in &pourpts.get_record(i).points {
+            let row = ((output.configs.north - p.y) / output.configs.resolution_y).floor() as isize;
+            let col = ((p.x - output.configs.west) / output.configs.resolution_x).floor() as isize;
+            if row >= 0 && row < rows && col >= 0 && col < columns {
+                output.set_value(row, col, id);
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,437 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 19, 2018
+Last Modified: January 19, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::io::BufReader;
+use std::fs::File;
+use std::io::prelude::*;
+use std;
+use std::env;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path;
+use lidar::*;
+use tools::*;
+
+/// Converts one or more ASCII text (e.g. CSV or XYZ) point files into LAS files. The
+/// `--pattern` parameter describes the content of each column, using the codes 'x', 'y',
+/// 'z', 'i' (intensity), 'c' (class), 'rn' (return number), 'nr' (number of returns),
+/// 'time' (GPS time), 'sa' (scan angle), 'r', 'g', 'b' (red, green, blue) and '_' (an
+/// ignored column). The point format of each output file is chosen based on whether GPS
+/// times and/or colours are present. Unless specified, the coordinate scale factor is
+/// based on the number of decimal places within the input coordinates. 8-bit colour
+/// values are rescaled to the 16-bit range of the LAS specification.
+pub struct AsciiToLas {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl AsciiToLas {
+    pub fn new() -> AsciiToLas { // public constructor
+        let name = "AsciiToLas".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Converts one or more ASCII text point files into LAS files.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input ASCII Files".to_owned(),
+            flags: vec!["-i".to_owned(), "--inputs".to_owned()],
+            description: "Input ASCII text point files; each is converted into a LAS file of the same name.".to_owned(),
+            parameter_type: ParameterType::FileList(ParameterFileType::Text),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Column Pattern".to_owned(),
+            flags: vec!["--pattern".to_owned()],
+            description: "Comma-separated column pattern, using 'x', 'y', 'z', 'i', 'c', 'rn', 'nr', 'time', 'sa', 'r', 'g', 'b', and '_' (ignored); e.g. 'x,y,z,i,c'.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("x,y,z".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Number of Header Lines".to_owned(),
+            flags: vec!["--skip".to_owned()],
+            description: "Number of header lines to skip at the start of each file.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Delimiter".to_owned(),
+            flags: vec!["--delimiter".to_owned()],
+            description: "Column delimiter; options are 'auto' (default), 'comma', 'semicolon', 'tab', and 'space' (any whitespace).".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["auto".to_owned(), "comma".to_owned(), "semicolon".to_owned(), "tab".to_owned(), "space".to_owned()]),
+            default_value: Some("auto".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Coordinate Scale Factor (optional)".to_owned(),
+            flags: vec!["--scale".to_owned()],
+            description: "Optional x, y, z coordinate scale factor, e.g. 0.001; by default, it is based on the precision of the input coordinates.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"file1.csv, file2.csv\" --pattern=\"x,y,z,i,c,rn,nr,time\" --skip=1 --delimiter=comma", short_exe, name).replace("*", &sep);
+
+        AsciiToLas {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for AsciiToLas {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_files: String = String::new();
+        let mut pattern = "x,y,z".to_string();
+        let mut skip = 0usize;
+        let mut delimiter = "auto".to_string();
+        let mut user_scale = 0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 { keyval = true; }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-inputs" {
+                input_files = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-pattern" {
+                pattern = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+            } else if flag_val == "-skip" {
+                skip = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            } else if flag_val == "-delimiter" {
+                delimiter = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+            } else if flag_val == "-scale" {
+                user_scale = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        // interpret the column pattern
+        let mut columns: Vec<Field> = vec![];
+        for code in pattern.split(|c| c == ',' || c == ';' || c == ' ') {
+            if !code.trim().is_empty() {
+                match Field::from_code(code.trim()) {
+                    Some(f) => columns.push(f),
+                    None => return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unrecognized column pattern code '{}'.", code.trim()))),
+                }
+            }
+        }
+        if !columns.contains(&Field::X) || !columns.contains(&Field::Y) || !columns.contains(&Field::Z) {
+            return Err(Error::new(ErrorKind::InvalidInput, "The column pattern must include 'x', 'y' and 'z'."));
+        }
+        let has_time = columns.contains(&Field::GpsTime);
+        let has_rgb = columns.contains(&Field::Red) || columns.contains(&Field::Green) || columns.contains(&Field::Blue);
+        let point_format = match (has_time, has_rgb) {
+            (false, false) => 0u8,
+            (true, false) => 1u8,
+            (false, true) => 2u8,
+            (true, true) => 3u8,
+        };
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = std::path::MAIN_SEPARATOR;
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+        let start = time::now();
+
+        let mut cmd = input_files.split(";");
+        let mut vec = cmd.collect::<Vec<&str>>();
+        if vec.len() == 1 {
+            cmd = input_files.split(",");
+            vec = cmd.collect::<Vec<&str>>();
+        }
+        let mut file_num = 1;
+        let num_files = vec.len();
+        for value in vec {
+            if !value.trim().is_empty() {
+                let mut input_file = value.trim().to_owned();
+                if !input_file.contains(sep) {
+                    input_file = format!("{}{}", working_directory, input_file);
+                }
+                let output_file = match input_file.rfind('.') {
+                    Some(idx) => format!("{}.las", &input_file[..idx]),
+                    None => format!("{}.las", input_file),
+                };
+
+                let f = match File::open(&input_file) {
+                    Ok(f) => f,
+                    Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
+                };
+                let reader = BufReader::new(f);
+
+                let mut points: Vec<PointData> = vec![];
+                let mut gps_times: Vec<f64> = vec![];
+                let mut colours: Vec<RgbData> = vec![];
+                let mut max_decimals = 0usize;
+                let mut max_colour = 0u16;
+                for (line_num, line) in reader.lines().enumerate() {
+                    let line = line?;
+                    if line_num < skip || line.trim().is_empty() {
+                        continue;
+                    }
+                    let values: Vec<&str> = match &delimiter as &str {
+                        "comma" | "," => line.split(',').map(|s| s.trim()).collect(),
+                        "semicolon" | ";" => line.split(';').map(|s| s.trim()).collect(),
+                        "tab" => line.split('\t').map(|s| s.trim()).collect(),
+                        "space" | " " => line.split_whitespace().collect(),
+                        _ => {
+                            if line.contains(',') {
+                                line.split(',').map(|s| s.trim()).collect()
+                            } else if line.contains(';') {
+                                line.split(';').map(|s| s.trim()).collect()
+                            } else {
+                                line.split_whitespace().collect()
+                            }
+                        },
+                    };
+                    if values.len() < columns.len() {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                            format!("Line {} of {} has fewer columns than the column pattern.", line_num + 1, input_file)));
+                    }
+
+                    let mut p: PointData = Default::default();
+                    let (mut rn, mut nr) = (1u8, 1u8);
+                    let mut gps_time = 0f64;
+                    let mut rgb: RgbData = Default::default();
+                    for c in 0..columns.len() {
+                        if columns[c] == Field::Ignore {
+                            continue;
+                        }
+                        let v = match values[c].parse::<f64>() {
+                            Ok(v) => v,
+                            Err(_) => return Err(Error::new(ErrorKind::InvalidData,
+                                format!("Unable to parse '{}' on line {} of {}.", values[c], line_num + 1, input_file))),
+                        };
+                        match columns[c] {
+                            Field::X | Field::Y | Field::Z => {
+                                if let Some(idx) = values[c].find('.') {
+                                    let decimals = values[c][idx + 1..].chars().take_while(|ch| ch.is_digit(10)).count();
+                                    if decimals > max_decimals {
+                                        max_decimals = decimals;
+                                    }
+                                }
+                                match columns[c] {
+                                    Field::X => p.x = v,
+                                    Field::Y => p.y = v,
+                                    _ => p.z = v,
+                                }
+                            },
+                            Field::Intensity => p.intensity = v as u16,
+                            Field::Class => p.set_classification(v as u8),
+                            Field::ReturnNumber => rn = v as u8,
+                            Field::NumberOfReturns => nr = v as u8,
+                            Field::GpsTime => gps_time = v,
+                            Field::ScanAngle => p.scan_angle = v as i8,
+                            Field::Red => rgb.red = v as u16,
+                            Field::Green => rgb.green = v as u16,
+                            Field::Blue => rgb.blue = v as u16,
+                            Field::Ignore => {},
+                        }
+                    }
+                    p.bit_field = PointBitField { value: (rn & 7u8) | ((nr & 7u8) << 3) };
+                    max_colour = max_colour.max(rgb.red).max(rgb.green).max(rgb.blue);
+                    points.push(p);
+                    gps_times.push(gps_time);
+                    colours.push(rgb);
+                }
+
+                let n_points = points.len();
+                if n_points == 0 {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("No points were read from {}.", input_file)));
+                }
+
+                // 8-bit colours are rescaled to the 16-bit range
+                if has_rgb && max_colour <= 255u16 {
+                    for rgb in colours.iter_mut() {
+                        rgb.red *= 256u16;
+                        rgb.green *= 256u16;
+                        rgb.blue *= 256u16;
+                    }
+                }
+
+                let mut output = LasFile::new(&output_file, "w")?;
+                let mut header: LasHeader = Default::default();
+                header.project_id_used = true;
+                header.point_format = point_format;
+                output.add_header(header);
+
+                for k in 0..n_points {
+                    let pr = match point_format {
+                        0 => LidarPointRecord::PointRecord0 { point_data: points[k] },
+                        1 => LidarPointRecord::PointRecord1 { point_data: points[k], gps_data: gps_times[k] },
+                        2 => LidarPointRecord::PointRecord2 { point_data: points[k], rgb_data: colours[k] },
+                        _ => LidarPointRecord::PointRecord3 { point_data: points[k], gps_data: gps_times[k], rgb_data: colours[k] },
+                    };
+                    output.add_point_record(pr);
+                    if verbose {
+                        progress = (100.0_f64 * k as f64 / (n_points - 1).max(1) as f64) as usize;
+                        if progress != old_progress {
+                            if num_files > 1 {
+                                println!("Creating file: {} of {}: {}%", file_num, num_files, progress);
+                            } else {
+                                println!("Progress: {}%", progress);
+                            }
+                            old_progress = progress;
+                        }
+                    }
+                }
+
+                // the scale factor must allow the full range of coordinates to be stored as 32-bit integers
+                let mut scale = if user_scale > 0f64 { user_scale } else { 10f64.powi(-(max_decimals.min(6) as i32)) };
+                let max_range = (output.header.max_x - output.header.min_x)
+                    .max(output.header.max_y - output.header.min_y)
+                    .max(output.header.max_z - output.header.min_z);
+                while max_range / scale > i32::max_value() as f64 {
+                    scale *= 10f64;
+                }
+                output.header.x_scale_factor = scale;
+                output.header.y_scale_factor = scale;
+                output.header.z_scale_factor = scale;
+
+                output.write()?;
+                if verbose {
+                    println!("Output file written: {}", output_file);
+                }
+            }
+            file_num += 1;
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        println!("{}", &format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    X,
+    Y,
+    Z,
+    Intensity,
+    Class,
+    ReturnNumber,
+    NumberOfReturns,
+    GpsTime,
+    ScanAngle,
+    Red,
+    Green,
+    Blue,
+    Ignore,
+}
+
+impl Field {
+    fn from_code(code: &str) -> Option<Field> {
+        match code {
+            "x" => Some(Field::X),
+            "y" => Some(Field::Y),
+            "z" => Some(Field::Z),
+            "i" | "intensity" => Some(Field::Intensity),
+            "c" | "class" => Some(Field::Class),
+            "rn" => Some(Field::ReturnNumber),
+            "nr" => Some(Field::NumberOfReturns),
+            "time" | "gps_time" => Some(Field::GpsTime),
+            "sa" => Some(Field::ScanAngle),
+            "r" | "red" => Some(Field::Red),
+            "g" | "green" => Some(Field::Green),
+            "b" | "blue" => Some(Field::Blue),
+            "_" | "-" => Some(Field::Ignore),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,503 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 14, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::env;
+use std::f64;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::io::{Error, ErrorKind};
+use std::mem;
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use tools::*;
+
+/// Identifies individual trees within a canopy height model (CHM), e.g. as created by the
+/// PitFreeChm tool. Tree tops are located as local maxima within a circular window whose
+/// radius increases linearly with the height of the cell, from `--min_radius` at
+/// `--min_height` to `--max_radius` at the highest point in the CHM. Crowns are then grown
+/// from the tree tops using a marker-controlled watershed of the CHM, limited to cells of
+/// at least `--min_height`.
+///
+/// The output raster contains the crown ID of each cell. A CSV table listing each tree's
+/// ID, position, height, crown area and crown diameter is written beside it, and the tree
+/// IDs can optionally be assigned to the points of a LAS file as a 'tree_id' attribute.
+pub struct IndividualTreeDetection {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl IndividualTreeDetection {
+    pub fn new() -> IndividualTreeDetection {
+        // public constructor
+        let name = "IndividualTreeDetection".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Identifies tree tops and delineates tree crowns from a canopy height model (CHM).".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input CHM File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input canopy height model raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Crown Raster File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output labelled crown raster file; a CSV table of trees with the same name is also created.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Tree Height".to_owned(),
+            flags: vec!["--min_height".to_owned()],
+            description: "Minimum height of tree tops and crown cells.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Search Window Radius".to_owned(),
+            flags: vec!["--min_radius".to_owned()],
+            description: "Local maximum search window radius of the shortest trees, in map units.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Search Window Radius".to_owned(),
+            flags: vec!["--max_radius".to_owned()],
+            description: "Local maximum search window radius of the tallest trees, in map units.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("3.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input LiDAR File (optional)".to_owned(),
+            flags: vec!["--input_las".to_owned()],
+            description: "Optional input LAS file whose points are assigned tree IDs.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output LiDAR File (optional)".to_owned(),
+            flags: vec!["--output_las".to_owned()],
+            description: "Optional output LAS file, with a 'tree_id' attribute for each point (zero outside of crowns).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=chm.tif -o=crowns.tif --min_height=2.0 --min_radius=1.0 --max_radius=4.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=chm.tif -o=crowns.tif --input_las=file.las --output_las=trees.las", short_exe, name).replace("*", &sep);
+
+        IndividualTreeDetection {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for IndividualTreeDetection {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self,
+               args: Vec<String>,
+               working_directory: &'a str,
+               verbose: bool)
+               -> Result<(), Error> {
+        let mut input_file = String::new();
+        let mut output_file = String::new();
+        let mut input_las_file = String::new();
+        let mut output_las_file = String::new();
+        let mut min_height = 2.0f64;
+        let mut min_radius = 1.0f64;
+        let mut max_radius = 3.0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-input_las" {
+                input_las_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-output_las" {
+                output_las_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-min_height" {
+                min_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_radius" {
+                min_radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_radius" {
+                max_radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if max_radius < min_radius {
+            max_radius = min_radius;
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        if !input_file.contains(&sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(&sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !input_las_file.is_empty() && !input_las_file.contains(&sep) {
+            input_las_file = format!("{}{}", working_directory, input_las_file);
+        }
+        if !output_las_file.is_empty() && !output_las_file.contains(&sep) {
+            output_las_file = format!("{}{}", working_directory, output_las_file);
+        }
+        if !input_las_file.is_empty() && output_las_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "An output LAS file must be specified along with the input LAS file."));
+        }
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+        if verbose { println!("Reading data...") };
+        let input = Arc::new(Raster::new(&input_file, "r")?);
+
+        let start = time::now();
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+        let res_x = input.configs.resolution_x;
+        let res_y = input.configs.resolution_y;
+        let max_height = input.configs.maximum;
+
+        ////////////////////////////////////////////////
+        // Find the tree tops, using a variable window //
+        ////////////////////////////////////////////////
+        let num_procs = num_cpus::get() as isize;
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let input = input.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let mut z: f64;
+                let mut zn: f64;
+                for row in (0..rows).filter(|r| r % num_procs == tid) {
+                    let mut tops = vec![];
+                    for col in 0..columns {
+                        z = input.get_value(row, col);
+                        if z == nodata || z < min_height {
+                            continue;
+                        }
+                        let radius = if max_height > min_height {
+                            min_radius + (max_radius - min_radius) * ((z - min_height) / (max_height - min_height)).min(1f64)
+                        } else {
+                            min_radius
+                        };
+                        let dr = (radius / res_y).floor() as isize;
+                        let dc = (radius / res_x).floor() as isize;
+                        let radius_sqr = radius * radius;
+                        let mut is_top = true;
+                        'window: for r in -dr..dr + 1 {
+                            for c in -dc..dc + 1 {
+                                if (r == 0 && c == 0) ||
+                                    (r as f64 * res_y) * (r as f64 * res_y) + (c as f64 * res_x) * (c as f64 * res_x) > radius_sqr {
+                                    continue;
+                                }
+                                zn = input.get_value(row + r, col + c);
+                                // ties are resolved in favour of the first cell in row-major order
+                                if zn != nodata && (zn > z || (zn == z && (r < 0 || (r == 0 && c < 0)))) {
+                                    is_top = false;
+                                    break 'window;
+                                }
+                            }
+                        }
+                        if is_top {
+                            tops.push((col, z));
+                        }
+                    }
+                    tx.send((row, tops)).unwrap();
+                }
+            });
+        }
+
+        let mut tree_tops: Vec<(isize, isize, f64)> = vec![];
+        for row in 0..rows {
+            let (r, tops) = rx.recv().unwrap();
+            for (c, z) in tops {
+                tree_tops.push((r, c, z));
+            }
+            if verbose {
+                progress = (100.0_f64 * row as f64 / (rows - 1).max(1) as f64) as usize;
+                if progress != old_progress {
+                    println!("Finding tree tops: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+        // label the trees in row-major order, so that the output is deterministic
+        tree_tops.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        let num_trees = tree_tops.len();
+
+        ///////////////////////////////////////////
+        // Grow the crowns by watershed flooding //
+        ///////////////////////////////////////////
+        let mut output = Raster::initialize_using_file(&output_file, &input);
+        output.configs.data_type = DataType::I32;
+        output.configs.palette = "qual.plt".to_string();
+        output.configs.photometric_interp = PhotometricInterpretation::Categorical;
+        let out_nodata = -32768f64;
+        output.configs.nodata = out_nodata;
+        output.reinitialize_values(out_nodata);
+
+        let dx = [1, 1, 1, 0, -1, -1, -1, 0];
+        let dy = [-1, 0, 1, 1, 1, 0, -1, -1];
+        let mut crown_cells = vec![0usize; num_trees + 1];
+        let mut heap = BinaryHeap::with_capacity(num_trees);
+        for i in 0..num_trees {
+            let (row, col, z) = tree_tops[i];
+            output.set_value(row, col, (i + 1) as f64);
+            heap.push(GridCell { row: row, column: col, priority: z });
+        }
+        let (mut rn, mut cn): (isize, isize);
+        let mut zn: f64;
+        let mut num_cells = 0f64; // used for progress calculation only
+        for row in 0..rows {
+            for col in 0..columns {
+                zn = input.get_value(row, col);
+                if zn != nodata && zn >= min_height {
+                    num_cells += 1f64;
+                }
+            }
+        }
+        let mut num_solved = 0f64;
+        while let Some(cell) = heap.pop() {
+            let label = output.get_value(cell.row, cell.column);
+            crown_cells[label as usize] += 1;
+            for n in 0..8 {
+                rn = cell.row + dy[n];
+                cn = cell.column + dx[n];
+                zn = input.get_value(rn, cn);
+                if zn != nodata && zn >= min_height && output.get_value(rn, cn) == out_nodata &&
+                    rn >= 0 && cn >= 0 && rn < rows && cn < columns {
+                    output.set_value(rn, cn, label);
+                    heap.push(GridCell { row: rn, column: cn, priority: zn });
+                }
+            }
+            if verbose {
+                num_solved += 1f64;
+                progress = (100.0_f64 * num_solved / num_cells) as usize;
+                if progress != old_progress {
+                    println!("Growing crowns: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        //////////////////////////
+        // Write the tree table //
+        //////////////////////////
+        let csv_file = match output_file.rfind('.') {
+            Some(idx) => format!("{}.csv", &output_file[..idx]),
+            None => format!("{}.csv", output_file),
+        };
+        let f = File::create(&csv_file)?;
+        let mut writer = BufWriter::new(f);
+        writer.write_all("TREE_ID,X,Y,HEIGHT,CROWN_AREA,CROWN_DIAMETER\n".as_bytes())?;
+        let cell_area = res_x * res_y;
+        for i in 0..num_trees {
+            let (row, col, z) = tree_tops[i];
+            let area = crown_cells[i + 1] as f64 * cell_area;
+            // the tree top is located at the centre of its cell
+            let x = input.get_x_from_column_centre(col);
+            let y = input.get_y_from_row_centre(row);
+            let s = format!("{},{},{},{},{},{}\n", i + 1, x, y, z, area, 2f64 * (area / f64::consts::PI).sqrt());
+            writer.write_all(s.as_bytes())?;
+        }
+        writer.flush()?;
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+        output.add_metadata_entry(format!("Input file: {}", input_file));
+        output.add_metadata_entry(format!("Minimum height: {}", min_height));
+        output.add_metadata_entry(format!("Search window radius: {} to {}", min_radius, max_radius));
+        output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+        if verbose { println!("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { println!("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        ////////////////////////////////////////
+        // Assign the tree IDs to LAS points //
+        ////////////////////////////////////////
+        if !input_las_file.is_empty() {
+            if verbose { println!("Assigning tree IDs to LAS points..."); }
+            let input_las = match LasFile::new(&input_las_file, "r") {
+                Ok(lf) => lf,
+                Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_las_file))),
+            };
+            let mut output_las = LasFile::initialize_using_file(&output_las_file, &input_las);
+            output_las.add_extra_bytes_attribute("tree_id", "Individual tree ID", ExtraBytesDataType::U32)?;
+            let n_points = input_las.header.number_of_points as usize;
+            let mut label: f64;
+            for i in 0..n_points {
+                let p: PointData = input_las[i];
+                label = output.get_value(output.get_row_from_y(p.y), output.get_column_from_x(p.x));
+                let tree_id: u32 = if label != out_nodata { label as u32 } else { 0u32 };
+                let bytes: [u8; 4] = unsafe { mem::transmute(tree_id) };
+                output_las.add_point_record(input_las.get_record(i));
+                output_las.add_extra_bytes(&bytes);
+            }
+            let _ = match output_las.write() {
+                Ok(_) => if verbose { println!("Output LAS file written") },
+                Err(e) => return Err(e),
+            };
+        }
+
+        if verbose {
+            println!("Number of trees: {}", num_trees);
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Debug)]
+struct GridCell {
+    row: isize,
+    column: isize,
+    priority: f64,
+}
+
+impl Eq for GridCell {}
+
+impl PartialOrd for GridCell {
+    fn partial_cmp(&self, other: &GridCell) -> Option<Ordering> {
+        self.priority.partial_cmp(&other.priority)
+    }
+}
+
+impl Ord for GridCell {
+    fn cmp(&self, other: &GridCell) -> Ordering {
+        // higher cells are flooded first
+        self.partial_cmp(other).unwrap()
+    }
+}
@@ -0,0 +1,300 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 21, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::BufWriter;
+use std::fs::File;
+use std::io::prelude::*;
+use std;
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::path;
+use lidar::*;
+use tools::*;
+
+/// Converts one or more LAS files into PLY (Polygon File Format) files, in either the
+/// ASCII or binary little-endian encoding. Each point is written as a vertex with double
+/// precision x, y and z properties, along with its intensity and classification, and the
+/// GPS time ('gps_time') of the point formats that contain it. The RGB
+/// colours of the point formats that contain them are written as 8-bit 'red', 'green' and
+/// 'blue' properties or, when `--normals` is specified, are interpreted as the normal
+/// vectors stored by the NormalVectors tool and written as 'nx', 'ny' and 'nz' properties.
+pub struct LasToPly {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LasToPly {
+    pub fn new() -> LasToPly { // public constructor
+        let name = "LasToPly".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Converts one or more LAS files into PLY files.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input LiDAR Files".to_owned(),
+            flags: vec!["-i".to_owned(), "--inputs".to_owned()],
+            description: "Input LiDAR files; each is converted into a PLY file of the same name.".to_owned(),
+            parameter_type: ParameterType::FileList(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "PLY Format".to_owned(),
+            flags: vec!["--format".to_owned()],
+            description: "Output PLY encoding; options are 'binary' (little-endian, default) and 'ascii'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["binary".to_owned(), "ascii".to_owned()]),
+            default_value: Some("binary".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Write RGB values as normal vectors?".to_owned(),
+            flags: vec!["--normals".to_owned()],
+            description: "Optional boolean flag indicating that the RGB field contains normal vectors (see NormalVectors).".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"file1.las, file2.las\" --format=ascii", short_exe, name).replace("*", &sep);
+
+        LasToPly {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LasToPly {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_files: String = String::new();
+        let mut binary = true;
+        let mut normals = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 { keyval = true; }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-inputs" {
+                input_files = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-format" {
+                let format = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+                binary = !format.contains("ascii");
+            } else if flag_val == "-normals" {
+                normals = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = std::path::MAIN_SEPARATOR;
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+        let start = time::now();
+
+        let mut cmd = input_files.split(";");
+        let mut vec = cmd.collect::<Vec<&str>>();
+        if vec.len() == 1 {
+            cmd = input_files.split(",");
+            vec = cmd.collect::<Vec<&str>>();
+        }
+        let mut i = 1;
+        let num_files = vec.len();
+        for value in vec {
+            if !value.trim().is_empty() {
+                let mut input_file = value.trim().to_owned();
+                if !input_file.contains(sep) {
+                    input_file = format!("{}{}", working_directory, input_file);
+                }
+
+                let input = match LasFile::new(&input_file, "r") {
+                    Ok(lf) => lf,
+                    Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
+                };
+
+                let output_file = match input_file.rfind('.') {
+                    Some(idx) => format!("{}.ply", &input_file[..idx]),
+                    None => format!("{}.ply", input_file),
+                };
+
+                let has_rgb = input.header.point_format == 2 || input.header.point_format == 3;
+                let has_gps_time = input.header.point_format == 1 || input.header.point_format == 3;
+                if normals && !has_rgb {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("{} does not contain RGB data and therefore cannot contain normal vectors.", input_file)));
+                }
+
+                let f = File::create(&output_file)?;
+                let mut writer = BufWriter::new(f);
+
+                let n_points = input.header.number_of_points as usize;
+
+                // write the header
+                let mut header = String::from("ply\n");
+                header.push_str(if binary { "format binary_little_endian 1.0\n" } else { "format ascii 1.0\n" });
+                header.push_str("comment Created by WhiteboxTools\n");
+                header.push_str(&format!("element vertex {}\n", n_points));
+                header.push_str("property double x\nproperty double y\nproperty double z\n");
+                header.push_str("property ushort intensity\nproperty uchar classification\n");
+                if has_gps_time {
+                    header.push_str("property double gps_time\n");
+                }
+                if has_rgb {
+                    if normals {
+                        header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+                    } else {
+                        header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\n");
+                    }
+                }
+                header.push_str("end_header\n");
+                writer.write_all(header.as_bytes())?;
+
+                for k in 0..n_points {
+                    let pd = input[k];
+                    let rgb = if has_rgb { input.get_rgb(k)? } else { Default::default() };
+                    let gps_time = if has_gps_time { input.get_gps_time(k)? } else { 0f64 };
+                    // normal vectors are stored as 8-bit values, scaled to the 16-bit range
+                    let (nx, ny, nz) = (rgb_to_normal(rgb.red), rgb_to_normal(rgb.green), rgb_to_normal(rgb.blue));
+                    if binary {
+                        writer.write_f64::<LittleEndian>(pd.x)?;
+                        writer.write_f64::<LittleEndian>(pd.y)?;
+                        writer.write_f64::<LittleEndian>(pd.z)?;
+                        writer.write_u16::<LittleEndian>(pd.intensity)?;
+                        writer.write_u8(pd.classification())?;
+                        if has_gps_time {
+                            writer.write_f64::<LittleEndian>(gps_time)?;
+                        }
+                        if has_rgb {
+                            if normals {
+                                writer.write_f32::<LittleEndian>(nx)?;
+                                writer.write_f32::<LittleEndian>(ny)?;
+                                writer.write_f32::<LittleEndian>(nz)?;
+                            } else {
+                                writer.write_u8((rgb.red / 256u16) as u8)?;
+                                writer.write_u8((rgb.green / 256u16) as u8)?;
+                                writer.write_u8((rgb.blue / 256u16) as u8)?;
+                            }
+                        }
+                    } else {
+                        let mut s = format!("{} {} {} {} {}", pd.x, pd.y, pd.z, pd.intensity, pd.classification());
+                        if has_gps_time {
+                            s.push_str(&format!(" {}", gps_time));
+                        }
+                        if has_rgb {
+                            if normals {
+                                s.push_str(&format!(" {} {} {}", nx, ny, nz));
+                            } else {
+                                s.push_str(&format!(" {} {} {}", rgb.red / 256u16, rgb.green / 256u16, rgb.blue / 256u16));
+                            }
+                        }
+                        s.push_str("\n");
+                        writer.write_all(s.as_bytes())?;
+                    }
+
+                    if verbose {
+                        progress = (100.0_f64 * k as f64 / (n_points - 1).max(1) as f64) as usize;
+                        if progress != old_progress {
+                            if num_files > 1 {
+                                println!("Creating file: {} of {}: {}%", i, num_files, progress);
+                            } else {
+                                println!("Progress: {}%", progress);
+                            }
+                            old_progress = progress;
+                        }
+                    }
+                }
+                writer.flush()?;
+            }
+            i += 1;
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        println!("{}", &format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
+
+        Ok(())
+    }
+}
+
+/// Converts an RGB value containing a normal vector component, as encoded by the
+/// NormalVectors tool, back into the range -1 to 1.
+fn rgb_to_normal(value: u16) -> f32 {
+    (value / 256u16) as f32 / 255f32 * 2f32 - 1f32
+}
@@ -0,0 +1,617 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 8, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use tools::*;
+
+pub struct LidarBlockStatistics {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarBlockStatistics {
+    pub fn new() -> LidarBlockStatistics {
+        // public constructor
+        let name = "LidarBlockStatistics".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Bins the points in a LAS file into a grid and outputs a raster for each of a set of per-cell statistics."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file (including extension).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file (including extension); the name of each statistic is appended to the file name.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Statistics".to_owned(),
+            flags: vec!["--stats".to_owned()],
+            description: "Comma-separated list of statistics; options are 'count', 'min', 'max', 'range', 'mean', 'stdev', 'skewness', 'kurtosis', 'median', 'first_ratio', 'last_ratio', and percentiles given as 'p10', 'p95', etc.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("count,min,max,mean,stdev".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Point Attribute".to_owned(),
+            flags: vec!["--parameter".to_owned()],
+            description: "Point attribute used to calculate statistics; options are 'elevation' (default; also 'z'), 'intensity', 'scan angle'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["elevation".to_owned(), "intensity".to_owned(), "scan angle".to_owned()]),
+            default_value: Some("elevation".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Point Returns Included".to_owned(),
+            flags: vec!["--returns".to_owned()],
+            description: "Point return types to include; options are 'all' (default), 'last', 'first'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["all".to_owned(), "last".to_owned(), "first".to_owned()]),
+            default_value: Some("all".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(),
+            flags: vec!["--exclude_cls".to_owned()],
+            description: "Optional exclude classes from the analysis; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Elevation Value (optional)".to_owned(),
+            flags: vec!["--minz".to_owned()],
+            description: "Optional minimum elevation for inclusion in the analysis.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Elevation Value (optional)".to_owned(),
+            flags: vec!["--maxz".to_owned()],
+            description: "Optional maximum elevation for inclusion in the analysis.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las -o=metrics.tif --resolution=20.0 --stats='count,mean,stdev,skewness,p10,p50,p95,first_ratio' --exclude_cls='7,18'", short_exe, name).replace("*", &sep);
+
+        LidarBlockStatistics {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarBlockStatistics {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self,
+               args: Vec<String>,
+               working_directory: &'a str,
+               verbose: bool)
+               -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut stats_str = "count,min,max,mean,stdev".to_string();
+        let mut interp_parameter = "elevation".to_string();
+        let mut return_type = "all".to_string();
+        let mut grid_res: f64 = 1.0;
+        let mut include_class_vals = vec![true; 256];
+        let mut exclude_cls_str = String::new();
+        let mut max_z = f64::INFINITY;
+        let mut min_z = f64::NEG_INFINITY;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-stats" {
+                stats_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-parameter" {
+                interp_parameter = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+            } else if flag_val == "-returns" {
+                return_type = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-exclude_cls" {
+                exclude_cls_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+                let mut cmd = exclude_cls_str.split(",");
+                let mut vec = cmd.collect::<Vec<&str>>();
+                if vec.len() == 1 {
+                    cmd = exclude_cls_str.split(";");
+                    vec = cmd.collect::<Vec<&str>>();
+                }
+                for value in vec {
+                    if !value.trim().is_empty() {
+                        let c = value.trim().parse::<usize>().unwrap();
+                        include_class_vals[c] = false;
+                    }
+                }
+            } else if flag_val == "-minz" {
+                min_z = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-maxz" {
+                max_z = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        // parse the list of statistics
+        let mut stats: Vec<Statistic> = vec![];
+        for s in stats_str.split(|c| c == ',' || c == ';') {
+            if !s.trim().is_empty() {
+                match Statistic::from_str(s) {
+                    Some(stat) => stats.push(stat),
+                    None => return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unrecognized statistic '{}'.", s.trim()))),
+                }
+            }
+        }
+        if stats.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "At least one statistic must be specified."));
+        }
+
+        let interp_parameter = match interp_parameter.trim() {
+            "z" | "elevation" => "elevation",
+            "intensity" => "intensity",
+            "scan angle" | "scan_angle" => "scan angle",
+            _ => return Err(Error::new(ErrorKind::InvalidInput,
+                format!("Unrecognized point attribute '{}'; options are 'elevation', 'intensity' and 'scan angle'.", interp_parameter.trim()))),
+        };
+
+        let (all_returns, late_returns, early_returns): (bool, bool, bool);
+        if return_type.contains("last") {
+            all_returns = false;
+            late_returns = true;
+            early_returns = false;
+        } else if return_type.contains("first") {
+            all_returns = false;
+            late_returns = false;
+            early_returns = true;
+        } else {
+            // all
+            all_returns = true;
+            late_returns = false;
+            early_returns = false;
+        }
+
+        if !input_file.contains(path::MAIN_SEPARATOR) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if output_file.is_empty() {
+            output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+        }
+        if !output_file.contains(path::MAIN_SEPARATOR) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let start = time::now();
+
+        if verbose {
+            println!("Reading input LAS file...");
+        }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(_) => {
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("No such file or directory ({})", input_file)))
+            }
+        };
+
+        let start_run = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+
+        let west: f64 = input.header.min_x;
+        let north: f64 = input.header.max_y;
+        let rows: usize = ((((north - input.header.min_y) / grid_res).ceil()) as usize).max(1);
+        let columns: usize = ((((input.header.max_x - west) / grid_res).ceil()) as usize).max(1);
+        let south: f64 = north - rows as f64 * grid_res;
+        let east = west + columns as f64 * grid_res;
+        let nodata = -32768.0f64;
+
+        ////////////////////////////////////
+        // Bin the points, in a single pass
+        ////////////////////////////////////
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+        let mut bins: Vec<BinnedPoint> = Vec::with_capacity(n_points);
+        let (mut row, mut col): (usize, usize);
+        for i in 0..n_points {
+            let p: PointData = input[i];
+            if !p.class_bit_field.withheld() && include_class_vals[p.classification() as usize] &&
+                p.z >= min_z && p.z <= max_z {
+                if all_returns || (p.is_late_return() & late_returns) || (p.is_early_return() & early_returns) {
+                    row = (((north - p.y) / grid_res).floor() as usize).min(rows - 1);
+                    col = (((p.x - west) / grid_res).floor() as usize).min(columns - 1);
+                    bins.push(BinnedPoint {
+                        cell: row * columns + col,
+                        value: match interp_parameter {
+                            "intensity" => p.intensity as f64,
+                            "scan angle" => p.scan_angle as f64,
+                            _ => p.z, // elevation
+                        },
+                        is_first: p.is_early_return(),
+                        is_last: p.is_late_return(),
+                    });
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Binning points: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // sorting by cell, then value, places each cell's points in a contiguous, ordered run
+        if verbose {
+            println!("Sorting binned points...");
+        }
+        bins.sort_by(|a, b| a.cell.cmp(&b.cell).then(a.value.partial_cmp(&b.value).unwrap()));
+        let num_cells = rows * columns;
+        let mut cell_start = vec![0usize; num_cells + 1];
+        for bp in &bins {
+            cell_start[bp.cell + 1] += 1;
+        }
+        for i in 0..num_cells {
+            cell_start[i + 1] += cell_start[i];
+        }
+
+        ////////////////////////////
+        // Calculate the statistics
+        ////////////////////////////
+        let bins = Arc::new(bins);
+        let cell_start = Arc::new(cell_start);
+        let stats = Arc::new(stats);
+        let num_procs = num_cpus::get();
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let bins = bins.clone();
+            let cell_start = cell_start.clone();
+            let stats = stats.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for row in (0..rows).filter(|r| r % num_procs == tid) {
+                    let mut data = vec![vec![nodata; columns]; stats.len()];
+                    for col in 0..columns {
+                        let cell = row * columns + col;
+                        let points = &bins[cell_start[cell]..cell_start[cell + 1]];
+                        for s in 0..stats.len() {
+                            data[s][col] = stats[s].calculate(points, nodata);
+                        }
+                    }
+                    tx.send((row, data)).unwrap();
+                }
+            });
+        }
+
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.north = north;
+        configs.south = south;
+        configs.east = east;
+        configs.west = west;
+        configs.resolution_x = grid_res;
+        configs.resolution_y = grid_res;
+        configs.nodata = nodata;
+        configs.data_type = DataType::F32;
+        configs.photometric_interp = PhotometricInterpretation::Continuous;
+
+        let (stem, extension) = match output_file.rfind('.') {
+            Some(idx) => (output_file[..idx].to_string(), output_file[idx..].to_string()),
+            None => (output_file.clone(), ".tif".to_string()),
+        };
+        let mut outputs: Vec<Raster> = vec![];
+        for s in stats.iter() {
+            let file_name = format!("{}_{}{}", stem, s.name(), extension);
+            outputs.push(Raster::initialize_using_config(&file_name, &configs));
+        }
+
+        for r in 0..rows {
+            let (row, data) = rx.recv().unwrap();
+            for s in 0..data.len() {
+                outputs[s].set_row_data(row as isize, data[s].clone());
+            }
+            if verbose {
+                progress = (100.0_f64 * r as f64 / (rows - 1).max(1) as f64) as i32;
+                if progress != old_progress {
+                    println!("Calculating statistics: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end_run = time::now();
+        let elapsed_time_run = end_run - start_run;
+
+        if verbose {
+            println!("Saving data...")
+        };
+        for s in 0..outputs.len() {
+            outputs[s].add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+            outputs[s].add_metadata_entry(format!("Input file: {}", input_file));
+            outputs[s].add_metadata_entry(format!("Statistic: {} of {}", stats[s].name(), interp_parameter));
+            outputs[s].add_metadata_entry(format!("Grid resolution: {}", grid_res));
+            outputs[s].add_metadata_entry(format!("Returns: {}", return_type));
+            outputs[s].add_metadata_entry(format!("Excluded classes: {}", exclude_cls_str));
+            outputs[s].add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time_run).replace("PT", ""));
+            let _ = match outputs[s].write() {
+                Ok(_) => {
+                    if verbose {
+                        println!("Output file written: {}", outputs[s].file_name);
+                    }
+                }
+                Err(e) => return Err(e),
+            };
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+struct BinnedPoint {
+    cell: usize,
+    value: f64,
+    is_first: bool,
+    is_last: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Statistic {
+    Count,
+    Min,
+    Max,
+    Range,
+    Mean,
+    StdDev,
+    Skewness,
+    Kurtosis,
+    Percentile(f64),
+    FirstReturnRatio,
+    LastReturnRatio,
+}
+
+impl Statistic {
+    fn from_str(s: &str) -> Option<Statistic> {
+        let s = s.trim().to_lowercase();
+        match &s as &str {
+            "count" | "num_points" => Some(Statistic::Count),
+            "min" | "minimum" => Some(Statistic::Min),
+            "max" | "maximum" => Some(Statistic::Max),
+            "range" => Some(Statistic::Range),
+            "mean" | "average" => Some(Statistic::Mean),
+            "stdev" | "std_dev" | "stddev" => Some(Statistic::StdDev),
+            "skewness" | "skew" => Some(Statistic::Skewness),
+            "kurtosis" => Some(Statistic::Kurtosis),
+            "median" => Some(Statistic::Percentile(50f64)),
+            "first_ratio" => Some(Statistic::FirstReturnRatio),
+            "last_ratio" => Some(Statistic::LastReturnRatio),
+            _ => {
+                if s.starts_with("p") {
+                    match s[1..].parse::<f64>() {
+                        Ok(p) if p >= 0f64 && p <= 100f64 => Some(Statistic::Percentile(p)),
+                        _ => None,
+                    }
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        match *self {
+            Statistic::Count => "count".to_string(),
+            Statistic::Min => "min".to_string(),
+            Statistic::Max => "max".to_string(),
+            Statistic::Range => "range".to_string(),
+            Statistic::Mean => "mean".to_string(),
+            Statistic::StdDev => "stdev".to_string(),
+            Statistic::Skewness => "skewness".to_string(),
+            Statistic::Kurtosis => "kurtosis".to_string(),
+            Statistic::Percentile(p) => format!("p{}", p),
+            Statistic::FirstReturnRatio => "first_ratio".to_string(),
+            Statistic::LastReturnRatio => "last_ratio".to_string(),
+        }
+    }
+
+    /// Calculates the statistic for the points in a cell, which must be sorted by value.
+    fn calculate(&self, points: &[BinnedPoint], nodata: f64) -> f64 {
+        let n = points.len();
+        if n == 0 {
+            return match *self {
+                Statistic::Count => 0f64,
+                _ => nodata,
+            };
+        }
+        match *self {
+            Statistic::Count => n as f64,
+            Statistic::Min => points[0].value,
+            Statistic::Max => points[n - 1].value,
+            Statistic::Range => points[n - 1].value - points[0].value,
+            Statistic::Mean => points.iter().map(|p| p.value).sum::<f64>() / n as f64,
+            Statistic::StdDev | Statistic::Skewness | Statistic::Kurtosis => {
+                let mean = points.iter().map(|p| p.value).sum::<f64>() / n as f64;
+                let (mut m2, mut m3, mut m4) = (0f64, 0f64, 0f64);
+                for p in points {
+                    let d = p.value - mean;
+                    m2 += d * d;
+                    m3 += d * d * d;
+                    m4 += d * d * d * d;
+                }
+                m2 /= n as f64;
+                m3 /= n as f64;
+                m4 /= n as f64;
+                match *self {
+                    Statistic::StdDev => m2.sqrt(),
+                    Statistic::Skewness => if m2 > 0f64 { m3 / m2.powf(1.5) } else { 0f64 },
+                    _ => if m2 > 0f64 { m4 / (m2 * m2) - 3f64 } else { 0f64 },
+                }
+            },
+            Statistic::Percentile(p) => {
+                // linear interpolation between the closest ranks
+                let rank = p / 100f64 * (n - 1) as f64;
+                let lower = rank.floor() as usize;
+                let upper = rank.ceil() as usize;
+                let frac = rank - lower as f64;
+                points[lower].value + frac * (points[upper].value - points[lower].value)
+            },
+            Statistic::FirstReturnRatio => points.iter().filter(|p| p.is_first).count() as f64 / n as f64,
+            Statistic::LastReturnRatio => points.iter().filter(|p| p.is_last).count() as f64 / n as f64,
+        }
+    }
+}
@@ -0,0 +1,673 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 25, 2018
+Last Modified: January 25, 2018
+License: MIT
+
+Notes: The input file is expected to have had its ground points classified (class 2),
+       e.g. using the LidarGroundPointFilter or LidarSegmentationBasedFilter tools, unless
+       a DTM is supplied.
+*/
+extern crate time;
+extern crate nalgebra as na;
+extern crate num_cpus;
+
+use std::collections::HashSet;
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use self::na::Vector3;
+use structures::{FixedRadiusSearch3D, Point2D};
+use super::normal_vectors::plane_from_points;
+use tools::*;
+
+/// Classifies the non-ground points of a LiDAR file into buildings (class 6) and low (3),
+/// medium (4) and high (5) vegetation. Point heights above ground are measured from a DTM,
+/// if one is supplied, or else from a TIN of the ground-classified (class 2) points.
+///
+/// Points higher than `--min_building_height` are segmented into planar patches in the same
+/// way as the LidarSegmentation tool, i.e. by growing regions of neighbouring points (within
+/// `--radius`) with similar normal vectors (`--norm_diff`) and elevations (`--maxzdiff`).
+/// Segments with a plan area of at least `--min_building_area`, and whose points deviate from
+/// a best-fit plane by no more than `--max_rmse` (root-mean-square), are classified as building
+/// roofs; the latter test excludes the occasional large segment that grows through tree crowns.
+/// Segment areas are estimated from the number of grid cells, sized to the average point
+/// spacing, that they occupy. The remaining points are classified as vegetation by
+/// their heights: low vegetation is lower than `--low_veg_height`, high vegetation is higher
+/// than `--high_veg_height` and medium vegetation lies in between. Points lower than
+/// `--min_height`, as well as ground, withheld and noise (classes 7 and 18) points, keep
+/// their original classes.
+pub struct LidarBuildingVegetationClassification {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarBuildingVegetationClassification {
+    pub fn new() -> LidarBuildingVegetationClassification {
+        // public constructor
+        let name = "LidarBuildingVegetationClassification".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Classifies non-ground LiDAR points as buildings (class 6) or low, medium and high vegetation (classes 3-5)."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input DTM File (optional)".to_owned(),
+            flags: vec!["--dtm".to_owned()],
+            description: "Optional input ground surface raster; if unspecified, a TIN of the ground-classified (class 2) points is used.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Search Radius".to_owned(),
+            flags: vec!["--radius".to_owned()],
+            description: "Search radius used for normal vector estimation and segmentation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Normal Difference Threshold".to_owned(),
+            flags: vec!["--norm_diff".to_owned()],
+            description: "Maximum difference in normal vectors, in degrees, between points of a roof segment.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("5.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Elevation Difference Between Points".to_owned(),
+            flags: vec!["--maxzdiff".to_owned()],
+            description: "Maximum difference in elevation (z units) between neighbouring points of a roof segment.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Building Area".to_owned(),
+            flags: vec!["--min_building_area".to_owned()],
+            description: "Minimum plan area of a roof segment, in squared xy units.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("25.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Roof Plane RMSE".to_owned(),
+            flags: vec!["--max_rmse".to_owned()],
+            description: "Maximum root-mean-square deviation of a roof segment's points from its best-fit plane.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.25".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Building Height".to_owned(),
+            flags: vec!["--min_building_height".to_owned()],
+            description: "Minimum height above ground of building roof points.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Height".to_owned(),
+            flags: vec!["--min_height".to_owned()],
+            description: "Minimum height above ground of classified vegetation points.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.1".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Low Vegetation Upper Height".to_owned(),
+            flags: vec!["--low_veg_height".to_owned()],
+            description: "Height separating low (class 3) and medium (class 4) vegetation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.5".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "High Vegetation Lower Height".to_owned(),
+            flags: vec!["--high_veg_height".to_owned()],
+            description: "Height separating medium (class 4) and high (class 5) vegetation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --radius=2.0 --norm_diff=5.0 --min_building_area=25.0 --max_rmse=0.25 --min_building_height=2.0 --low_veg_height=0.5 --high_veg_height=2.0", short_exe, name).replace("*", &sep);
+
+        LidarBuildingVegetationClassification {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarBuildingVegetationClassification {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut dtm_file: String = "".to_string();
+        let mut search_radius = 2.0f64;
+        let mut max_norm_diff = 5.0f64;
+        let mut max_z_diff = 1.0f64;
+        let mut min_building_area = 25.0f64;
+        let mut max_rmse = 0.25f64;
+        let mut min_building_height = 2.0f64;
+        let mut min_height = 0.1f64;
+        let mut low_veg_height = 0.5f64;
+        let mut high_veg_height = 2.0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-dtm" {
+                dtm_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-radius" {
+                search_radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-norm_diff" {
+                max_norm_diff = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-maxzdiff" {
+                max_z_diff = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_building_area" {
+                min_building_area = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_rmse" {
+                max_rmse = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_building_height" {
+                min_building_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_height" {
+                min_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-low_veg_height" {
+                low_veg_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-high_veg_height" {
+                high_veg_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !dtm_file.is_empty() && !dtm_file.contains(sep) {
+            dtm_file = format!("{}{}", working_directory, dtm_file);
+        }
+        if low_veg_height > high_veg_height {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The low vegetation height must not be greater than the high vegetation height."));
+        }
+        if max_norm_diff < 0f64 { max_norm_diff = 0f64; }
+        if max_norm_diff > 90f64 { max_norm_diff = 90f64; }
+        let min_cos_norm_diff = max_norm_diff.to_radians().cos();
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        let input = Arc::new(input);
+        let num_procs = num_cpus::get();
+
+        /////////////////////////////////////////////
+        // Calculate the heights above the ground //
+        /////////////////////////////////////////////
+        let nodata = f64::NEG_INFINITY;
+        let mut heights = vec![nodata; n_points];
+        {
+            let (tx, rx) = mpsc::channel();
+            if !dtm_file.is_empty() {
+                if verbose { println!("Reading DTM raster..."); }
+                let dtm = Arc::new(Raster::new(&dtm_file, "r")?);
+                for tid in 0..num_procs {
+                    let input = input.clone();
+                    let dtm = dtm.clone();
+                    let tx = tx.clone();
+                    thread::spawn(move || {
+                        for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                            let p: PointData = input.get_point_info(i);
+                            tx.send((i, interpolate_raster_value(&dtm, p.x, p.y))).unwrap();
+                        }
+                    });
+                }
+            } else {
+                if verbose { println!("Triangulating ground points..."); }
+                let mut points: Vec<Point2D> = vec![];
+                let mut z_values: Vec<f64> = vec![];
+                for i in 0..n_points {
+                    let p: PointData = input.get_point_info(i);
+                    if p.classification() == 2 && !p.class_bit_field.withheld() {
+                        points.push(Point2D::new(p.x, p.y));
+                        z_values.push(p.z);
+                    }
+                }
+                let ground = match GroundTin::new(points, z_values) {
+                    Some(g) => Arc::new(g),
+                    None => return Err(Error::new(ErrorKind::InvalidInput,
+                        "The input file does not contain enough ground-classified (class 2) points to triangulate.")),
+                };
+                for tid in 0..num_procs {
+                    let input = input.clone();
+                    let ground = ground.clone();
+                    let tx = tx.clone();
+                    thread::spawn(move || {
+                        for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                            let p: PointData = input.get_point_info(i);
+                            tx.send((i, ground.get_elevation(p.x, p.y))).unwrap();
+                        }
+                    });
+                }
+            }
+
+            for i in 0..n_points {
+                let (idx, z) = rx.recv().unwrap();
+                if z != nodata {
+                    heights[idx] = input.get_point_info(idx).z - z;
+                }
+                if verbose {
+                    progress = (100.0_f64 * i as f64 / num_points) as i32;
+                    if progress != old_progress {
+                        println!("Calculating heights above ground: {}%", progress);
+                        old_progress = progress;
+                    }
+                }
+            }
+        }
+
+        // the points eligible for classification
+        let mut is_candidate = vec![false; n_points];
+        for i in 0..n_points {
+            let p: PointData = input.get_point_info(i);
+            let class_val = p.classification();
+            is_candidate[i] = heights[i] != nodata && heights[i] >= min_height && class_val != 2
+                && class_val != 7 && class_val != 18 && !p.class_bit_field.withheld();
+        }
+
+        //////////////////////////////////////////////////////
+        // Calculate the normals of the elevated points     //
+        //////////////////////////////////////////////////////
+        if verbose { println!("Calculating point normals..."); }
+        let mut frs: FixedRadiusSearch3D<usize> = FixedRadiusSearch3D::new(search_radius);
+        for i in 0..n_points {
+            if is_candidate[i] && heights[i] >= min_building_height {
+                let p: PointData = input.get_point_info(i);
+                frs.insert(p.x, p.y, p.z, i);
+            }
+        }
+        let frs = Arc::new(frs);
+
+        let zero_vector = Vector3 { x: 0f64, y: 0f64, z: 0f64 };
+        let mut normals = vec![zero_vector; n_points];
+        {
+            let is_candidate = Arc::new(is_candidate.clone());
+            let heights = Arc::new(heights.clone());
+            let (tx, rx) = mpsc::channel();
+            for tid in 0..num_procs {
+                let frs = frs.clone();
+                let input = input.clone();
+                let is_candidate = is_candidate.clone();
+                let heights = heights.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                        if !is_candidate[i] || heights[i] < min_building_height {
+                            tx.send((i, zero_vector)).unwrap();
+                            continue;
+                        }
+                        let p: PointData = input.get_point_info(i);
+                        let ret = frs.search(p.x, p.y, p.z);
+                        let mut data: Vec<Vector3<f64>> = Vec::with_capacity(ret.len());
+                        for j in 0..ret.len() {
+                            let pn: PointData = input.get_point_info(ret[j].0);
+                            if (pn.z - p.z).abs() < max_z_diff {
+                                data.push(Vector3 { x: pn.x, y: pn.y, z: pn.z });
+                            }
+                        }
+                        let normal = plane_from_points(&data);
+                        if normal.x.is_finite() && normal.y.is_finite() && normal.z.is_finite() {
+                            tx.send((i, normal)).unwrap();
+                        } else {
+                            tx.send((i, zero_vector)).unwrap();
+                        }
+                    }
+                });
+            }
+
+            for i in 0..n_points {
+                let (idx, normal) = rx.recv().unwrap();
+                normals[idx] = normal;
+                if verbose {
+                    progress = (100.0_f64 * i as f64 / num_points) as i32;
+                    if progress != old_progress {
+                        println!("Calculating point normals: {}%", progress);
+                        old_progress = progress;
+                    }
+                }
+            }
+        }
+
+        ////////////////////////////////////////////////
+        // Segment the elevated points into planes    //
+        ////////////////////////////////////////////////
+        if verbose { println!("Segmenting the point cloud..."); }
+        let mut segment_id = vec![0usize; n_points];
+        let mut segment_members: Vec<Vec<usize>> = vec![vec![]]; // segment zero is unused
+        let mut stack = vec![];
+        for seed in 0..n_points {
+            if segment_id[seed] != 0 || normals[seed] == zero_vector {
+                continue;
+            }
+            let current_segment = segment_members.len();
+            segment_id[seed] = current_segment;
+            let mut members = vec![seed];
+            stack.push(seed);
+            while let Some(point_id) = stack.pop() {
+                let p: PointData = input.get_point_info(point_id);
+                let ret = frs.search(p.x, p.y, p.z);
+                for j in 0..ret.len() {
+                    let index_n = ret[j].0;
+                    if segment_id[index_n] == 0 && normals[index_n] != zero_vector {
+                        let pn: PointData = input.get_point_info(index_n);
+                        // normals are unoriented, so only the absolute value of the cosine matters
+                        if (pn.z - p.z).abs() < max_z_diff && dot(&normals[point_id], &normals[index_n]).abs() >= min_cos_norm_diff {
+                            segment_id[index_n] = current_segment;
+                            members.push(index_n);
+                            stack.push(index_n);
+                        }
+                    }
+                }
+            }
+            segment_members.push(members);
+            if verbose {
+                progress = (100.0_f64 * seed as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Segmenting the point cloud: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // The plan area of each segment is estimated from the number of occupied grid cells,
+        // with the cell size equal to the average point spacing.
+        let extent_area = (input.header.max_x - input.header.min_x) * (input.header.max_y - input.header.min_y);
+        let cell_size = (extent_area / n_points as f64).sqrt().max(1e-6);
+        let mut is_building = vec![false; segment_members.len()];
+        let mut num_buildings = 0usize;
+        for s in 1..segment_members.len() {
+            if (segment_members[s].len() as f64) * cell_size * cell_size < min_building_area {
+                continue; // it can't possibly be large enough
+            }
+            let mut cells = HashSet::new();
+            let mut data: Vec<Vector3<f64>> = Vec::with_capacity(segment_members[s].len());
+            for &i in &segment_members[s] {
+                let p: PointData = input.get_point_info(i);
+                cells.insert(((p.x / cell_size).floor() as isize, (p.y / cell_size).floor() as isize));
+                data.push(Vector3 { x: p.x, y: p.y, z: p.z });
+            }
+            if (cells.len() as f64) * cell_size * cell_size < min_building_area {
+                continue;
+            }
+
+            // planarity test
+            let normal = plane_from_points(&data);
+            let n = data.len() as f64;
+            let centroid = Vector3 {
+                x: data.iter().map(|v| v.x).sum::<f64>() / n,
+                y: data.iter().map(|v| v.y).sum::<f64>() / n,
+                z: data.iter().map(|v| v.z).sum::<f64>() / n,
+            };
+            let mut sum_sq = 0f64;
+            for v in &data {
+                let d = (v.x - centroid.x) * normal.x + (v.y - centroid.y) * normal.y + (v.z - centroid.z) * normal.z;
+                sum_sq += d * d;
+            }
+            if (sum_sq / n).sqrt() <= max_rmse {
+                is_building[s] = true;
+                num_buildings += 1;
+            }
+        }
+
+        /////////////////////
+        // Output the data //
+        /////////////////////
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+
+        let (mut num_building_pts, mut num_low, mut num_medium, mut num_high) = (0usize, 0usize, 0usize, 0usize);
+        for i in 0..n_points {
+            if !is_candidate[i] {
+                output.add_point_record(input.get_record(i));
+                continue;
+            }
+            let class_val = if is_building[segment_id[i]] {
+                num_building_pts += 1;
+                6u8
+            } else if heights[i] < low_veg_height {
+                num_low += 1;
+                3u8
+            } else if heights[i] <= high_veg_height {
+                num_medium += 1;
+                4u8
+            } else {
+                num_high += 1;
+                5u8
+            };
+            let pr2: LidarPointRecord;
+            match input.get_record(i) {
+                LidarPointRecord::PointRecord0 { mut point_data }  => {
+                    point_data.set_classification(class_val);
+                    pr2 = LidarPointRecord::PointRecord0 { point_data: point_data };
+                },
+                LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
+                    point_data.set_classification(class_val);
+                    pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
+                },
+                LidarPointRecord::PointRecord2 { mut point_data, rgb_data } => {
+                    point_data.set_classification(class_val);
+                    pr2 = LidarPointRecord::PointRecord2 { point_data: point_data, rgb_data: rgb_data };
+                },
+                LidarPointRecord::PointRecord3 { mut point_data, gps_data, rgb_data } => {
+                    point_data.set_classification(class_val);
+                    pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
+                        gps_data: gps_data, rgb_data: rgb_data};
+                },
+            }
+            output.add_point_record(pr2);
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if verbose {
+            println!("Number of building segments: {}", num_buildings);
+            println!("Building points (class 6): {}", num_building_pts);
+            println!("Low vegetation points (class 3): {}", num_low);
+            println!("Medium vegetation points (class 4): {}", num_medium);
+            println!("High vegetation points (class 5): {}", num_high);
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[inline]
+fn dot(a: &Vector3<f64>, b: &Vector3<f64>) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
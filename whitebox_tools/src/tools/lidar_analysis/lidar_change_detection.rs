@@ -0,0 +1,587 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 23, 2018
+Last Modified: January 23, 2018
+License: MIT
+*/
+extern crate time;
+extern crate nalgebra as na;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::mem;
+use std::path;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use self::na::Vector3;
+use structures::KdTree;
+use structures::kd_tree::squared_euclidean;
+use super::normal_vectors::plane_from_points;
+use tools::*;
+
+/// Measures the change between two epochs of LiDAR data, calculating a distance for each
+/// point in the input (reference) file to the comparison file. Two methods are available:
+///
+/// * `c2c`: the cloud-to-cloud distance, i.e. the (unsigned) 3D distance to the nearest
+///   point in the comparison cloud.
+/// * `m3c2`: the Multiscale Model to Model Cloud Comparison method of Lague et al. (2013).
+///   A normal vector is fitted to the reference points within `--normal_radius` of each
+///   point and oriented upwards. The points of each epoch within a cylinder of radius
+///   `--proj_radius` and half-length `--max_depth`, centred on the point and aligned with the
+///   normal, are projected onto the cylinder axis and the signed distance is the difference
+///   in their mean positions (positive where the comparison surface lies above the reference
+///   surface). A 95% level of detection (`lod95 = 1.96 * sqrt(s1^2/n1 + s2^2/n2) + reg`)
+///   is also calculated, where `reg` is the registration error; changes smaller than this
+///   confidence interval are not statistically significant.
+///
+/// The output LAS file contains each input point along with a 'distance' (and for M3C2, a
+/// 'lod95') extra bytes attribute. Points for which a distance could not be calculated,
+/// e.g. because either cylinder contains fewer than `--min_points` points, are assigned
+/// -32768. If `--output_raster` is specified, the mean distance of the points within each grid
+/// cell is also output as a raster.
+pub struct LidarChangeDetection {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarChangeDetection {
+    pub fn new() -> LidarChangeDetection {
+        // public constructor
+        let name = "LidarChangeDetection".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Calculates point distances between two epochs of LiDAR data using the cloud-to-cloud (C2C) or M3C2 method."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input (Reference) File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file of the earlier (reference) epoch.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Comparison File".to_owned(),
+            flags: vec!["--compare".to_owned()],
+            description: "Input LiDAR file of the later (comparison) epoch.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Method".to_owned(),
+            flags: vec!["--method".to_owned()],
+            description: "Distance method; options are 'c2c' (nearest neighbour) and 'm3c2' (default).".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["c2c".to_owned(), "m3c2".to_owned()]),
+            default_value: Some("m3c2".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Normal Radius".to_owned(),
+            flags: vec!["--normal_radius".to_owned()],
+            description: "Search radius used to fit the M3C2 normal vectors.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Projection Radius".to_owned(),
+            flags: vec!["--proj_radius".to_owned()],
+            description: "Radius of the M3C2 projection cylinder.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Depth".to_owned(),
+            flags: vec!["--max_depth".to_owned()],
+            description: "Half-length of the M3C2 projection cylinder, i.e. the maximum detectable change.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("5.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Points per Cylinder".to_owned(),
+            flags: vec!["--min_points".to_owned()],
+            description: "Minimum number of points of each epoch within the M3C2 projection cylinder.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("3".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Registration Error".to_owned(),
+            flags: vec!["--reg_error".to_owned()],
+            description: "Registration error between the two epochs, added to the M3C2 level of detection.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Change Raster (optional)".to_owned(),
+            flags: vec!["--output_raster".to_owned()],
+            description: "Optional output raster of the mean distance within each grid cell.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"2016.las\" --compare=\"2017.las\" -o=\"change.las\" --method=m3c2 --normal_radius=2.0 --proj_radius=1.0 --max_depth=5.0 --reg_error=0.05 --output_raster=\"change.tif\" --resolution=1.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"2016.las\" --compare=\"2017.las\" -o=\"change.las\" --method=c2c", short_exe, name).replace("*", &sep);
+
+        LidarChangeDetection {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarChangeDetection {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut compare_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut raster_file: String = "".to_string();
+        let mut use_m3c2 = true;
+        let mut normal_radius = 2.0f64;
+        let mut proj_radius = 1.0f64;
+        let mut max_depth = 5.0f64;
+        let mut min_points = 3usize;
+        let mut reg_error = 0.0f64;
+        let mut grid_res = 1.0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-compare" {
+                compare_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-method" {
+                let method = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+                use_m3c2 = !method.contains("c2c");
+            } else if flag_val == "-normal_radius" {
+                normal_radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-proj_radius" {
+                proj_radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_depth" {
+                max_depth = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_points" {
+                min_points = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap() as usize
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap() as usize
+                };
+            } else if flag_val == "-reg_error" {
+                reg_error = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-output_raster" {
+                raster_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !compare_file.contains(sep) {
+            compare_file = format!("{}{}", working_directory, compare_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !raster_file.is_empty() && !raster_file.contains(sep) {
+            raster_file = format!("{}{}", working_directory, raster_file);
+        }
+        if grid_res <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The grid resolution must be greater than zero."));
+        }
+        if min_points < 1 {
+            min_points = 1;
+        }
+
+        if verbose { println!("Reading input LAS files..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+        let compare = match LasFile::new(&compare_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", compare_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        if verbose { println!("Building search trees..."); }
+        let mut tree_ref = KdTree::new_with_capacity(3, 64);
+        if use_m3c2 {
+            for i in 0..n_points {
+                let p: PointData = input.get_point_info(i);
+                if !p.class_bit_field.withheld() {
+                    tree_ref.add([p.x, p.y, p.z], i).unwrap();
+                }
+            }
+        }
+        let n_compare = compare.header.number_of_points as usize;
+        let mut tree_cmp = KdTree::new_with_capacity(3, 64);
+        for i in 0..n_compare {
+            let p: PointData = compare.get_point_info(i);
+            if !p.class_bit_field.withheld() {
+                tree_cmp.add([p.x, p.y, p.z], i).unwrap();
+            }
+        }
+        if tree_cmp.size() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The comparison file does not contain any points."));
+        }
+
+        let nodata = -32768f64;
+        let mut distances = vec![nodata; n_points];
+        let mut lods = vec![nodata; n_points];
+
+        let input = Arc::new(input);
+        let compare = Arc::new(compare);
+        let tree_ref = Arc::new(tree_ref);
+        let tree_cmp = Arc::new(tree_cmp);
+        let num_procs = num_cpus::get();
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let input = input.clone();
+            let compare = compare.clone();
+            let tree_ref = tree_ref.clone();
+            let tree_cmp = tree_cmp.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let sq_normal_radius = normal_radius * normal_radius;
+                let sq_proj_radius = proj_radius * proj_radius;
+                // the radius of the sphere enclosing the projection cylinder
+                let sq_search_radius = sq_proj_radius + max_depth * max_depth;
+                for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                    let p: PointData = input.get_point_info(i);
+                    if !use_m3c2 {
+                        let ret = tree_cmp.nearest(&[p.x, p.y, p.z], 1, &squared_euclidean).unwrap();
+                        tx.send((i, ret[0].0.sqrt(), nodata)).unwrap();
+                        continue;
+                    }
+
+                    // fit the normal to the neighbouring reference points
+                    let ret = tree_ref.within(&[p.x, p.y, p.z], sq_normal_radius, &squared_euclidean).unwrap();
+                    let mut data: Vec<Vector3<f64>> = Vec::with_capacity(ret.len());
+                    for &(_, &j) in &ret {
+                        let p2: PointData = input.get_point_info(j);
+                        data.push(Vector3 { x: p2.x, y: p2.y, z: p2.z });
+                    }
+                    let mut normal = plane_from_points(&data);
+                    if ret.len() < 3 || !(normal.x.is_finite() && normal.y.is_finite() && normal.z.is_finite()) {
+                        tx.send((i, nodata, nodata)).unwrap();
+                        continue;
+                    }
+                    if normal.z < 0f64 {
+                        normal = -normal;
+                    }
+
+                    let (n1, mean1, var1) = cylinder_stats(&input, &tree_ref, &p, &normal, sq_search_radius, sq_proj_radius, max_depth);
+                    let (n2, mean2, var2) = cylinder_stats(&compare, &tree_cmp, &p, &normal, sq_search_radius, sq_proj_radius, max_depth);
+                    if n1 < min_points || n2 < min_points {
+                        tx.send((i, nodata, nodata)).unwrap();
+                        continue;
+                    }
+                    let lod = 1.96 * (var1 / n1 as f64 + var2 / n2 as f64).sqrt() + reg_error;
+                    tx.send((i, mean2 - mean1, lod)).unwrap();
+                }
+            });
+        }
+
+        for i in 0..n_points {
+            let (idx, dist, lod) = rx.recv().unwrap();
+            distances[idx] = dist;
+            lods[idx] = lod;
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Calculating distances: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let mut num_resolved = 0usize;
+        let mut num_significant = 0usize;
+        for i in 0..n_points {
+            if distances[i] != nodata {
+                num_resolved += 1;
+                if use_m3c2 && distances[i].abs() > lods[i] {
+                    num_significant += 1;
+                }
+            }
+        }
+        if verbose {
+            println!("Distances were calculated for {} of {} points.", num_resolved, n_points);
+            if use_m3c2 {
+                println!("{} points have a significant change (|distance| > lod95).", num_significant);
+            }
+        }
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        output.add_extra_bytes_attribute("distance", "Change distance", ExtraBytesDataType::F64)?;
+        if use_m3c2 {
+            output.add_extra_bytes_attribute("lod95", "M3C2 95% level of detection", ExtraBytesDataType::F64)?;
+        }
+        for i in 0..n_points {
+            output.add_point_record(input.get_record(i));
+            let bytes: [u8; 8] = unsafe { mem::transmute(distances[i]) };
+            output.add_extra_bytes(&bytes);
+            if use_m3c2 {
+                let bytes: [u8; 8] = unsafe { mem::transmute(lods[i]) };
+                output.add_extra_bytes(&bytes);
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if !raster_file.is_empty() {
+            let west = input.header.min_x;
+            let north = input.header.max_y;
+            let rows = ((((north - input.header.min_y) / grid_res).ceil()) as usize).max(1);
+            let columns = ((((input.header.max_x - west) / grid_res).ceil()) as usize).max(1);
+
+            let mut configs = RasterConfigs { ..Default::default() };
+            configs.rows = rows;
+            configs.columns = columns;
+            configs.north = north;
+            configs.south = north - rows as f64 * grid_res;
+            configs.east = west + columns as f64 * grid_res;
+            configs.west = west;
+            configs.resolution_x = grid_res;
+            configs.resolution_y = grid_res;
+            configs.nodata = nodata;
+            configs.data_type = DataType::F32;
+            configs.photometric_interp = PhotometricInterpretation::Continuous;
+            let mut raster = Raster::initialize_using_config(&raster_file, &configs);
+
+            let mut sum = vec![0f64; rows * columns];
+            let mut count = vec![0usize; rows * columns];
+            for i in 0..n_points {
+                if distances[i] != nodata {
+                    let p: PointData = input.get_point_info(i);
+                    let row = (((north - p.y) / grid_res).floor() as usize).min(rows - 1);
+                    let col = (((p.x - west) / grid_res).floor() as usize).min(columns - 1);
+                    sum[row * columns + col] += distances[i];
+                    count[row * columns + col] += 1;
+                }
+            }
+            for row in 0..rows {
+                for col in 0..columns {
+                    if count[row * columns + col] > 0 {
+                        raster.set_value(row as isize, col as isize, sum[row * columns + col] / count[row * columns + col] as f64);
+                    }
+                }
+            }
+
+            raster.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+            raster.add_metadata_entry(format!("Reference file: {}", input_file));
+            raster.add_metadata_entry(format!("Comparison file: {}", compare_file));
+            raster.add_metadata_entry(format!("Method: {}", if use_m3c2 { "M3C2" } else { "C2C" }));
+            raster.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+            if verbose { println!("Writing output raster..."); }
+            let _ = match raster.write() {
+                Ok(_) => if verbose { println!("Output raster written") },
+                Err(e) => return Err(e),
+            };
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the number of points within an M3C2 projection cylinder, along with the mean
+/// and (sample) variance of their positions along the cylinder axis.
+fn cylinder_stats(las: &LasFile, tree: &KdTree<usize, [f64; 3]>, p: &PointData, normal: &Vector3<f64>,
+    sq_search_radius: f64, sq_proj_radius: f64, max_depth: f64) -> (usize, f64, f64) {
+    let ret = tree.within(&[p.x, p.y, p.z], sq_search_radius, &squared_euclidean).unwrap();
+    let mut along = Vec::with_capacity(ret.len());
+    for &(sq_dist, &j) in &ret {
+        let q: PointData = las.get_point_info(j);
+        let t = (q.x - p.x) * normal.x + (q.y - p.y) * normal.y + (q.z - p.z) * normal.z;
+        if t.abs() <= max_depth && sq_dist - t * t <= sq_proj_radius {
+            along.push(t);
+        }
+    }
+    let n = along.len();
+    if n == 0 {
+        return (0, 0f64, 0f64);
+    }
+    let mean = along.iter().sum::<f64>() / n as f64;
+    let var = if n > 1 {
+        along.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / (n - 1) as f64
+    } else {
+        0f64
+    };
+    (n, mean, var)
+}
@@ -0,0 +1,363 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 29, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path;
+use std::io::{Error, ErrorKind};
+use lidar::*;
+use raster::*;
+use tools::*;
+use structures::Point2D;
+use vector::{is_vector_file, Shapefile, ShapefileGeometry, ShapeType};
+
+/// Clips a LiDAR file to the area within a set of polygons, or to the valid (non-nodata)
+/// cells of a raster mask. Polygons may be read from a Shapefile (polygon, polygonZ and
+/// polygonM shape types), a text file containing one or more WKT POLYGON or MULTIPOLYGON
+/// geometries, or a GeoJSON file (.geojson or .json) containing Polygon or MultiPolygon
+/// geometries. Polygon holes are respected and a point need only fall within one of several
+/// polygons to be retained. The `--invert` flag retains the points outside of the clip area
+/// instead.
+pub struct LidarClip {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarClip {
+    pub fn new() -> LidarClip {
+        // public constructor
+        let name = "LidarClip".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Clips a LiDAR file to a polygon (Shapefile, WKT or GeoJSON) or to the valid cells of a raster mask."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input Polygon File".to_owned(),
+            flags: vec!["--polygons".to_owned()],
+            description: "Input clip polygon file (Shapefile, WKT text or GeoJSON); required unless a mask is specified.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input Raster Mask".to_owned(),
+            flags: vec!["--mask".to_owned()],
+            description: "Input raster mask; points overlying nodata cells are clipped.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Retain the points outside of the clip area?".to_owned(),
+            flags: vec!["--invert".to_owned()],
+            description: "Optional boolean flag indicating that points outside of the clip area are retained.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" --polygons=\"watershed.shp\" -o=\"output.las\"
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" --mask=\"mask.tif\" -o=\"output.las\" --invert", short_exe, name).replace("*", &sep);
+
+        LidarClip {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarClip {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut polygon_file: String = "".to_string();
+        let mut mask_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut invert = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-polygons" {
+                polygon_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-mask" {
+                mask_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-invert" {
+                invert = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if polygon_file.is_empty() == mask_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Either a polygon file or a raster mask, but not both, must be specified."));
+        }
+        if !polygon_file.is_empty() && !polygon_file.contains(sep) {
+            polygon_file = format!("{}{}", working_directory, polygon_file);
+        }
+        if !mask_file.is_empty() && !mask_file.contains(sep) {
+            mask_file = format!("{}{}", working_directory, mask_file);
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let clip_area = if !polygon_file.is_empty() {
+            if verbose { println!("Reading clip polygons..."); }
+            let polygons = read_polygons(&polygon_file)?;
+            if polygons.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput, "The polygon file does not contain any polygons."));
+            }
+            ClipArea::Polygons(polygons)
+        } else {
+            if verbose { println!("Reading raster mask..."); }
+            ClipArea::Mask(Raster::new(&mask_file, "r")?)
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        let mut num_retained = 0usize;
+        for i in 0..n_points {
+            let p: PointData = input.get_point_info(i);
+            if clip_area.contains(p.x, p.y) != invert {
+                output.add_point_record(input.get_record(i));
+                num_retained += 1;
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Clipping points: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if num_retained == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "No points were retained; the output file was not created."));
+        }
+        if verbose {
+            println!("{} of {} points were retained.", num_retained, n_points);
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+enum ClipArea {
+    Polygons(Vec<ShapefileGeometry>),
+    Mask(Raster),
+}
+
+impl ClipArea {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        match *self {
+            ClipArea::Polygons(ref polygons) => {
+                let p = Point2D::new(x, y);
+                polygons.iter().any(|poly| poly.contains(&p))
+            },
+            ClipArea::Mask(ref mask) => {
+                let row = mask.get_row_from_y(y);
+                let col = mask.get_column_from_x(x);
+                // get_value returns nodata beyond the raster's edges
+                mask.get_value(row, col) != mask.configs.nodata
+            },
+        }
+    }
+}
+
+fn read_polygons(file_name: &str) -> Result<Vec<ShapefileGeometry>, Error> {
+    if is_vector_file(file_name) {
+        return read_vector_polygons(file_name);
+    }
+    let mut f = File::open(file_name)?;
+    let mut buffer = vec![];
+    f.read_to_end(&mut buffer)?;
+    Ok(read_wkt_polygons(&String::from_utf8_lossy(&buffer)))
+}
+
+fn read_vector_polygons(file_name: &str) -> Result<Vec<ShapefileGeometry>, Error> {
+    let input = Shapefile::new(file_name, "r")?;
+    if input.header.shape_type.base_shape_type() != ShapeType::Polygon {
+        return Err(Error::new(ErrorKind::InvalidInput, "The input vector file must contain polygons."));
+    }
+    Ok(input.records.into_iter().filter(|record| record.shape_type != ShapeType::Null).collect())
+}
+
+/// Reads the WKT POLYGON and MULTIPOLYGON geometries in a text, each polygon becoming a
+/// separate geometry. Rings are the innermost parenthesized groups and a polygon ends with
+/// the group enclosing its rings.
+fn read_wkt_polygons(text: &str) -> Vec<ShapefileGeometry> {
+    let mut polygons = vec![];
+    let mut polygon = ShapefileGeometry::new(ShapeType::Polygon);
+    let mut depth = 0usize;
+    let mut ring_depth = 0usize;
+    let mut ring_start = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => {
+                depth += 1;
+                ring_start = i + 1;
+            },
+            ')' if depth > 0 => {
+                if ring_start > 0 {
+                    // an innermost group
+                    let ring: Vec<Point2D> = text[ring_start..i].split(',').filter_map(|pt| {
+                        let vals: Vec<f64> = pt.split_whitespace().filter_map(|s| s.parse::<f64>().ok()).collect();
+                        if vals.len() >= 2 { Some(Point2D::new(vals[0], vals[1])) } else { None }
+                    }).collect();
+                    polygon.add_part(&ring);
+                    ring_depth = depth;
+                    ring_start = 0;
+                } else if depth + 1 == ring_depth && polygon.num_parts > 0 {
+                    polygons.push(polygon);
+                    polygon = ShapefileGeometry::new(ShapeType::Polygon);
+                }
+                depth -= 1;
+            },
+            _ => {},
+        }
+    }
+    polygons
+}
@@ -0,0 +1,434 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 31, 2018
+Last Modified: February 22, 2018
+License: MIT
+
+Notes: The input point heights must be relative to the ground surface, e.g. the output
+       of the LidarHeightNormalization tool.
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path;
+use lidar::*;
+use raster::*;
+use tools::*;
+
+/// Calculates area-based forest structure metrics from a height-normalized LiDAR file,
+/// outputting one raster per metric, named `<output stem>_<metric><extension>`:
+///
+/// * `cover`: canopy cover, the proportion of first (including only) returns higher than
+///   `--height_threshold`.
+/// * `gap_first`: the first-return gap fraction, i.e. the proportion of first returns at or
+///   below the threshold (one minus the canopy cover).
+/// * `gap_all`: the all-return gap fraction, the proportion of all returns at or below the threshold.
+/// * `lai`: the effective leaf area index, estimated from the gap fraction using the Beer-Lambert
+///   law, `LAIe = -ln(P) / k`, where `k` is the extinction coefficient (`--k`). The gap fraction
+///   `P` is calculated from return ratios, with each return weighted by the reciprocal of the
+///   number of returns of its pulse, such that each pulse contributes equally.
+/// * `density_<lower>_<upper>`: the vertical density profile, i.e. the proportion of all returns
+///   within each height stratum (`--strata`); the final stratum is unbounded above.
+///
+/// Withheld and noise (classes 7 and 18) points are excluded and cells without first returns
+/// are assigned nodata.
+pub struct LidarForestMetrics {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarForestMetrics {
+    pub fn new() -> LidarForestMetrics {
+        // public constructor
+        let name = "LidarForestMetrics".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Calculates canopy cover, gap fraction, vertical density profile and effective LAI rasters from a height-normalized LiDAR file."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input height-normalized LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file; the metric name is appended to the file stem of each output.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("10.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Canopy Height Threshold".to_owned(),
+            flags: vec!["--height_threshold".to_owned()],
+            description: "Height above which returns are considered canopy.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Height Strata".to_owned(),
+            flags: vec!["--strata".to_owned()],
+            description: "Comma-separated, ascending lower bounds of the vertical density profile strata.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("0,0.5,2,5,10,15,20,30".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Extinction Coefficient".to_owned(),
+            flags: vec!["--k".to_owned()],
+            description: "Extinction coefficient used to estimate effective LAI.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.5".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"normalized.las\" -o=\"forest.tif\" --resolution=20.0 --height_threshold=2.0 --strata=\"0,2,5,10,20\" --k=0.5", short_exe, name).replace("*", &sep);
+
+        LidarForestMetrics {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarForestMetrics {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut grid_res = 10.0f64;
+        let mut height_threshold = 2.0f64;
+        let mut strata_str = "0,0.5,2,5,10,15,20,30".to_string();
+        let mut k = 0.5f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-height_threshold" {
+                height_threshold = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-strata" {
+                strata_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-k" {
+                k = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        // parse the strata lower bounds
+        let mut strata: Vec<f64> = vec![];
+        for s in strata_str.split(|c| c == ',' || c == ';') {
+            if !s.trim().is_empty() {
+                match s.trim().parse::<f64>() {
+                    Ok(v) => strata.push(v),
+                    Err(_) => return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unrecognized stratum height '{}'.", s.trim()))),
+                }
+            }
+        }
+        for s in 1..strata.len() {
+            if strata[s] <= strata[s - 1] {
+                return Err(Error::new(ErrorKind::InvalidInput, "The strata heights must be in ascending order."));
+            }
+        }
+        if grid_res <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The grid resolution must be greater than zero."));
+        }
+        if k <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The extinction coefficient must be greater than zero."));
+        }
+
+        if !input_file.contains(path::MAIN_SEPARATOR) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(path::MAIN_SEPARATOR) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let start = time::now();
+
+        if verbose {
+            println!("Reading input LAS file...");
+        }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(_) => {
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("No such file or directory ({})", input_file)))
+            }
+        };
+
+        let start_run = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+
+        let west: f64 = input.header.min_x;
+        let north: f64 = input.header.max_y;
+        let rows: usize = ((((north - input.header.min_y) / grid_res).ceil()) as usize).max(1);
+        let columns: usize = ((((input.header.max_x - west) / grid_res).ceil()) as usize).max(1);
+        let south: f64 = north - rows as f64 * grid_res;
+        let east = west + columns as f64 * grid_res;
+        let nodata = -32768.0f64;
+        let num_cells = rows * columns;
+
+        ///////////////////////////////////////
+        // Accumulate the per-cell counts
+        ///////////////////////////////////////
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+        let mut num_first = vec![0usize; num_cells];
+        let mut num_first_canopy = vec![0usize; num_cells];
+        let mut num_all = vec![0usize; num_cells];
+        let mut num_all_below = vec![0usize; num_cells];
+        let mut weight_all = vec![0f64; num_cells];
+        let mut weight_below = vec![0f64; num_cells];
+        let mut num_stratum = vec![vec![0usize; num_cells]; strata.len()];
+        let (mut row, mut col, mut cell): (usize, usize, usize);
+        for i in 0..n_points {
+            let p: PointData = input[i];
+            if !p.class_bit_field.withheld() && !p.is_classified_noise() {
+                row = (((north - p.y) / grid_res).floor() as usize).min(rows - 1);
+                col = (((p.x - west) / grid_res).floor() as usize).min(columns - 1);
+                cell = row * columns + col;
+                let is_canopy = p.z > height_threshold;
+                if p.is_early_return() {
+                    num_first[cell] += 1;
+                    if is_canopy {
+                        num_first_canopy[cell] += 1;
+                    }
+                }
+                num_all[cell] += 1;
+                // a point with a number of returns of zero is treated as a single return
+                let weight = 1f64 / p.number_of_returns().max(1) as f64;
+                weight_all[cell] += weight;
+                if !is_canopy {
+                    num_all_below[cell] += 1;
+                    weight_below[cell] += weight;
+                }
+                // the highest stratum whose lower bound is not above the point
+                if let Some(s) = strata.iter().rposition(|lower| p.z >= *lower) {
+                    num_stratum[s][cell] += 1;
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Binning points: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        //////////////////////////////
+        // Calculate the metrics
+        //////////////////////////////
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.north = north;
+        configs.south = south;
+        configs.east = east;
+        configs.west = west;
+        configs.resolution_x = grid_res;
+        configs.resolution_y = grid_res;
+        configs.nodata = nodata;
+        configs.data_type = DataType::F32;
+        configs.photometric_interp = PhotometricInterpretation::Continuous;
+
+        let (stem, extension) = match output_file.rfind('.') {
+            Some(idx) => (output_file[..idx].to_string(), output_file[idx..].to_string()),
+            None => (output_file.clone(), ".tif".to_string()),
+        };
+        let mut metric_names = vec!["cover".to_string(), "gap_first".to_string(), "gap_all".to_string(), "lai".to_string()];
+        for s in 0..strata.len() {
+            if s < strata.len() - 1 {
+                metric_names.push(format!("density_{}_{}", strata[s], strata[s + 1]));
+            } else {
+                metric_names.push(format!("density_{}_max", strata[s]));
+            }
+        }
+        let mut outputs: Vec<Raster> = vec![];
+        for m in &metric_names {
+            let file_name = format!("{}_{}{}", stem, m, extension);
+            outputs.push(Raster::initialize_using_config(&file_name, &configs));
+        }
+
+        for row in 0..rows {
+            for col in 0..columns {
+                let cell = row * columns + col;
+                if num_first[cell] == 0 {
+                    continue;
+                }
+                let cover = num_first_canopy[cell] as f64 / num_first[cell] as f64;
+                outputs[0].set_value(row as isize, col as isize, cover);
+                outputs[1].set_value(row as isize, col as isize, 1f64 - cover);
+                outputs[2].set_value(row as isize, col as isize, num_all_below[cell] as f64 / num_all[cell] as f64);
+                // A gap fraction of zero implies an infinite LAI; it is instead limited by the
+                // smallest measurable gap fraction, i.e. half of a single pulse.
+                let gap = (weight_below[cell] / weight_all[cell]).max(0.5 / weight_all[cell]).min(1f64);
+                outputs[3].set_value(row as isize, col as isize, -gap.ln() / k);
+                for s in 0..strata.len() {
+                    outputs[4 + s].set_value(row as isize, col as isize, num_stratum[s][cell] as f64 / num_all[cell] as f64);
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * row as f64 / (rows - 1).max(1) as f64) as i32;
+                if progress != old_progress {
+                    println!("Calculating metrics: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end_run = time::now();
+        let elapsed_time_run = end_run - start_run;
+
+        if verbose {
+            println!("Saving data...")
+        };
+        for m in 0..outputs.len() {
+            outputs[m].add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+            outputs[m].add_metadata_entry(format!("Input file: {}", input_file));
+            outputs[m].add_metadata_entry(format!("Metric: {}", metric_names[m]));
+            outputs[m].add_metadata_entry(format!("Grid resolution: {}", grid_res));
+            outputs[m].add_metadata_entry(format!("Canopy height threshold: {}", height_threshold));
+            if m == 3 {
+                outputs[m].add_metadata_entry(format!("Extinction coefficient: {}", k));
+            }
+            outputs[m].add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time_run).replace("PT", ""));
+            let _ = match outputs[m].write() {
+                Ok(_) => {
+                    if verbose {
+                        println!("Output file written: {}", outputs[m].file_name);
+                    }
+                }
+                Err(e) => return Err(e),
+            };
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: June 2, 2017
-Last Modified: December 15, 2017
+Last Modified: February 22, 2018
 License: MIT
 */
 extern crate time;
@@ -38,10 +38,10 @@ impl LidarGroundPointFilter {
         parameters.push(ToolParameter{
             name: "Input File".to_owned(), 
             flags: vec!["-i".to_owned(), "--input".to_owned()], 
-            description: "Input LiDAR file.".to_owned(),
+            description: "Input LiDAR file; if unspecified, all of the LAS files in the working directory are filtered, excluding the *_ground.las outputs of earlier runs.".to_owned(),
             parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
             default_value: None,
-            optional: false
+            optional: true
         });
 
         parameters.push(ToolParameter{
@@ -50,7 +50,7 @@ impl LidarGroundPointFilter {
             description: "Output LiDAR file.".to_owned(),
             parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
             default_value: None,
-            optional: false
+            optional: true
         });
 
         parameters.push(ToolParameter{
@@ -80,6 +80,15 @@ impl LidarGroundPointFilter {
             optional: true
         });
 
+        parameters.push(ToolParameter{
+            name: "Tile Buffer Distance (optional)".to_owned(), 
+            flags: vec!["--buffer".to_owned()], 
+            description: "Optional width of the buffer of points drawn from adjacent tiles when filtering all of the LAS files in the working directory; defaults to twice the search radius.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
         let sep: String = path::MAIN_SEPARATOR.to_string();
         let p = format!("{}", env::current_dir().unwrap().display());
         let e = format!("{}", env::current_exe().unwrap().display());
@@ -87,7 +96,8 @@ impl LidarGroundPointFilter {
         if e.contains(".exe") {
             short_exe += ".exe";
         }
-        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --radius=10.0", short_exe, name).replace("*", &sep);
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --radius=10.0
+>>.*{0} -r={1} -v --wd=\"*path*to*tiles*\" --radius=10.0 --buffer=25.0", short_exe, name).replace("*", &sep);
     
         LidarGroundPointFilter { 
             name: name, 
@@ -140,6 +150,7 @@ impl WhiteboxTool for LidarGroundPointFilter {
         let mut search_radius: f64 = -1.0;
         let mut height_threshold: f64 = 1.0;
         let mut slope_threshold: f64 = 15.0;
+        let mut buffer: f64 = -1.0;
         
         // read the arguments
         if args.len() == 0 {
@@ -176,6 +187,12 @@ impl WhiteboxTool for LidarGroundPointFilter {
                 } else {
                     height_threshold = args[i+1].to_string().parse::<f64>().unwrap();
                 }
+            } else if vec[0].to_lowercase() == "-buffer" || vec[0].to_lowercase() == "--buffer" {
+                if keyval {
+                    buffer = vec[1].to_string().parse::<f64>().unwrap();
+                } else {
+                    buffer = args[i+1].to_string().parse::<f64>().unwrap();
+                }
             } else if vec[0].to_lowercase() == "-slope_threshold" || vec[0].to_lowercase() == "--slope_threshold" {
                 if keyval {
                     slope_threshold = vec[1].to_string().parse::<f64>().unwrap();
@@ -191,6 +208,46 @@ impl WhiteboxTool for LidarGroundPointFilter {
             println!("***************{}", "*".repeat(self.get_tool_name().len()));
         }
 
+        if input_file.is_empty() {
+            if working_directory.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "This tool must be run by specifying either an individual input file or a working directory."));
+            }
+            // The erosion and dilation each reach one search radius beyond a point.
+            if buffer < 0f64 {
+                buffer = 2f64 * search_radius;
+            }
+            let start = time::now();
+            if verbose { println!("Reading LAS tile headers..."); }
+            let mut catalog = LasCatalog::new(working_directory)?;
+            // the outputs of earlier runs are not filtered again
+            catalog.tiles.retain(|tile| !is_ground_output_file(&tile.file_name));
+            if verbose {
+                println!("Filtering {} tiles using a {} buffer...", catalog.len(), buffer);
+            }
+            let slope_threshold = slope_threshold.to_radians().tan();
+            catalog.process_tiles(buffer, verbose, move |tile| {
+                let output_file = ground_output_file(&tile.info.file_name);
+                // the tiles are already processed in parallel
+                let is_off_terrain = find_off_terrain_points(tile.clone(), search_radius, height_threshold, slope_threshold, 1, false);
+                
+                // only the tile's own points are output; the buffer points are trimmed
+                let mut output = LasFile::initialize_using_file(&output_file, &tile.las);
+                output.header.system_id = "EXTRACTION".to_string();
+                for i in 0..tile.num_core_points() {
+                    if !is_off_terrain[i] {
+                        output.add_point_record(tile.las.get_record(i));
+                    }
+                }
+                output.write()
+            })?;
+
+            let end = time::now();
+            let elapsed_time = end - start;
+            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            return Ok(());
+        }
+
         let sep = path::MAIN_SEPARATOR;
         if !input_file.contains(sep) {
             input_file = format!("{}{}", working_directory, input_file);
@@ -199,11 +256,9 @@ impl WhiteboxTool for LidarGroundPointFilter {
             output_file = format!("{}{}", working_directory, output_file);
         }
 
+        // a single file is filtered as a catalog of one unbuffered tile
         if verbose { println!("Reading input LAS file..."); }
-        let input = match LasFile::new(&input_file, "r") {
-            Ok(lf) => lf,
-            Err(err) => panic!("Error reading file {}: {}", input_file, err),
-        };
+        let tile = Arc::new(LasCatalog::from_files(vec![input_file.clone()])?.read_buffered_tile(0, 0f64)?);
 
         let start = time::now();
 
@@ -211,210 +266,234 @@ impl WhiteboxTool for LidarGroundPointFilter {
 
         slope_threshold = slope_threshold.to_radians().tan();
 
-        let n_points = input.header.number_of_points as usize;
-        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let is_off_terrain = find_off_terrain_points(tile.clone(), search_radius, height_threshold, slope_threshold, num_cpus::get(), verbose);
 
+        // now output the data
+        let n_points = tile.num_core_points();
+        let num_points: f64 = n_points as f64 - 1f64; // used for progress calculation only
         let mut progress: i32;
         let mut old_progress: i32 = -1;
-        let mut frs: FixedRadiusSearch2D<usize> = FixedRadiusSearch2D::new(search_radius);
+        let mut output = LasFile::initialize_using_file(&output_file, &tile.las);
+        output.header.system_id = "EXTRACTION".to_string();
+
         for i in 0..n_points {
-            let p: PointData = input.get_point_info(i);
-            if p.is_late_return() && !p.is_classified_noise() {
-                frs.insert(p.x, p.y, i);
+            if !is_off_terrain[i] {
+                output.add_point_record(tile.las.get_record(i));
             }
             if verbose {
                 progress = (100.0_f64 * i as f64 / num_points) as i32;
                 if progress != old_progress {
-                    println!("Binning points: {}%", progress);
+                    println!("Saving data: {}%", progress);
                     old_progress = progress;
                 }
             }
         }
 
-        let mut neighbourhood_min = vec![f64::MAX; n_points];
-        let mut residuals = vec![f64::MIN; n_points];
-        
-        /////////////
-        // Erosion //
-        /////////////
-
-        let frs = Arc::new(frs); // wrap FRS in an Arc
-        let input = Arc::new(input); // wrap input in an Arc
-        let num_procs = num_cpus::get();
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let frs = frs.clone();
-            let input = input.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let mut index_n: usize;
-                let mut z_n: f64;
-                let mut min_z: f64;
-                for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
-                    let p: PointData = input.get_point_info(point_num);
-                    if p.is_late_return() && !p.is_classified_noise() {
-                        let ret = frs.search(p.x, p.y);
-                        min_z = f64::MAX;
-                        for j in 0..ret.len() {
-                            index_n = ret[j].0;
-                            z_n = input.get_point_info(index_n).z;
-                            if z_n < min_z {
-                                min_z = z_n;
-                            }
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the output file for a tile, i.e. the tile's file name
+/// with a `_ground` suffix added to its stem.
+fn ground_output_file(file_name: &str) -> String {
+    let lc = file_name.to_lowercase();
+    let ext_len = if lc.ends_with(".las.zip") { 8 } else if lc.ends_with(".las") { 4 } else { 0 };
+    let (stem, ext) = file_name.split_at(file_name.len() - ext_len);
+    format!("{}_ground{}", stem, ext.to_lowercase())
+}
+
+/// Returns `true` if the file is the output of a previous run of the tool.
+fn is_ground_output_file(file_name: &str) -> bool {
+    let lc = file_name.to_lowercase();
+    lc.ends_with("_ground.las") || lc.ends_with("_ground.las.zip")
+}
+
+/// Applies the erosion, dilation, and slope-based filter to a buffered tile,
+/// using `num_procs` threads, and returns `true` for each point that is
+/// identified as an off-terrain point.
+fn find_off_terrain_points(tile: Arc<BufferedTile>, search_radius: f64, height_threshold: f64, slope_threshold: f64, num_procs: usize, verbose: bool) -> Vec<bool> {
+    let n_points = tile.len();
+    let num_points: f64 = n_points as f64 - 1f64; // used for progress calculation only
+
+    let mut progress: i32;
+    let mut old_progress: i32 = -1;
+    let mut frs: FixedRadiusSearch2D<usize> = FixedRadiusSearch2D::new(search_radius);
+    for i in 0..n_points {
+        let p: PointData = tile.get_point_info(i);
+        if p.is_late_return() && !p.is_classified_noise() {
+            frs.insert(p.x, p.y, i);
+        }
+        if verbose {
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                println!("Binning points: {}%", progress);
+                old_progress = progress;
+            }
+        }
+    }
+
+    let mut neighbourhood_min = vec![f64::MAX; n_points];
+    let mut residuals = vec![f64::MIN; n_points];
+    
+    /////////////
+    // Erosion //
+    /////////////
+
+    let frs = Arc::new(frs); // wrap FRS in an Arc
+    let (tx, rx) = mpsc::channel();
+    for tid in 0..num_procs {
+        let frs = frs.clone();
+        let tile = tile.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut index_n: usize;
+            let mut z_n: f64;
+            let mut min_z: f64;
+            for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
+                let p: PointData = tile.get_point_info(point_num);
+                if p.is_late_return() && !p.is_classified_noise() {
+                    let ret = frs.search(p.x, p.y);
+                    min_z = f64::MAX;
+                    for j in 0..ret.len() {
+                        index_n = ret[j].0;
+                        z_n = tile.get_point_info(index_n).z;
+                        if z_n < min_z {
+                            min_z = z_n;
                         }
-                        tx.send((point_num, min_z)).unwrap();
-                    } else {
-                        tx.send((point_num, f64::MAX)).unwrap();
                     }
+                    tx.send((point_num, min_z)).unwrap();
+                } else {
+                    tx.send((point_num, f64::MAX)).unwrap();
                 }
-            });
-        }
+            }
+        });
+    }
 
-        for i in 0..n_points {
-            let data = rx.recv().unwrap();
-            neighbourhood_min[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Erosion: {}%", progress);
-                    old_progress = progress;
-                }
+    for i in 0..n_points {
+        let data = rx.recv().unwrap();
+        neighbourhood_min[data.0] = data.1;
+        if verbose {
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                println!("Erosion: {}%", progress);
+                old_progress = progress;
             }
         }
+    }
 
-        //////////////
-        // Dilation //
-        //////////////
-        let neighbourhood_min = Arc::new(neighbourhood_min); // wrap neighbourhood_min in an Arc
-        for tid in 0..num_procs {
-            let frs = frs.clone();
-            let input = input.clone();
-            let neighbourhood_min = neighbourhood_min.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let mut index_n: usize;
-                let mut z_n: f64;
-                let mut max_z: f64;
-                for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
-                    let p: PointData = input.get_point_info(point_num);
-                    if p.is_late_return() && !p.is_classified_noise() {
-                        let ret = frs.search(p.x, p.y);
-                        max_z = f64::MIN;
-                        for j in 0..ret.len() {
-                            index_n = ret[j].0;
-                            z_n = neighbourhood_min[index_n];
-                            if z_n > max_z {
-                                max_z = z_n;
-                            }
+    //////////////
+    // Dilation //
+    //////////////
+    let neighbourhood_min = Arc::new(neighbourhood_min); // wrap neighbourhood_min in an Arc
+    for tid in 0..num_procs {
+        let frs = frs.clone();
+        let tile = tile.clone();
+        let neighbourhood_min = neighbourhood_min.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut index_n: usize;
+            let mut z_n: f64;
+            let mut max_z: f64;
+            for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
+                let p: PointData = tile.get_point_info(point_num);
+                if p.is_late_return() && !p.is_classified_noise() {
+                    let ret = frs.search(p.x, p.y);
+                    max_z = f64::MIN;
+                    for j in 0..ret.len() {
+                        index_n = ret[j].0;
+                        z_n = neighbourhood_min[index_n];
+                        if z_n > max_z {
+                            max_z = z_n;
                         }
-                        tx.send((point_num, max_z)).unwrap();
-                    } else {
-                        tx.send((point_num, f64::MIN)).unwrap();
                     }
+                    tx.send((point_num, max_z)).unwrap();
+                } else {
+                    tx.send((point_num, f64::MIN)).unwrap();
                 }
-            });
-        }
-
-        for i in 0..n_points {
-            let data = rx.recv().unwrap();
-            if data.1 != f64::MIN {
-                let z = input.get_point_info(data.0).z;
-                residuals[data.0] = z - data.1;
             }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Dilation: {}%", progress);
-                    old_progress = progress;
-                }
+        });
+    }
+
+    for i in 0..n_points {
+        let data = rx.recv().unwrap();
+        if data.1 != f64::MIN {
+            let z = tile.get_point_info(data.0).z;
+            residuals[data.0] = z - data.1;
+        }
+        if verbose {
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                println!("Dilation: {}%", progress);
+                old_progress = progress;
             }
         }
+    }
 
-        ////////////////////////
-        // Slope-based filter //
-        ////////////////////////
-        let residuals = Arc::new(residuals);
-        let (tx, rx) = mpsc::channel();
-        for tid in 0..num_procs {
-            let frs = frs.clone();
-            let input = input.clone();
-            let residuals = residuals.clone();
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let mut index_n: usize;
-                let mut max_slope: f64;
-                let mut slope: f64;
-                let mut dist: f64;
-                for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
-                    let p: PointData = input.get_point_info(point_num);
-                    if residuals[point_num] < height_threshold && p.is_late_return() && !p.is_classified_noise() {
-                        let ret = frs.search(p.x, p.y);
-                        max_slope = f64::MIN;
-                        for j in 0..ret.len() {
-                            dist = ret[j].1;
-                            if dist > 0f64 {
-                                index_n = ret[j].0;
-                                slope = (residuals[point_num] - residuals[index_n]) / dist;
-                                if slope > max_slope {
-                                    max_slope = slope;
-                                }
+    ////////////////////////
+    // Slope-based filter //
+    ////////////////////////
+    let residuals = Arc::new(residuals);
+    let (tx, rx) = mpsc::channel();
+    for tid in 0..num_procs {
+        let frs = frs.clone();
+        let tile = tile.clone();
+        let residuals = residuals.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut index_n: usize;
+            let mut max_slope: f64;
+            let mut slope: f64;
+            let mut dist: f64;
+            for point_num in (0..n_points).filter(|point_num| point_num % num_procs == tid) {
+                let p: PointData = tile.get_point_info(point_num);
+                if residuals[point_num] < height_threshold && p.is_late_return() && !p.is_classified_noise() {
+                    let ret = frs.search(p.x, p.y);
+                    max_slope = f64::MIN;
+                    for j in 0..ret.len() {
+                        dist = ret[j].1;
+                        if dist > 0f64 {
+                            index_n = ret[j].0;
+                            slope = (residuals[point_num] - residuals[index_n]) / dist;
+                            if slope > max_slope {
+                                max_slope = slope;
                             }
                         }
-                        if max_slope > slope_threshold {
-                            tx.send((point_num, true)).unwrap();
-                        } else {
-                            tx.send((point_num, false)).unwrap();
-                        }
-                    } else {
+                    }
+                    if max_slope > slope_threshold {
                         tx.send((point_num, true)).unwrap();
+                    } else {
+                        tx.send((point_num, false)).unwrap();
                     }
-                }
-            });
-        }
-
-        let mut is_off_terrain = vec![false; n_points];
-        for i in 0..n_points {
-            let data = rx.recv().unwrap();
-            is_off_terrain[data.0] = data.1;
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Slope-based Filter: {}%", progress);
-                    old_progress = progress;
+                } else {
+                    tx.send((point_num, true)).unwrap();
                 }
             }
-        }
-
-
-        // now output the data
-        let mut output = LasFile::initialize_using_file(&output_file, &input);
-        output.header.system_id = "EXTRACTION".to_string();
+        });
+    }
 
-        for i in 0..n_points {
-            if !is_off_terrain[i] {
-                output.add_point_record(input.get_record(i));
-            }
-            if verbose {
-                progress = (100.0_f64 * i as f64 / num_points) as i32;
-                if progress != old_progress {
-                    println!("Saving data: {}%", progress);
-                    old_progress = progress;
-                }
+    let mut is_off_terrain = vec![false; n_points];
+    for i in 0..n_points {
+        let data = rx.recv().unwrap();
+        is_off_terrain[data.0] = data.1;
+        if verbose {
+            progress = (100.0_f64 * i as f64 / num_points) as i32;
+            if progress != old_progress {
+                println!("Slope-based Filter: {}%", progress);
+                old_progress = progress;
             }
         }
-
-        let end = time::now();
-        let elapsed_time = end - start;
-
-        if verbose { println!("Writing output LAS file..."); }
-        let _ = match output.write() {
-            Ok(_) => println!("Complete!"),
-            Err(e) => println!("error while writing: {:?}", e),
-        };
-
-        println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
-
-        Ok(())
     }
-}
 
+    is_off_terrain
+}
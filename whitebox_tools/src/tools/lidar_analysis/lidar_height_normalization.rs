@@ -0,0 +1,332 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 10, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::mem;
+use std::path;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use raster::*;
+use structures::Point2D;
+use tools::*;
+
+pub struct LidarHeightNormalization {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarHeightNormalization {
+    pub fn new() -> LidarHeightNormalization {
+        // public constructor
+        let name = "LidarHeightNormalization".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Normalizes LiDAR point elevations to heights above a ground surface (DTM raster or TIN of ground points)."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input DTM File (optional)".to_owned(),
+            flags: vec!["--dtm".to_owned()],
+            description: "Optional input ground surface raster; if unspecified, a TIN of the ground-classified (class 2) points is used.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Store heights in an extra attribute?".to_owned(),
+            flags: vec!["--attribute".to_owned()],
+            description: "Optional boolean flag indicating whether heights are stored in a 'height' extra bytes attribute rather than replacing point z values.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --dtm=\"dtm.tif\"
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --attribute", short_exe, name).replace("*", &sep);
+
+        LidarHeightNormalization {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarHeightNormalization {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut dtm_file: String = "".to_string();
+        let mut use_attribute = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-dtm" {
+                dtm_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-attribute" {
+                use_attribute = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !dtm_file.is_empty() && !dtm_file.contains(sep) {
+            dtm_file = format!("{}{}", working_directory, dtm_file);
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        let input = Arc::new(input);
+        let num_procs = num_cpus::get();
+        let (tx, rx) = mpsc::channel();
+        let nodata = f64::NEG_INFINITY;
+        let mut ground_z = vec![nodata; n_points];
+
+        if !dtm_file.is_empty() {
+            if verbose { println!("Reading DTM raster..."); }
+            let dtm = Arc::new(Raster::new(&dtm_file, "r")?);
+            for tid in 0..num_procs {
+                let input = input.clone();
+                let dtm = dtm.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                        let p: PointData = input.get_point_info(i);
+                        tx.send((i, interpolate_raster_value(&dtm, p.x, p.y))).unwrap();
+                    }
+                });
+            }
+        } else {
+            if verbose { println!("Triangulating ground points..."); }
+            let mut points: Vec<Point2D> = vec![];
+            let mut z_values: Vec<f64> = vec![];
+            for i in 0..n_points {
+                let p: PointData = input.get_point_info(i);
+                if p.classification() == 2 && !p.class_bit_field.withheld() {
+                    points.push(Point2D::new(p.x, p.y));
+                    z_values.push(p.z);
+                }
+            }
+            let ground = match GroundTin::new(points, z_values) {
+                Some(g) => Arc::new(g),
+                None => return Err(Error::new(ErrorKind::InvalidInput,
+                    "The input file does not contain enough ground-classified (class 2) points to triangulate.")),
+            };
+            for tid in 0..num_procs {
+                let input = input.clone();
+                let ground = ground.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                        let p: PointData = input.get_point_info(i);
+                        tx.send((i, ground.get_elevation(p.x, p.y))).unwrap();
+                    }
+                });
+            }
+        }
+
+        for i in 0..n_points {
+            let (idx, z) = rx.recv().unwrap();
+            ground_z[idx] = z;
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Calculating ground elevations: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        if use_attribute {
+            output.add_extra_bytes_attribute("height", "Height above ground", ExtraBytesDataType::F64)?;
+        }
+
+        let mut num_unresolved = 0;
+        let mut height: f64;
+        for i in 0..n_points {
+            let mut pr = input.get_record(i);
+            {
+                let point_data = match pr {
+                    LidarPointRecord::PointRecord0 { ref mut point_data } |
+                    LidarPointRecord::PointRecord1 { ref mut point_data, .. } |
+                    LidarPointRecord::PointRecord2 { ref mut point_data, .. } |
+                    LidarPointRecord::PointRecord3 { ref mut point_data, .. } => point_data,
+                };
+                if ground_z[i] == nodata {
+                    // there is no ground surface beneath this point; keep its elevation but withhold it
+                    num_unresolved += 1;
+                    point_data.set_withheld(true);
+                    height = f64::NAN;
+                } else {
+                    height = point_data.z - ground_z[i];
+                    if !use_attribute {
+                        point_data.z = height;
+                    }
+                }
+            }
+            output.add_point_record(pr);
+            if use_attribute {
+                let bytes: [u8; 8] = unsafe { mem::transmute(height) };
+                output.add_extra_bytes(&bytes);
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if num_unresolved > 0 {
+            println!("Warning: {} points lacking an underlying ground surface were flagged as withheld, with their elevations unchanged.", num_unresolved);
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
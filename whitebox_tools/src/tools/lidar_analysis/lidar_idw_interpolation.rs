@@ -2,18 +2,16 @@
 This tool is part of the WhiteboxTools geospatial analysis library.
 Authors: Dr. John Lindsay
 Created: July 3, 2017
-Last Modified: December 23, 2017
+Last Modified: February 22, 2018
 License: MIT
 
 NOTES: Add the ability to:
 Exclude points based on max scan angle divation
-Interpolate all LAS files within a directory (i.e. directory input rather than single file).
 */
 extern crate time;
 extern crate num_cpus;
 
 use std::env;
-use std::fs;
 use std::f64;
 use std::io::{Error, ErrorKind};
 use std::path;
@@ -105,6 +103,15 @@ impl LidarIdwInterpolation {
             optional: true
         });
         
+        parameters.push(ToolParameter{
+            name: "Tile Buffer Distance (optional)".to_owned(), 
+            flags: vec!["--buffer".to_owned()], 
+            description: "Optional width of the buffer of points drawn from adjacent tiles when interpolating all of the LAS files in the working directory; defaults to the search radius.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+        
         parameters.push(ToolParameter{
             name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(), 
             flags: vec!["--exclude_cls".to_owned()], 
@@ -211,6 +218,7 @@ impl WhiteboxTool for LidarIdwInterpolation {
         let mut grid_res: f64 = 1.0;
         let mut weight = 1.0;
         let mut search_radius = 2.5;
+        let mut buffer = -1f64;
         let mut include_class_vals = vec![true; 256];
         let mut palette = "default".to_string();
         let mut exclude_cls_str = String::new();
@@ -273,6 +281,12 @@ impl WhiteboxTool for LidarIdwInterpolation {
                 } else {
                     args[i + 1].to_string().parse::<f64>().unwrap()
                 };
+            } else if flag_val == "-buffer" {
+                buffer = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
             } else if flag_val == "-palette" {
                 palette = if keyval {
                     vec[1].to_string()
@@ -336,32 +350,106 @@ impl WhiteboxTool for LidarIdwInterpolation {
 
         let start = time::now();
 
-        let mut inputs = vec![];
-        let mut outputs = vec![];
         if input_file.is_empty() {
             if working_directory.is_empty() {
                 return Err(Error::new(ErrorKind::InvalidInput,
                     "This tool must be run by specifying either an individual input file or a working directory."));
             }
-            match fs::read_dir(working_directory) {
-                Err(why) => println!("! {:?}", why.kind()),
-                Ok(paths) => for path in paths {
-                    let s = format!("{:?}", path.unwrap().path());
-                    if s.replace("\"", "").to_lowercase().ends_with(".las") {
-                        inputs.push(format!("{:?}", s.replace("\"", "")));
-                        outputs.push(inputs[inputs.len()-1].replace(".las", ".tif").replace(".LAS", ".tif"))
-                    }
-                },
+            if buffer < 0f64 {
+                buffer = search_radius;
             }
-            // return Ok(());
-        } else {
-            inputs.push(input_file.clone());
-            if output_file.is_empty() {
-                output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+            if verbose {
+                println!("Reading LAS tile headers...");
+            }
+            let catalog = LasCatalog::new(working_directory)?;
+            if verbose {
+                println!("Interpolating {} tiles using a {} buffer...", catalog.len(), buffer);
             }
-            outputs.push(output_file);
+            let tool_name = self.get_tool_name();
+            catalog.process_tiles(buffer, verbose, move |tile| {
+                let output_file = tile.info.file_name.replace(".las.zip", ".tif").replace(".LAS.zip", ".tif")
+                    .replace(".las", ".tif").replace(".LAS", ".tif");
+                
+                // bin the points in the tile and its buffer
+                let mut frs: FixedRadiusSearch2D<usize> = FixedRadiusSearch2D::new(search_radius);
+                let mut interp_vals: Vec<f64> = vec![];
+                for i in 0..tile.len() {
+                    let p: PointData = tile.get_point_info(i);
+                    if !p.class_bit_field.withheld() {
+                        if all_returns || (p.is_late_return() & late_returns) ||
+                        (p.is_early_return() & early_returns) {
+                            if include_class_vals[p.classification() as usize] {
+                                if p.z >= min_z && p.z <= max_z {
+                                    frs.insert(p.x, p.y, interp_vals.len());
+                                    interp_vals.push(get_interp_value(&p, &interp_parameter));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // the output grid covers only the tile's own extent, trimming the buffer
+                let (west, max_x, min_y, north) = tile.core_extent();
+                let rows: isize = (((north - min_y) / grid_res).ceil()) as isize;
+                let columns: isize = (((max_x - west) / grid_res).ceil()) as isize;
+                let south: f64 = north - rows as f64 * grid_res;
+                let east = west + columns as f64 * grid_res;
+                let nodata = -32768.0f64;
+
+                let mut configs = RasterConfigs { ..Default::default() };
+                configs.rows = rows as usize;
+                configs.columns = columns as usize;
+                configs.north = north;
+                configs.south = south;
+                configs.east = east;
+                configs.west = west;
+                configs.resolution_x = grid_res;
+                configs.resolution_y = grid_res;
+                configs.nodata = nodata;
+                configs.data_type = DataType::F64;
+                configs.photometric_interp = PhotometricInterpretation::Continuous;
+                configs.palette = palette.clone();
+
+                let mut output = Raster::initialize_using_config(&output_file, &configs);
+                let (mut x, mut y): (f64, f64);
+                for row in 0..rows {
+                    let mut data = vec![nodata; columns as usize];
+                    for col in 0..columns {
+                        x = output.get_x_from_column_centre(col);
+                        y = output.get_y_from_row_centre(row);
+                        data[col as usize] = idw_value(&frs.search(x, y), &interp_vals, weight, nodata);
+                    }
+                    output.set_row_data(row, data);
+                }
+
+                output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", tool_name));
+                output.add_metadata_entry(format!("Input file: {}", tile.info.file_name));
+                output.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+                output.add_metadata_entry(format!("Search radius: {}", search_radius));
+                output.add_metadata_entry(format!("Tile buffer: {}", buffer));
+                output.add_metadata_entry(format!("Weight: {}", weight));
+                output.add_metadata_entry(format!("Interpolation parameter: {}", interp_parameter));
+                output.add_metadata_entry(format!("Returns: {}", return_type));
+                output.add_metadata_entry(format!("Excluded classes: {}", exclude_cls_str));
+                output.write()
+            })?;
+
+            let end = time::now();
+            let elapsed_time = end - start;
+            if verbose {
+                println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            }
+            return Ok(());
         }
 
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        inputs.push(input_file.clone());
+        if output_file.is_empty() {
+            output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+        }
+        outputs.push(output_file);
+
         for k in 0..inputs.len() {
             input_file = inputs[k].replace("\"", "").clone();
             output_file = outputs[k].replace("\"", "").clone();
@@ -560,38 +648,13 @@ impl WhiteboxTool for LidarIdwInterpolation {
                 id += 1;
                 let tx1 = tx.clone();
                 thread::spawn(move || {
-                    let mut index_n: usize;
                     let (mut x, mut y): (f64, f64);
-                    let mut zn: f64;
-                    let mut dist: f64;
-                    let mut val: f64;
-                    let mut sum_weights: f64;
                     for row in starting_row..ending_row {
                         let mut data = vec![nodata; columns as usize];
                         for col in 0..columns {
                             x = west + col as f64 * grid_res + 0.5;
                             y = north - row as f64 * grid_res - 0.5;
-                            let ret = frs.search(x, y);
-                            if ret.len() > 0 {
-                                sum_weights = 0.0;
-                                val = 0.0;
-                                for j in 0..ret.len() {
-                                    index_n = ret[j].0;
-                                    zn = interp_vals[index_n]; //input[index_n].z;
-                                    dist = ret[j].1;
-                                    if dist > 0.0 {
-                                        val += zn / dist.powf(weight);
-                                        sum_weights += 1.0 / dist.powf(weight);
-                                    } else {
-                                        data[col as usize] = zn;
-                                        sum_weights = 0.0;
-                                        break;
-                                    }
-                                }
-                                if sum_weights > 0.0 {
-                                    data[col as usize] = val / sum_weights;
-                                }
-                            }
+                            data[col as usize] = idw_value(&frs.search(x, y), &interp_vals, weight, nodata);
                         }
                         tx1.send((row, data)).unwrap();
                     }
@@ -647,3 +710,29 @@ impl WhiteboxTool for LidarIdwInterpolation {
         Ok(())
     }
 }
+
+/// Calculates the inverse-distance weighted value from a set of neighbouring
+/// points (index, distance), returning `nodata` if there are no neighbours.
+fn idw_value(neighbours: &Vec<(usize, f64)>, interp_vals: &Vec<f64>, weight: f64, nodata: f64) -> f64 {
+    if neighbours.len() == 0 {
+        return nodata;
+    }
+    let mut sum_weights = 0.0;
+    let mut val = 0.0;
+    let mut zn: f64;
+    let mut dist: f64;
+    for j in 0..neighbours.len() {
+        zn = interp_vals[neighbours[j].0];
+        dist = neighbours[j].1;
+        if dist > 0.0 {
+            val += zn / dist.powf(weight);
+            sum_weights += 1.0 / dist.powf(weight);
+        } else {
+            return zn;
+        }
+    }
+    if sum_weights > 0.0 {
+        return val / sum_weights;
+    }
+    nodata
+}
@@ -0,0 +1,428 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 16, 2018
+Last Modified: January 16, 2018
+License: MIT
+*/
+extern crate time;
+extern crate num_cpus;
+
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use lidar::*;
+use structures::{FixedRadiusSearch3D, KdTree};
+use structures::kd_tree::squared_euclidean;
+use tools::*;
+
+/// Identifies noise points within a LiDAR point cloud using two tests. The statistical
+/// outlier test calculates each point's mean distance to its `k` nearest neighbours and
+/// flags those points whose mean distance exceeds the global mean by more than
+/// `--stdev_mult` standard deviations. The isolated point test, applied when `--radius`
+/// is greater than zero, flags points with fewer than `--min_neighbours` other points
+/// within a 3D search radius. Flagged points are either removed from the output or, if
+/// `--classify` is specified, assigned to class 7 (low noise) or class 18 (high noise)
+/// depending on whether they lie below or above their neighbours.
+pub struct LidarNoiseFilter {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarNoiseFilter {
+    pub fn new() -> LidarNoiseFilter {
+        // public constructor
+        let name = "LidarNoiseFilter".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Identifies statistical outliers and isolated (noise) points in a LiDAR point cloud.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Number of Neighbours (k)".to_owned(),
+            flags: vec!["--k".to_owned()],
+            description: "Number of nearest neighbours used in the statistical outlier test.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("8".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Standard Deviation Multiplier".to_owned(),
+            flags: vec!["--stdev_mult".to_owned()],
+            description: "Number of standard deviations above the mean neighbour distance beyond which a point is an outlier.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("3.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Isolation Search Radius".to_owned(),
+            flags: vec!["--radius".to_owned()],
+            description: "3D search radius of the isolated point test; zero disables the test.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Number of Neighbours".to_owned(),
+            flags: vec!["--min_neighbours".to_owned()],
+            description: "Minimum number of other points within the search radius of a non-isolated point.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("1".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Classify noise points rather than removing them?".to_owned(),
+            flags: vec!["--classify".to_owned()],
+            description: "Optional boolean flag indicating whether noise points are retained and classified as low (7) or high (18) noise.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --k=10 --stdev_mult=2.5 --radius=5.0 --min_neighbours=2 --classify", short_exe, name).replace("*", &sep);
+
+        LidarNoiseFilter {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarNoiseFilter {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut k = 8usize;
+        let mut stdev_mult = 3.0f64;
+        let mut radius = 0.0f64;
+        let mut min_neighbours = 1usize;
+        let mut classify = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-k" {
+                k = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            } else if flag_val == "-stdev_mult" {
+                stdev_mult = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-radius" {
+                radius = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-min_neighbours" {
+                min_neighbours = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            } else if flag_val == "-classify" {
+                classify = true;
+            }
+        }
+
+        if k < 1 {
+            k = 1;
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+        let mut tree = KdTree::new_with_capacity(3, 64);
+        let mut frs: FixedRadiusSearch3D<usize> = FixedRadiusSearch3D::new(if radius > 0f64 { radius } else { 1f64 });
+        for i in 0..n_points {
+            let p: PointData = input.get_point_info(i);
+            tree.add([p.x, p.y, p.z], i).unwrap();
+            if radius > 0f64 {
+                frs.insert(p.x, p.y, p.z, i);
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Binning points: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // mean neighbour distances, neighbourhood mean elevations and isolation flags
+        let mut mean_dist = vec![0f64; n_points];
+        let mut neighbour_z = vec![0f64; n_points];
+        let mut isolated = vec![false; n_points];
+
+        let tree = Arc::new(tree);
+        let frs = Arc::new(frs);
+        let input = Arc::new(input);
+        let num_procs = num_cpus::get();
+        let (tx, rx) = mpsc::channel();
+        for tid in 0..num_procs {
+            let tree = tree.clone();
+            let frs = frs.clone();
+            let input = input.clone();
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for i in (0..n_points).filter(|i| i % num_procs == tid) {
+                    let p: PointData = input.get_point_info(i);
+                    // the nearest point is the point itself
+                    let ret = tree.nearest(&[p.x, p.y, p.z], k + 1, &squared_euclidean).unwrap();
+                    let mut sum_dist = 0f64;
+                    let mut sum_z = 0f64;
+                    let mut n = 0f64;
+                    for &(dist, &j) in &ret {
+                        if j != i {
+                            sum_dist += dist.sqrt();
+                            sum_z += input.get_point_info(j).z;
+                            n += 1f64;
+                        }
+                    }
+                    let (md, mz) = if n > 0f64 { (sum_dist / n, sum_z / n) } else { (f64::INFINITY, p.z) };
+                    let is_isolated = if radius > 0f64 {
+                        frs.search(p.x, p.y, p.z).len() - 1 < min_neighbours
+                    } else {
+                        false
+                    };
+                    tx.send((i, md, mz, is_isolated)).unwrap();
+                }
+            });
+        }
+
+        for i in 0..n_points {
+            let (idx, md, mz, is_isolated) = rx.recv().unwrap();
+            mean_dist[idx] = md;
+            neighbour_z[idx] = mz;
+            isolated[idx] = is_isolated;
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Finding neighbours: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // the global statistics of the mean neighbour distances
+        let mut sum = 0f64;
+        let mut sum_sqr = 0f64;
+        let mut n = 0f64;
+        for i in 0..n_points {
+            if mean_dist[i].is_finite() {
+                sum += mean_dist[i];
+                sum_sqr += mean_dist[i] * mean_dist[i];
+                n += 1f64;
+            }
+        }
+        let mean = if n > 0f64 { sum / n } else { 0f64 };
+        let stdev = if n > 0f64 { (sum_sqr / n - mean * mean).max(0f64).sqrt() } else { 0f64 };
+        let threshold = mean + stdev_mult * stdev;
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+
+        let mut num_outliers = 0usize;
+        let mut num_isolated = 0usize;
+        let (mut num_low, mut num_high) = (0usize, 0usize);
+        for i in 0..n_points {
+            let is_outlier = mean_dist[i] > threshold;
+            if is_outlier {
+                num_outliers += 1;
+            }
+            if isolated[i] {
+                num_isolated += 1;
+            }
+            if !is_outlier && !isolated[i] {
+                output.add_point_record(input.get_record(i));
+            } else {
+                let class_val = if input.get_point_info(i).z < neighbour_z[i] {
+                    num_low += 1;
+                    7u8
+                } else {
+                    num_high += 1;
+                    18u8
+                };
+                if classify {
+                    let pr2: LidarPointRecord;
+                    match input.get_record(i) {
+                        LidarPointRecord::PointRecord0 { mut point_data }  => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord0 { point_data: point_data };
+                        },
+                        LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
+                        },
+                        LidarPointRecord::PointRecord2 { mut point_data, rgb_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord2 { point_data: point_data, rgb_data: rgb_data };
+                        },
+                        LidarPointRecord::PointRecord3 { mut point_data, gps_data, rgb_data } => {
+                            point_data.set_classification(class_val);
+                            pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
+                                gps_data: gps_data, rgb_data: rgb_data};
+                        },
+                    }
+                    output.add_point_record(pr2);
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        println!("Statistical outliers: {}", num_outliers);
+        if radius > 0f64 {
+            println!("Isolated points: {}", num_isolated);
+        }
+        println!("Noise points {}: {} ({} low, {} high)", if classify { "classified" } else { "removed" },
+            num_low + num_high, num_low, num_high);
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 17, 2018
+Last Modified: January 17, 2018
+License: MIT
+*/
+extern crate time;
+extern crate rand;
+
+use std::collections::HashMap;
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use lidar::*;
+use structures::FixedRadiusSearch2D;
+use tools::*;
+use self::rand::Rng;
+
+/// Thins a LiDAR point cloud, reducing its point density. The available methods are:
+///
+/// * 'lowest', 'highest', 'random' and 'centre': overlays a grid with a cell size of
+///   `--resolution` and retains one point per grid cell, i.e. the lowest point, the
+///   highest point, a randomly selected point, or the point nearest the cell's centre.
+/// * 'nth': retains every nth point.
+/// * 'poisson': retains points such that no two retained points are closer (in the
+///   horizontal plane) than `--resolution` (Poisson-disk thinning). Points are
+///   visited in a random order.
+///
+/// Thinning may be restricted to points of particular classes and/or return types;
+/// all other points are output unchanged.
+pub struct LidarThin {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarThin {
+    pub fn new() -> LidarThin {
+        // public constructor
+        let name = "LidarThin".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Thins a LiDAR point cloud using grid-based, every-nth or Poisson-disk methods.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Thinning Method".to_owned(),
+            flags: vec!["--method".to_owned()],
+            description: "Thinning method; options are 'lowest' (default), 'highest', 'random', 'centre', 'nth', and 'poisson'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["lowest".to_owned(), "highest".to_owned(), "random".to_owned(), "centre".to_owned(), "nth".to_owned(), "poisson".to_owned()]),
+            default_value: Some("lowest".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution / Minimum Spacing".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Grid cell size of the grid-based methods, or the minimum point spacing of the Poisson-disk method.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "N".to_owned(),
+            flags: vec!["--n".to_owned()],
+            description: "Retain every nth point, for the 'nth' method.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("2".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Thinned Classes (optional)".to_owned(),
+            flags: vec!["--classes".to_owned()],
+            description: "Optional comma-separated list of the classes to thin, e.g. '1,2'; points of other classes are retained. All classes are thinned by default.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Thinned Returns".to_owned(),
+            flags: vec!["--returns".to_owned()],
+            description: "Point return types to thin; options are 'all' (default), 'last', 'first'. Points of other return types are retained.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["all".to_owned(), "last".to_owned(), "first".to_owned()]),
+            default_value: Some("all".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --method=lowest --resolution=2.0 --classes='2'
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --method=poisson --resolution=0.5", short_exe, name).replace("*", &sep);
+
+        LidarThin {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarThin {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut method = "lowest".to_string();
+        let mut resolution = 1.0f64;
+        let mut nth = 2usize;
+        let mut include_class_vals = vec![true; 256];
+        let mut return_type = "all".to_string();
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-method" {
+                method = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+            } else if flag_val == "-resolution" {
+                resolution = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-n" {
+                nth = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            } else if flag_val == "-classes" {
+                let classes_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+                include_class_vals = vec![false; 256];
+                for value in classes_str.split(|c| c == ',' || c == ';') {
+                    if !value.trim().is_empty() {
+                        let c = value.trim().parse::<usize>().unwrap();
+                        include_class_vals[c] = true;
+                    }
+                }
+            } else if flag_val == "-returns" {
+                return_type = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+            }
+        }
+
+        if method.contains("center") {
+            method = "centre".to_string();
+        }
+        if !["lowest", "highest", "random", "centre", "nth", "poisson"].contains(&(&method as &str)) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Unrecognized thinning method '{}'.", method)));
+        }
+        if nth < 1 {
+            nth = 1;
+        }
+        if resolution <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The resolution must be greater than zero."));
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        // points that are not subject to thinning are always retained
+        let mut retain = vec![false; n_points];
+        let mut eligible: Vec<usize> = Vec::with_capacity(n_points);
+        for i in 0..n_points {
+            let p: PointData = input[i];
+            let return_match = return_type == "all" ||
+                (return_type.contains("last") && p.is_late_return()) ||
+                (return_type.contains("first") && p.is_early_return());
+            if include_class_vals[p.classification() as usize] && return_match {
+                eligible.push(i);
+            } else {
+                retain[i] = true;
+            }
+        }
+        let num_eligible = eligible.len();
+
+        let mut rng = rand::thread_rng();
+        match &method as &str {
+            "nth" => {
+                for j in (0..num_eligible).filter(|j| j % nth == 0) {
+                    retain[eligible[j]] = true;
+                }
+            },
+            "poisson" => {
+                rng.shuffle(&mut eligible);
+                let mut frs: FixedRadiusSearch2D<usize> = FixedRadiusSearch2D::new(resolution);
+                for j in 0..num_eligible {
+                    let p: PointData = input[eligible[j]];
+                    if frs.search(p.x, p.y).len() == 0 {
+                        frs.insert(p.x, p.y, eligible[j]);
+                        retain[eligible[j]] = true;
+                    }
+                    if verbose {
+                        progress = (100.0_f64 * j as f64 / (num_eligible - 1).max(1) as f64) as i32;
+                        if progress != old_progress {
+                            println!("Thinning: {}%", progress);
+                            old_progress = progress;
+                        }
+                    }
+                }
+            },
+            _ => {
+                // grid-based methods; each cell holds the index and score of its best point
+                let west = input.header.min_x;
+                let south = input.header.min_y;
+                let mut cells: HashMap<(isize, isize), (usize, f64)> = HashMap::new();
+                let mut score: f64;
+                for j in 0..num_eligible {
+                    let p: PointData = input[eligible[j]];
+                    let col = ((p.x - west) / resolution).floor();
+                    let row = ((p.y - south) / resolution).floor();
+                    score = match &method as &str {
+                        "lowest" => p.z,
+                        "highest" => -p.z,
+                        "random" => rng.gen::<f64>(),
+                        _ => {
+                            // centre
+                            let dx = p.x - (west + (col + 0.5) * resolution);
+                            let dy = p.y - (south + (row + 0.5) * resolution);
+                            dx * dx + dy * dy
+                        },
+                    };
+                    let entry = cells.entry((row as isize, col as isize)).or_insert((eligible[j], score));
+                    if score < entry.1 {
+                        *entry = (eligible[j], score);
+                    }
+                    if verbose {
+                        progress = (100.0_f64 * j as f64 / (num_eligible - 1).max(1) as f64) as i32;
+                        if progress != old_progress {
+                            println!("Thinning: {}%", progress);
+                            old_progress = progress;
+                        }
+                    }
+                }
+                for &(idx, _) in cells.values() {
+                    retain[idx] = true;
+                }
+            },
+        }
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        let mut num_retained = 0usize;
+        for i in 0..n_points {
+            if retain[i] {
+                output.add_point_record(input.get_record(i));
+                num_retained += 1;
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        println!("Points retained: {} of {} ({} removed)", num_retained, n_points, n_points - num_retained);
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,502 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 5, 2018
+Last Modified: January 5, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::io::{Error, ErrorKind};
+use std::path;
+use lidar::*;
+use raster::*;
+use structures::Point2D;
+use tools::*;
+
+pub struct LidarTinGridding {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl LidarTinGridding {
+    pub fn new() -> LidarTinGridding {
+        // public constructor
+        let name = "LidarTinGridding".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Creates a raster grid based on a Delaunay triangular irregular network (TIN) fitted to LiDAR points."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file (including extension).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output raster file (including extension).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Interpolation Parameter".to_owned(),
+            flags: vec!["--parameter".to_owned()],
+            description: "Interpolation parameter; options are 'elevation' (default), 'intensity', 'class', 'scan angle', 'user data'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["elevation".to_owned(), "intensity".to_owned(), "class".to_owned(), "scan angle".to_owned(), "user data".to_owned()]),
+            default_value: Some("elevation".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Point Returns Included".to_owned(),
+            flags: vec!["--returns".to_owned()],
+            description: "Point return types to include; options are 'all' (default), 'last', 'first'.".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["all".to_owned(), "last".to_owned(), "first".to_owned()]),
+            default_value: Some("all".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Exclusion Classes (0-18, based on LAS spec; e.g. 3,4,5,6,7)".to_owned(),
+            flags: vec!["--exclude_cls".to_owned()],
+            description: "Optional exclude classes from interpolation; Valid class values range from 0 to 18, based on LAS specifications. Example, --exclude_cls='3,4,5,6,7,18'.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Elevation Value (optional)".to_owned(),
+            flags: vec!["--minz".to_owned()],
+            description: "Optional minimum elevation for inclusion in interpolation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Elevation Value (optional)".to_owned(),
+            flags: vec!["--maxz".to_owned()],
+            description: "Optional maximum elevation for inclusion in interpolation.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Triangle Edge Length (optional)".to_owned(),
+            flags: vec!["--max_triangle_edge_length".to_owned()],
+            description: "Optional maximum triangle edge length; triangles larger than this size will not be gridded.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Tile Buffer Distance (optional)".to_owned(),
+            flags: vec!["--buffer".to_owned()],
+            description: "Optional width of the buffer of points drawn from adjacent tiles when gridding all of the LAS files in the working directory; defaults to 10 times the grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Palette Name (Whitebox raster outputs only)".to_owned(),
+            flags: vec!["--palette".to_owned()],
+            description: "Optional palette name (for use with Whitebox raster files).".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=file.las -o=outfile.tif --returns=last --resolution=2.0 --exclude_cls='3,4,5,6,7,18' --max_triangle_edge_length=5.0
+>>.*{0} -r={1} -v --wd=\"*path*to*tiles*\" --resolution=1.0 --exclude_cls='1,3,4,5,6,7,18' --buffer=20.0", short_exe, name).replace("*", &sep);
+
+        LidarTinGridding {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for LidarTinGridding {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self,
+               args: Vec<String>,
+               working_directory: &'a str,
+               verbose: bool)
+               -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut interp_parameter = "elevation".to_string();
+        let mut return_type = "all".to_string();
+        let mut grid_res: f64 = 1.0;
+        let mut include_class_vals = vec![true; 256];
+        let mut palette = "default".to_string();
+        let mut exclude_cls_str = String::new();
+        let mut max_z = f64::INFINITY;
+        let mut min_z = f64::NEG_INFINITY;
+        let mut max_triangle_edge_length = f64::INFINITY;
+        let mut buffer = -1f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-parameter" {
+                interp_parameter = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-returns" {
+                return_type = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-palette" {
+                palette = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-exclude_cls" {
+                exclude_cls_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+                let mut cmd = exclude_cls_str.split(",");
+                let mut vec = cmd.collect::<Vec<&str>>();
+                if vec.len() == 1 {
+                    cmd = exclude_cls_str.split(";");
+                    vec = cmd.collect::<Vec<&str>>();
+                }
+                for value in vec {
+                    if !value.trim().is_empty() {
+                        let c = value.trim().parse::<usize>().unwrap();
+                        include_class_vals[c] = false;
+                    }
+                }
+            } else if flag_val == "-minz" {
+                min_z = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-maxz" {
+                max_z = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_triangle_edge_length" {
+                max_triangle_edge_length = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-buffer" {
+                buffer = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        let (all_returns, late_returns, early_returns): (bool, bool, bool);
+        if return_type.contains("last") {
+            all_returns = false;
+            late_returns = true;
+            early_returns = false;
+        } else if return_type.contains("first") {
+            all_returns = false;
+            late_returns = false;
+            early_returns = true;
+        } else {
+            // all
+            all_returns = true;
+            late_returns = false;
+            early_returns = false;
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let start = time::now();
+
+        let include_point = move |p: &PointData| -> bool {
+            if p.class_bit_field.withheld() {
+                return false;
+            }
+            if !(all_returns || (p.is_late_return() & late_returns) || (p.is_early_return() & early_returns)) {
+                return false;
+            }
+            include_class_vals[p.classification() as usize] && p.z >= min_z && p.z <= max_z
+        };
+
+        if input_file.is_empty() {
+            if working_directory.is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                    "This tool must be run by specifying either an individual input file or a working directory."));
+            }
+            if buffer < 0f64 {
+                buffer = 10f64 * grid_res;
+            }
+            if verbose {
+                println!("Reading LAS tile headers...");
+            }
+            let catalog = LasCatalog::new(working_directory)?;
+            if verbose {
+                println!("Gridding {} tiles using a {} buffer...", catalog.len(), buffer);
+            }
+            let tool_name = self.get_tool_name();
+            catalog.process_tiles(buffer, verbose, move |tile| {
+                let output_file = tile.info.file_name.replace(".las.zip", ".tif").replace(".LAS.zip", ".tif")
+                    .replace(".las", ".tif").replace(".LAS", ".tif");
+                let mut points = vec![];
+                let mut interp_vals = vec![];
+                for i in 0..tile.len() {
+                    let p: PointData = tile.get_point_info(i);
+                    if include_point(&p) {
+                        points.push(Point2D::new(p.x, p.y));
+                        interp_vals.push(get_interp_value(&p, &interp_parameter));
+                    }
+                }
+                let (west, east, south, north) = tile.core_extent();
+                let mut output = create_output_raster(&output_file, west, east, south, north, grid_res, &palette);
+                if !tin_interpolation(&points, &interp_vals, max_triangle_edge_length, TinMerge::Replace, &mut output) {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unable to triangulate the points in {}; at least three non-collinear points are required.", tile.info.file_name)));
+                }
+
+                output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", tool_name));
+                output.add_metadata_entry(format!("Input file: {}", tile.info.file_name));
+                output.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+                output.add_metadata_entry(format!("Tile buffer: {}", buffer));
+                output.add_metadata_entry(format!("Maximum triangle edge length: {}", max_triangle_edge_length));
+                output.add_metadata_entry(format!("Interpolation parameter: {}", interp_parameter));
+                output.add_metadata_entry(format!("Returns: {}", return_type));
+                output.add_metadata_entry(format!("Excluded classes: {}", exclude_cls_str));
+                output.write()
+            })?;
+
+            let end = time::now();
+            let elapsed_time = end - start;
+            if verbose {
+                println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+            }
+            return Ok(());
+        }
+
+        if output_file.is_empty() {
+            output_file = input_file.clone().replace(".las", ".tif").replace(".LAS", ".tif");
+        }
+        if !input_file.contains(path::MAIN_SEPARATOR) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(path::MAIN_SEPARATOR) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose {
+            println!("Reading input LAS file...");
+        }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start_run = time::now();
+
+        if verbose {
+            println!("Performing analysis...");
+        }
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+        let mut points = vec![];
+        let mut interp_vals = vec![];
+        for i in 0..n_points {
+            let p: PointData = input[i];
+            if include_point(&p) {
+                points.push(Point2D::new(p.x, p.y));
+                interp_vals.push(get_interp_value(&p, &interp_parameter));
+            }
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Reading points: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if verbose {
+            println!("Triangulating and gridding {} points...", points.len());
+        }
+        let mut output = create_output_raster(&output_file, input.header.min_x, input.header.max_x,
+            input.header.min_y, input.header.max_y, grid_res, &palette);
+        if !tin_interpolation(&points, &interp_vals, max_triangle_edge_length, TinMerge::Replace, &mut output) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "Unable to triangulate the points; at least three non-collinear points are required."));
+        }
+
+        let end_run = time::now();
+        let elapsed_time_run = end_run - start_run;
+
+        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool",
+                                        self.get_tool_name()));
+        output.add_metadata_entry(format!("Input file: {}", input_file));
+        output.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+        output.add_metadata_entry(format!("Maximum triangle edge length: {}", max_triangle_edge_length));
+        output.add_metadata_entry(format!("Interpolation parameter: {}", interp_parameter));
+        output.add_metadata_entry(format!("Returns: {}", return_type));
+        output.add_metadata_entry(format!("Excluded classes: {}", exclude_cls_str));
+        output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time_run).replace("PT", ""));
+
+        if verbose {
+            println!("Saving data...")
+        };
+        let _ = match output.write() {
+            Ok(_) => {
+                if verbose {
+                    println!("Output file written")
+                }
+            }
+            Err(e) => return Err(e),
+        };
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+fn create_output_raster(file_name: &str, west: f64, east: f64, south: f64, north: f64, grid_res: f64, palette: &str) -> Raster {
+    let rows = ((north - south) / grid_res).ceil() as usize;
+    let columns = ((east - west) / grid_res).ceil() as usize;
+    let mut configs = RasterConfigs { ..Default::default() };
+    configs.rows = rows;
+    configs.columns = columns;
+    configs.north = north;
+    configs.south = north - rows as f64 * grid_res;
+    configs.east = west + columns as f64 * grid_res;
+    configs.west = west;
+    configs.resolution_x = grid_res;
+    configs.resolution_y = grid_res;
+    configs.nodata = -32768.0f64;
+    configs.data_type = DataType::F32;
+    configs.photometric_interp = PhotometricInterpretation::Continuous;
+    configs.palette = palette.to_string();
+    Raster::initialize_using_config(file_name, &configs)
+}
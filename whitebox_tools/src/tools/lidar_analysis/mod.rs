@@ -1,12 +1,21 @@
 // private sub-module defined in other files
+mod ascii_to_las;
 mod block_maximum;
 mod block_minimum;
 mod filter_lidar_scan_angles;
 mod find_flightline_edge_points;
 mod flightline_overlap;
+mod individual_tree_detection;
 mod las_to_ascii;
+mod las_to_ply;
+mod lidar_block_statistics;
+mod lidar_building_vegetation_classification;
+mod lidar_change_detection;
+mod lidar_clip;
 mod lidar_elevation_slice; 
+mod lidar_forest_metrics;
 mod lidar_ground_point_filter;
+mod lidar_height_normalization;
 mod lidar_hillshade;
 mod lidar_histogram;
 mod lidar_idw_interpolation;
@@ -14,22 +23,38 @@ mod lidar_info;
 mod lidar_join;
 mod lidar_kappa;
 mod lidar_nn_gridding;
+mod lidar_noise_filter;
 mod lidar_point_density;
 mod lidar_segmentation;
 mod lidar_segmentation_based_filter;
+mod lidar_thin;
 mod lidar_tile;
+mod lidar_tin_gridding;
 mod lidar_tophat_transform;
 mod normal_vectors;
+mod normalize_lidar_intensity;
+mod pit_free_chm;
+mod ply_to_las;
+mod recover_flightline_info;
 
 // exports identifiers from private sub-modules in the current module namespace
+pub use self::ascii_to_las::AsciiToLas;
 pub use self::block_maximum::BlockMaximum;
 pub use self::block_minimum::BlockMinimum;
 pub use self::filter_lidar_scan_angles::FilterLidarScanAngles;
 pub use self::find_flightline_edge_points::FindFlightlineEdgePoints;
 pub use self::flightline_overlap::FlightlineOverlap;
+pub use self::individual_tree_detection::IndividualTreeDetection;
 pub use self::las_to_ascii::LasToAscii;
+pub use self::las_to_ply::LasToPly;
+pub use self::lidar_block_statistics::LidarBlockStatistics;
+pub use self::lidar_building_vegetation_classification::LidarBuildingVegetationClassification;
+pub use self::lidar_change_detection::LidarChangeDetection;
+pub use self::lidar_clip::LidarClip;
 pub use self::lidar_elevation_slice::LidarElevationSlice;
+pub use self::lidar_forest_metrics::LidarForestMetrics;
 pub use self::lidar_ground_point_filter::LidarGroundPointFilter;
+pub use self::lidar_height_normalization::LidarHeightNormalization;
 pub use self::lidar_hillshade::LidarHillshade;
 pub use self::lidar_histogram::LidarHistogram;
 pub use self::lidar_idw_interpolation::LidarIdwInterpolation;
@@ -37,9 +62,16 @@ pub use self::lidar_info::LidarInfo;
 pub use self::lidar_join::LidarJoin;
 pub use self::lidar_kappa::LidarKappaIndex;
 pub use self::lidar_nn_gridding::LidarNearestNeighbourGridding;
+pub use self::lidar_noise_filter::LidarNoiseFilter;
 pub use self::lidar_point_density::LidarPointDensity;
 pub use self::lidar_segmentation::LidarSegmentation;
 pub use self::lidar_segmentation_based_filter::LidarSegmentationBasedFilter;
+pub use self::lidar_thin::LidarThin;
 pub use self::lidar_tile::LidarTile;
+pub use self::lidar_tin_gridding::LidarTinGridding;
 pub use self::lidar_tophat_transform::LidarTophatTransform;
-pub use self::normal_vectors::NormalVectors;
\ No newline at end of file
+pub use self::normal_vectors::NormalVectors;
+pub use self::normalize_lidar_intensity::NormalizeLidarIntensity;
+pub use self::pit_free_chm::PitFreeChm;
+pub use self::ply_to_las::PlyToLas;
+pub use self::recover_flightline_info::RecoverFlightlineInfo;
\ No newline at end of file
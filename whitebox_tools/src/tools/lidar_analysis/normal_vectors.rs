@@ -286,7 +286,7 @@ impl WhiteboxTool for NormalVectors {
 // Constructs a plane from a collection of points
 // so that the summed squared distance to all points is minimzized
 #[inline]
-fn plane_from_points(points: &Vec<Vector3<f64>>) -> Vector3<f64> {
+pub fn plane_from_points(points: &Vec<Vector3<f64>>) -> Vector3<f64> {
     let n = points.len();
     // assert!(n >= 3, "At least three points required");
     if n < 3 {
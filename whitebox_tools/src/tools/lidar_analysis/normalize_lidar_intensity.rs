@@ -0,0 +1,397 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 27, 2018
+Last Modified: January 27, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::f64;
+use std::path;
+use std::io::{Error, ErrorKind};
+use lidar::*;
+use tools::*;
+
+/// Normalizes the intensity values of overlapping flightlines, identified by their point
+/// source IDs (see RecoverFlightlineInfo), to remove the striping that otherwise appears in
+/// intensity images. The flightline with the most points is the reference. The remaining
+/// flightlines are adjusted in turn, each time choosing the flightline that shares the
+/// most grid cells (`--resolution`) with those already adjusted, by comparing its
+/// intensities within the shared cells to those of the adjusted flightlines. Two methods
+/// are available:
+///
+/// * `range`: a linear transformation that maps the 2nd to 98th percentile range of the
+///   flightline's intensities onto that of the reference intensities.
+/// * `histogram`: histogram matching, i.e. each intensity is mapped to the reference
+///   intensity of the same cumulative frequency.
+///
+/// Flightlines that do not overlap any other flightline are left unchanged.
+pub struct NormalizeLidarIntensity {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl NormalizeLidarIntensity {
+    pub fn new() -> NormalizeLidarIntensity {
+        // public constructor
+        let name = "NormalizeLidarIntensity".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Normalizes the intensity values of overlapping flightlines to remove striping.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Method".to_owned(),
+            flags: vec!["--method".to_owned()],
+            description: "Normalization method; options are 'range' and 'histogram' (default).".to_owned(),
+            parameter_type: ParameterType::OptionList(vec!["range".to_owned(), "histogram".to_owned()]),
+            default_value: Some("histogram".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Overlap Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Resolution of the grid used to identify overlapping areas.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("2.0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --method=histogram --resolution=2.0", short_exe, name).replace("*", &sep);
+
+        NormalizeLidarIntensity {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for NormalizeLidarIntensity {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut histogram_matching = true;
+        let mut grid_res = 2.0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-method" {
+                let method = if keyval {
+                    vec[1].to_lowercase()
+                } else {
+                    args[i + 1].to_lowercase()
+                };
+                histogram_matching = !method.contains("range");
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if grid_res <= 0f64 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The grid resolution must be greater than zero."));
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        // bin the points of each flightline into the grid cells
+        let west = input.header.min_x;
+        let north = input.header.max_y;
+        let columns = (((input.header.max_x - west) / grid_res).floor() as usize) + 1;
+        let mut cell = vec![0usize; n_points];
+        let mut intensity = vec![0f64; n_points];
+        let mut flightline_points: HashMap<u16, Vec<usize>> = HashMap::new();
+        let mut flightline_cells: HashMap<u16, HashSet<usize>> = HashMap::new();
+        for i in 0..n_points {
+            let p: PointData = input.get_point_info(i);
+            let row = ((north - p.y) / grid_res).floor() as usize;
+            let col = ((p.x - west) / grid_res).floor() as usize;
+            cell[i] = row * columns + col;
+            intensity[i] = p.intensity as f64;
+            if !p.class_bit_field.withheld() && !p.is_classified_noise() {
+                flightline_points.entry(p.point_source_id).or_insert(vec![]).push(i);
+                flightline_cells.entry(p.point_source_id).or_insert(HashSet::new()).insert(cell[i]);
+            }
+        }
+
+        let num_flightlines = flightline_points.len();
+        if verbose { println!("Number of flightlines: {}", num_flightlines); }
+        if num_flightlines < 2 {
+            println!("Warning: The input file contains fewer than two flightlines (point source IDs); the intensities are unchanged.");
+        }
+
+        // the reference flightline is the one with the most points
+        let mut remaining: Vec<u16> = flightline_points.keys().cloned().collect();
+        remaining.sort();
+        let mut adjusted_cells: HashMap<usize, Vec<usize>> = HashMap::new(); // cell -> adjusted points
+        if let Some(reference) = remaining.iter().cloned().max_by_key(|id| flightline_points[id].len()) {
+            remaining.retain(|id| *id != reference);
+            for &i in &flightline_points[&reference] {
+                adjusted_cells.entry(cell[i]).or_insert(vec![]).push(i);
+            }
+            if verbose { println!("Reference flightline: {}", reference); }
+        }
+
+        let mut num_adjusted = 0;
+        while !remaining.is_empty() {
+            // find the flightline sharing the most cells with the adjusted flightlines
+            let mut best = 0usize;
+            let mut best_overlap = 0usize;
+            for k in 0..remaining.len() {
+                let overlap = flightline_cells[&remaining[k]].iter().filter(|c| adjusted_cells.contains_key(c)).count();
+                if overlap > best_overlap {
+                    best_overlap = overlap;
+                    best = k;
+                }
+            }
+            let id = remaining.remove(best);
+            if best_overlap == 0 {
+                if verbose { println!("Flightline {} does not overlap the others and is unchanged.", id); }
+            } else {
+                let overlap_cells: HashSet<usize> = flightline_cells[&id].iter().filter(|c| adjusted_cells.contains_key(c)).cloned().collect();
+                let mut source: Vec<f64> = flightline_points[&id].iter().filter(|i| overlap_cells.contains(&cell[**i])).map(|i| intensity[*i]).collect();
+                let mut target: Vec<f64> = vec![];
+                for c in &overlap_cells {
+                    for &i in &adjusted_cells[c] {
+                        target.push(intensity[i]);
+                    }
+                }
+                source.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                target.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                if histogram_matching {
+                    for &i in &flightline_points[&id] {
+                        intensity[i] = match_histogram(intensity[i], &source, &target);
+                    }
+                } else {
+                    let (s_low, s_high) = (percentile(&source, 2f64), percentile(&source, 98f64));
+                    let (t_low, t_high) = (percentile(&target, 2f64), percentile(&target, 98f64));
+                    let gain = if s_high > s_low { (t_high - t_low) / (s_high - s_low) } else { 1f64 };
+                    for &i in &flightline_points[&id] {
+                        intensity[i] = t_low + (intensity[i] - s_low) * gain;
+                    }
+                }
+                num_adjusted += 1;
+                if verbose {
+                    println!("Flightline {} adjusted using {} overlapping cells", id, overlap_cells.len());
+                }
+            }
+            for &i in &flightline_points[&id] {
+                adjusted_cells.entry(cell[i]).or_insert(vec![]).push(i);
+            }
+        }
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        for i in 0..n_points {
+            let value = intensity[i].round().max(0f64).min(u16::max_value() as f64) as u16;
+            let pr2: LidarPointRecord;
+            match input.get_record(i) {
+                LidarPointRecord::PointRecord0 { mut point_data }  => {
+                    point_data.intensity = value;
+                    pr2 = LidarPointRecord::PointRecord0 { point_data: point_data };
+                },
+                LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
+                    point_data.intensity = value;
+                    pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
+                },
+                LidarPointRecord::PointRecord2 { mut point_data, rgb_data } => {
+                    point_data.intensity = value;
+                    pr2 = LidarPointRecord::PointRecord2 { point_data: point_data, rgb_data: rgb_data };
+                },
+                LidarPointRecord::PointRecord3 { mut point_data, gps_data, rgb_data } => {
+                    point_data.intensity = value;
+                    pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
+                        gps_data: gps_data, rgb_data: rgb_data};
+                },
+            }
+            output.add_point_record(pr2);
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if verbose {
+            println!("Number of adjusted flightlines: {}", num_adjusted);
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a percentile of sorted data, linearly interpolating between ranks.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let rank = p / 100f64 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = (lower + 1).min(sorted.len() - 1);
+    sorted[lower] + (rank - lower as f64) * (sorted[upper] - sorted[lower])
+}
+
+/// Maps a value to the target value of the same cumulative frequency, given sorted
+/// source and target samples.
+fn match_histogram(value: f64, source: &[f64], target: &[f64]) -> f64 {
+    // the mid-rank of the value among the source samples
+    let below = count_while(source, |v| v < value);
+    let not_above = count_while(source, |v| v <= value);
+    let rank = (below + not_above) as f64 / 2f64;
+    let q = (rank / source.len() as f64).max(0f64).min(1f64);
+    percentile(target, q * 100f64)
+}
+
+/// Returns the number of leading elements of sorted data satisfying a predicate, which
+/// must be true for a prefix of the data, using a binary search.
+fn count_while<F>(sorted: &[f64], pred: F) -> usize where F: Fn(f64) -> bool {
+    let (mut low, mut high) = (0usize, sorted.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        if pred(sorted[mid]) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
@@ -0,0 +1,380 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 12, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::f64::consts::PI;
+use std::io::{Error, ErrorKind};
+use std::path;
+use lidar::*;
+use raster::*;
+use structures::Point2D;
+use tools::*;
+
+/// Creates a canopy height model (CHM) using the pit-free algorithm of Khosravipour et al. (2014).
+/// First-return points, from a height-normalized LAS file, are triangulated at a series of
+/// height thresholds and the resulting partial CHMs are merged by taking the maximum value
+/// in each grid cell. Each partial CHM is triangulated from the points at or above its
+/// threshold, excluding triangles with edges longer than a maximum length, which removes the
+/// pits caused by first returns that penetrate deep into the canopy before reaching a hard
+/// target. A base layer of all of the first returns, without the edge-length limit, fills any
+/// gaps between the partial CHMs.
+///
+/// Reference:
+/// Khosravipour, A., Skidmore, A. K., Isenburg, M., Wang, T., & Hussin, Y. A. (2014).
+/// Generating pit-free canopy height models from airborne lidar. Photogrammetric
+/// Engineering & Remote Sensing, 80(9), 863-872.
+pub struct PitFreeChm {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl PitFreeChm {
+    pub fn new() -> PitFreeChm {
+        // public constructor
+        let name = "PitFreeChm".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Creates a pit-free canopy height model (CHM) from a height-normalized LAS file.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input height-normalized LiDAR file (see LidarHeightNormalization).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output CHM raster file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Grid Resolution".to_owned(),
+            flags: vec!["--resolution".to_owned()],
+            description: "Output raster's grid resolution.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.5".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Height Thresholds".to_owned(),
+            flags: vec!["--thresholds".to_owned()],
+            description: "Comma-separated list of the height thresholds of the partial CHMs.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("0,2,5,10,15,20,25,30".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Triangle Edge Length".to_owned(),
+            flags: vec!["--max_triangle_edge_length".to_owned()],
+            description: "Maximum triangle edge length within the above-ground layers (the freeze distance).".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("1.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Subcircle Radius".to_owned(),
+            flags: vec!["--subcircle".to_owned()],
+            description: "Radius of the circle of additional points used to represent each first return, approximating the laser beam's footprint; zero for none.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Height (optional)".to_owned(),
+            flags: vec!["--max_height".to_owned()],
+            description: "Optional maximum height; higher points (e.g. birds) are excluded.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: None,
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=normalized.las -o=chm.tif --resolution=0.5 --thresholds='0,2,5,10,15,20' --max_triangle_edge_length=1.5 --subcircle=0.15", short_exe, name).replace("*", &sep);
+
+        PitFreeChm {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for PitFreeChm {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self,
+               args: Vec<String>,
+               working_directory: &'a str,
+               verbose: bool)
+               -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut grid_res: f64 = 0.5;
+        let mut thresholds_str = "0,2,5,10,15,20,25,30".to_string();
+        let mut max_edge_length = 1.0f64;
+        let mut subcircle = 0.0f64;
+        let mut max_height = f64::INFINITY;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-resolution" {
+                grid_res = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-thresholds" {
+                thresholds_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-max_triangle_edge_length" {
+                max_edge_length = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-subcircle" {
+                subcircle = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_height" {
+                max_height = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        let mut thresholds: Vec<f64> = vec![];
+        for s in thresholds_str.split(|c| c == ',' || c == ';') {
+            if !s.trim().is_empty() {
+                match s.trim().parse::<f64>() {
+                    Ok(t) if t.is_finite() => thresholds.push(t),
+                    _ => return Err(Error::new(ErrorKind::InvalidInput,
+                        format!("Unrecognized height threshold '{}'.", s.trim()))),
+                }
+            }
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if thresholds.len() == 0 {
+            thresholds.push(0f64);
+        }
+
+        if !input_file.contains(path::MAIN_SEPARATOR) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(path::MAIN_SEPARATOR) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let start = time::now();
+
+        if verbose {
+            println!("Reading input LAS file...");
+        }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(_) => {
+                return Err(Error::new(ErrorKind::NotFound,
+                                      format!("No such file or directory ({})", input_file)))
+            }
+        };
+
+        let start_run = time::now();
+
+        // gather the first returns, surrounding each with a subcircle of points if requested
+        let n_points = input.header.number_of_points as usize;
+        let mut points: Vec<Point2D> = vec![];
+        let mut heights: Vec<f64> = vec![];
+        let num_subcircle_pts = if subcircle > 0f64 { 8 } else { 0 };
+        for i in 0..n_points {
+            let p: PointData = input[i];
+            if p.is_early_return() && !p.class_bit_field.withheld() && p.z <= max_height {
+                let h = p.z.max(0f64);
+                points.push(Point2D::new(p.x, p.y));
+                heights.push(h);
+                for k in 0..num_subcircle_pts {
+                    let angle = k as f64 * 2f64 * PI / num_subcircle_pts as f64;
+                    points.push(Point2D::new(p.x + subcircle * angle.cos(), p.y + subcircle * angle.sin()));
+                    heights.push(h);
+                }
+            }
+        }
+        if points.len() < 3 {
+            return Err(Error::new(ErrorKind::InvalidInput, "The input file does not contain enough first-return points."));
+        }
+
+        let west = input.header.min_x;
+        let north = input.header.max_y;
+        let rows = ((((north - input.header.min_y) / grid_res).ceil()) as usize).max(1);
+        let columns = ((((input.header.max_x - west) / grid_res).ceil()) as usize).max(1);
+        let nodata = -32768.0f64;
+
+        let mut configs = RasterConfigs { ..Default::default() };
+        configs.rows = rows;
+        configs.columns = columns;
+        configs.north = north;
+        configs.south = north - rows as f64 * grid_res;
+        configs.east = west + columns as f64 * grid_res;
+        configs.west = west;
+        configs.resolution_x = grid_res;
+        configs.resolution_y = grid_res;
+        configs.nodata = nodata;
+        configs.data_type = DataType::F32;
+        configs.photometric_interp = PhotometricInterpretation::Continuous;
+        let mut output = Raster::initialize_using_config(&output_file, &configs);
+
+        // the base layer is interpolated from all first returns and is unrestricted in its
+        // edge lengths, filling any gaps left by the layers above.
+        tin_interpolation(&points, &heights, f64::INFINITY, TinMerge::Max, &mut output);
+        if verbose {
+            println!("Base layer complete");
+        }
+
+        for l in 0..thresholds.len() {
+            let mut layer_points = vec![];
+            let mut layer_heights = vec![];
+            for j in 0..points.len() {
+                if heights[j] >= thresholds[l] {
+                    layer_points.push(points[j]);
+                    layer_heights.push(heights[j]);
+                }
+            }
+            tin_interpolation(&layer_points, &layer_heights, max_edge_length, TinMerge::Max, &mut output);
+            if verbose {
+                println!("Layer {} of {} (height >= {}) complete", l + 1, thresholds.len(), thresholds[l]);
+            }
+        }
+
+        let end_run = time::now();
+        let elapsed_time_run = end_run - start_run;
+
+        output.add_metadata_entry(format!("Created by whitebox_tools\' {} tool", self.get_tool_name()));
+        output.add_metadata_entry(format!("Input file: {}", input_file));
+        output.add_metadata_entry(format!("Grid resolution: {}", grid_res));
+        output.add_metadata_entry(format!("Height thresholds: {}", thresholds_str));
+        output.add_metadata_entry(format!("Max triangle edge length: {}", max_edge_length));
+        output.add_metadata_entry(format!("Subcircle radius: {}", subcircle));
+        output.add_metadata_entry(format!("Elapsed Time (excluding I/O): {}", elapsed_time_run).replace("PT", ""));
+
+        if verbose {
+            println!("Saving data...")
+        };
+        let _ = match output.write() {
+            Ok(_) => {
+                if verbose {
+                    println!("Output file written")
+                }
+            }
+            Err(e) => return Err(e),
+        };
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (including I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,535 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 21, 2018
+Last Modified: January 21, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::fs::File;
+use std::io::prelude::*;
+use std;
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::path;
+use io_utils::{ByteOrderReader, Endianness};
+use lidar::*;
+use tools::*;
+
+/// Converts one or more PLY (Polygon File Format) files, in the ASCII, binary
+/// little-endian or binary big-endian encodings, into LAS files. The vertices of each
+/// PLY file become the LAS points. The 'x', 'y' and 'z' vertex properties are required,
+/// and the 'intensity', 'classification', 'gps_time', 'red', 'green' and 'blue' properties
+/// are mapped to the corresponding LAS attributes, when present. Vertex normals ('nx',
+/// 'ny' and 'nz') are stored in the RGB field using the same encoding as the NormalVectors
+/// tool, when the file does not contain colours. All other elements (e.g. faces) and
+/// properties are ignored.
+pub struct PlyToLas {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl PlyToLas {
+    pub fn new() -> PlyToLas { // public constructor
+        let name = "PlyToLas".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Converts one or more PLY files into LAS files.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input PLY Files".to_owned(),
+            flags: vec!["-i".to_owned(), "--inputs".to_owned()],
+            description: "Input PLY files; each is converted into a LAS file of the same name.".to_owned(),
+            parameter_type: ParameterType::FileList(ParameterFileType::Any),
+            default_value: None,
+            optional: false
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "").replace(".exe", "").replace(".", "").replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"file1.ply, file2.ply\"", short_exe, name).replace("*", &sep);
+
+        PlyToLas {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for PlyToLas {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_files: String = String::new();
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 { keyval = true; }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-inputs" {
+                input_files = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = std::path::MAIN_SEPARATOR;
+
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+
+        let start = time::now();
+
+        let mut cmd = input_files.split(";");
+        let mut vec = cmd.collect::<Vec<&str>>();
+        if vec.len() == 1 {
+            cmd = input_files.split(",");
+            vec = cmd.collect::<Vec<&str>>();
+        }
+        let mut file_num = 1;
+        let num_files = vec.len();
+        for value in vec {
+            if !value.trim().is_empty() {
+                let mut input_file = value.trim().to_owned();
+                if !input_file.contains(sep) {
+                    input_file = format!("{}{}", working_directory, input_file);
+                }
+                let output_file = match input_file.rfind('.') {
+                    Some(idx) => format!("{}.las", &input_file[..idx]),
+                    None => format!("{}.las", input_file),
+                };
+
+                let mut f = match File::open(&input_file) {
+                    Ok(f) => f,
+                    Err(_) => return Err(Error::new(ErrorKind::NotFound, format!("No such file or directory ({})", input_file))),
+                };
+                let mut buffer = vec![];
+                f.read_to_end(&mut buffer)?;
+
+                let header = PlyHeader::read(&buffer)?;
+                let vertex_element = match header.elements.iter().position(|e| e.name == "vertex") {
+                    Some(idx) => idx,
+                    None => return Err(Error::new(ErrorKind::InvalidData, format!("{} does not contain vertices.", input_file))),
+                };
+                let vertex = &header.elements[vertex_element];
+                let prop_index = |name: &str| vertex.properties.iter().position(|p| p.name == name);
+                let (ix, iy, iz) = match (prop_index("x"), prop_index("y"), prop_index("z")) {
+                    (Some(ix), Some(iy), Some(iz)) => (ix, iy, iz),
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "The PLY vertices must have x, y and z properties.")),
+                };
+                let i_intensity = prop_index("intensity").or(prop_index("scalar_intensity"));
+                let i_class = prop_index("classification").or(prop_index("scalar_classification"));
+                let i_time = prop_index("gps_time").or(prop_index("scalar_gps_time"));
+                let (i_red, i_green, i_blue) = (prop_index("red"), prop_index("green"), prop_index("blue"));
+                let (i_nx, i_ny, i_nz) = (prop_index("nx"), prop_index("ny"), prop_index("nz"));
+                let has_rgb = i_red.is_some() && i_green.is_some() && i_blue.is_some();
+                let has_normals = !has_rgb && i_nx.is_some() && i_ny.is_some() && i_nz.is_some();
+                let has_time = i_time.is_some();
+                let point_format = match (has_time, has_rgb || has_normals) {
+                    (false, false) => 0u8,
+                    (true, false) => 1u8,
+                    (false, true) => 2u8,
+                    (true, true) => 3u8,
+                };
+
+                let mut output = LasFile::new(&output_file, "w")?;
+                let mut las_header: LasHeader = Default::default();
+                las_header.project_id_used = true;
+                las_header.point_format = point_format;
+                output.add_header(las_header);
+
+                let mut reader = PlyVertexReader::new(buffer, &header, vertex_element)?;
+                let n_points = vertex.count;
+                for k in 0..n_points {
+                    let vals = reader.next_vertex()?;
+                    let mut p: PointData = Default::default();
+                    p.x = vals[ix];
+                    p.y = vals[iy];
+                    p.z = vals[iz];
+                    if let Some(idx) = i_intensity {
+                        p.intensity = vals[idx] as u16;
+                    }
+                    if let Some(idx) = i_class {
+                        p.set_classification(vals[idx] as u8);
+                    }
+                    p.bit_field = PointBitField { value: 0b0000_1001u8 }; // return 1 of 1
+                    let gps_time = match i_time {
+                        Some(idx) => vals[idx],
+                        None => 0f64,
+                    };
+                    let mut rgb: RgbData = Default::default();
+                    if has_rgb {
+                        let colour_scale = match vertex.properties[i_red.unwrap()].data_type {
+                            PlyType::U8 | PlyType::I8 => 256f64,
+                            PlyType::F32 | PlyType::F64 => 65535f64,
+                            _ => 1f64,
+                        };
+                        rgb.red = (vals[i_red.unwrap()] * colour_scale) as u16;
+                        rgb.green = (vals[i_green.unwrap()] * colour_scale) as u16;
+                        rgb.blue = (vals[i_blue.unwrap()] * colour_scale) as u16;
+                    } else if has_normals {
+                        // the same encoding used by the NormalVectors tool
+                        rgb.red = ((1.0 + vals[i_nx.unwrap()]) / 2.0 * 255.0) as u16 * 256u16;
+                        rgb.green = ((1.0 + vals[i_ny.unwrap()]) / 2.0 * 255.0) as u16 * 256u16;
+                        rgb.blue = ((1.0 + vals[i_nz.unwrap()]) / 2.0 * 255.0) as u16 * 256u16;
+                    }
+                    let pr = match point_format {
+                        0 => LidarPointRecord::PointRecord0 { point_data: p },
+                        1 => LidarPointRecord::PointRecord1 { point_data: p, gps_data: gps_time },
+                        2 => LidarPointRecord::PointRecord2 { point_data: p, rgb_data: rgb },
+                        _ => LidarPointRecord::PointRecord3 { point_data: p, gps_data: gps_time, rgb_data: rgb },
+                    };
+                    output.add_point_record(pr);
+
+                    if verbose {
+                        progress = (100.0_f64 * k as f64 / (n_points - 1).max(1) as f64) as usize;
+                        if progress != old_progress {
+                            if num_files > 1 {
+                                println!("Creating file: {} of {}: {}%", file_num, num_files, progress);
+                            } else {
+                                println!("Progress: {}%", progress);
+                            }
+                            old_progress = progress;
+                        }
+                    }
+                }
+
+                // the scale factor must allow the full range of coordinates to be stored as 32-bit integers
+                let mut scale = 0.001f64;
+                let max_range = (output.header.max_x - output.header.min_x)
+                    .max(output.header.max_y - output.header.min_y)
+                    .max(output.header.max_z - output.header.min_z);
+                while max_range / scale > i32::max_value() as f64 {
+                    scale *= 10f64;
+                }
+                output.header.x_scale_factor = scale;
+                output.header.y_scale_factor = scale;
+                output.header.z_scale_factor = scale;
+
+                output.write()?;
+                if verbose {
+                    println!("Output file written: {}", output_file);
+                }
+            }
+            file_num += 1;
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+        println!("{}", &format!("Elapsed Time: {}", elapsed_time).replace("PT", ""));
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn from_str(s: &str) -> Option<PlyType> {
+        match s {
+            "char" | "int8" => Some(PlyType::I8),
+            "uchar" | "uint8" => Some(PlyType::U8),
+            "short" | "int16" => Some(PlyType::I16),
+            "ushort" | "uint16" => Some(PlyType::U16),
+            "int" | "int32" => Some(PlyType::I32),
+            "uint" | "uint32" => Some(PlyType::U32),
+            "float" | "float32" => Some(PlyType::F32),
+            "double" | "float64" => Some(PlyType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match *self {
+            PlyType::I8 | PlyType::U8 => 1,
+            PlyType::I16 | PlyType::U16 => 2,
+            PlyType::I32 | PlyType::U32 | PlyType::F32 => 4,
+            PlyType::F64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PlyProperty {
+    name: String,
+    data_type: PlyType,
+    // the type of the item count, for list properties
+    list_count_type: Option<PlyType>,
+}
+
+#[derive(Clone, Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+#[derive(Clone, Debug)]
+enum PlyFormat {
+    Ascii,
+    Binary(Endianness),
+}
+
+#[derive(Clone, Debug)]
+struct PlyHeader {
+    format: PlyFormat,
+    elements: Vec<PlyElement>,
+    // the byte offset of the body
+    length: usize,
+}
+
+impl PlyHeader {
+    fn read(buffer: &[u8]) -> Result<PlyHeader, Error> {
+        if buffer.len() < 4 || &buffer[0..3] != b"ply" {
+            return Err(Error::new(ErrorKind::InvalidData, "The file is not a PLY file."));
+        }
+        let mut format = PlyFormat::Ascii;
+        let mut elements: Vec<PlyElement> = vec![];
+        let mut pos = 0usize;
+        loop {
+            let end = match buffer[pos..].iter().position(|&b| b == b'\n') {
+                Some(e) => pos + e,
+                None => return Err(Error::new(ErrorKind::InvalidData, "The PLY header is incomplete.")),
+            };
+            let line = String::from_utf8_lossy(&buffer[pos..end]).trim().to_string();
+            pos = end + 1;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() == 0 {
+                continue;
+            }
+            match words[0] {
+                "end_header" => break,
+                "format" if words.len() > 1 => {
+                    format = match words[1] {
+                        "ascii" => PlyFormat::Ascii,
+                        "binary_little_endian" => PlyFormat::Binary(Endianness::LittleEndian),
+                        "binary_big_endian" => PlyFormat::Binary(Endianness::BigEndian),
+                        _ => return Err(Error::new(ErrorKind::InvalidData, format!("Unsupported PLY format '{}'.", words[1]))),
+                    };
+                },
+                "element" if words.len() > 2 => {
+                    let count = match words[2].parse::<usize>() {
+                        Ok(c) => c,
+                        Err(_) => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid PLY element '{}'.", line))),
+                    };
+                    elements.push(PlyElement { name: words[1].to_string(), count: count, properties: vec![] });
+                },
+                "property" if words.len() > 2 && elements.len() > 0 => {
+                    let property = if words[1] == "list" && words.len() > 4 {
+                        match (PlyType::from_str(words[2]), PlyType::from_str(words[3])) {
+                            (Some(count_type), Some(data_type)) => PlyProperty {
+                                name: words[4].to_string(),
+                                data_type: data_type,
+                                list_count_type: Some(count_type),
+                            },
+                            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid PLY property '{}'.", line))),
+                        }
+                    } else {
+                        match PlyType::from_str(words[1]) {
+                            Some(data_type) => PlyProperty {
+                                name: words[2].to_string(),
+                                data_type: data_type,
+                                list_count_type: None,
+                            },
+                            None => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid PLY property '{}'.", line))),
+                        }
+                    };
+                    let idx = elements.len() - 1;
+                    elements[idx].properties.push(property);
+                },
+                _ => {}, // comments, obj_info, etc.
+            }
+        }
+        Ok(PlyHeader { format: format, elements: elements, length: pos })
+    }
+}
+
+/// Reads the vertices of a PLY file, returning the values of each vertex's scalar properties.
+struct PlyVertexReader {
+    format: PlyFormat,
+    properties: Vec<PlyProperty>,
+    binary: ByteOrderReader,
+    lines: Vec<String>,
+    line_num: usize,
+}
+
+impl PlyVertexReader {
+    fn new(buffer: Vec<u8>, header: &PlyHeader, vertex_element: usize) -> Result<PlyVertexReader, Error> {
+        let properties = header.elements[vertex_element].properties.clone();
+        match header.format {
+            PlyFormat::Ascii => {
+                // each element item occupies a line
+                let skip: usize = header.elements[0..vertex_element].iter().map(|e| e.count).sum();
+                let lines: Vec<String> = String::from_utf8_lossy(&buffer[header.length..])
+                    .lines()
+                    .skip(skip)
+                    .take(header.elements[vertex_element].count)
+                    .map(|s| s.to_string())
+                    .collect();
+                Ok(PlyVertexReader {
+                    format: PlyFormat::Ascii,
+                    properties: properties,
+                    binary: ByteOrderReader::new(vec![], Endianness::LittleEndian),
+                    lines: lines,
+                    line_num: 0,
+                })
+            },
+            PlyFormat::Binary(ref byte_order) => {
+                // skip any preceding elements, which must be of a fixed size
+                let mut offset = header.length;
+                for e in &header.elements[0..vertex_element] {
+                    if e.properties.iter().any(|p| p.list_count_type.is_some()) {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                            "PLY files with list properties preceding the vertex element are unsupported."));
+                    }
+                    offset += e.count * e.properties.iter().map(|p| p.data_type.size()).sum::<usize>();
+                }
+                let mut binary = ByteOrderReader::new(buffer, byte_order.clone());
+                binary.seek(offset);
+                Ok(PlyVertexReader {
+                    format: PlyFormat::Binary(byte_order.clone()),
+                    properties: properties,
+                    binary: binary,
+                    lines: vec![],
+                    line_num: 0,
+                })
+            },
+        }
+    }
+
+    fn next_vertex(&mut self) -> Result<Vec<f64>, Error> {
+        let mut values = vec![0f64; self.properties.len()];
+        match self.format {
+            PlyFormat::Ascii => {
+                if self.line_num >= self.lines.len() {
+                    return Err(Error::new(ErrorKind::InvalidData, "The PLY file contains fewer vertices than expected."));
+                }
+                let words: Vec<&str> = self.lines[self.line_num].split_whitespace().collect();
+                self.line_num += 1;
+                let mut w = 0usize;
+                for p in 0..self.properties.len() {
+                    if self.properties[p].list_count_type.is_some() {
+                        // skip the list items
+                        let n = words.get(w).and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+                        w += n + 1;
+                        continue;
+                    }
+                    values[p] = match words.get(w).and_then(|s| s.parse::<f64>().ok()) {
+                        Some(v) => v,
+                        None => return Err(Error::new(ErrorKind::InvalidData,
+                            format!("Unable to read vertex {} of the PLY file.", self.line_num))),
+                    };
+                    w += 1;
+                }
+            },
+            PlyFormat::Binary(_) => {
+                for p in 0..self.properties.len() {
+                    match self.properties[p].list_count_type {
+                        Some(count_type) => {
+                            if self.binary.pos + count_type.size() > self.binary.buffer.len() {
+                                return Err(Error::new(ErrorKind::InvalidData, "The PLY file contains fewer vertices than expected."));
+                            }
+                            let n = self.read_value(count_type) as usize;
+                            let data_type = self.properties[p].data_type;
+                            if n * data_type.size() > self.binary.buffer.len() - self.binary.pos {
+                                return Err(Error::new(ErrorKind::InvalidData, "The PLY file contains fewer vertices than expected."));
+                            }
+                            self.binary.pos += n * data_type.size();
+                        },
+                        None => {
+                            let data_type = self.properties[p].data_type;
+                            if self.binary.pos + data_type.size() > self.binary.buffer.len() {
+                                return Err(Error::new(ErrorKind::InvalidData, "The PLY file contains fewer vertices than expected."));
+                            }
+                            values[p] = self.read_value(data_type);
+                        },
+                    }
+                }
+            },
+        }
+        Ok(values)
+    }
+
+    fn read_value(&mut self, data_type: PlyType) -> f64 {
+        match data_type {
+            PlyType::I8 => self.binary.read_i8() as f64,
+            PlyType::U8 => self.binary.read_u8() as f64,
+            PlyType::I16 => self.binary.read_i16() as f64,
+            PlyType::U16 => self.binary.read_u16() as f64,
+            PlyType::I32 => self.binary.read_i32() as f64,
+            PlyType::U32 => self.binary.read_u32() as f64,
+            PlyType::F32 => self.binary.read_f32() as f64,
+            PlyType::F64 => self.binary.read_f64(),
+        }
+    }
+}
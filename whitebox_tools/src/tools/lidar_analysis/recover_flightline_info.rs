@@ -0,0 +1,364 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: January 27, 2018
+Last Modified: January 27, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::f64::consts::PI;
+use std::path;
+use std::io::{Error, ErrorKind};
+use lidar::*;
+use tools::*;
+
+/// Reconstructs the flightlines of a LiDAR file that lacks point source IDs, storing the
+/// flightline number (starting at 1) in each point's `point_source_id` field. Points are
+/// sorted by GPS time and a new flightline begins wherever there is a gap between successive
+/// GPS times larger than `--max_time_diff` seconds. Where the scanner continued to collect data
+/// through the aircraft's turns, flightlines are also separated using the scan direction flag:
+/// each run of points with the same flag is a single scan line, the sequence of scan line
+/// centroids traces the ground track of the aircraft, and a new flightline begins wherever
+/// the heading of the ground track deviates from that of the current flightline by more than
+/// `--max_angle` degrees. This second step is skipped if the scan direction flags are unset.
+pub struct RecoverFlightlineInfo {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl RecoverFlightlineInfo {
+    pub fn new() -> RecoverFlightlineInfo {
+        // public constructor
+        let name = "RecoverFlightlineInfo".to_string();
+        let toolbox = "LiDAR Tools".to_string();
+        let description = "Reconstructs flightlines from GPS time gaps and scan direction changes, assigning point source IDs."
+            .to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input LiDAR file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output LiDAR file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Lidar),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Time Difference (s)".to_owned(),
+            flags: vec!["--max_time_diff".to_owned()],
+            description: "Maximum gap in GPS time, in seconds, between successive points of a flightline.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("5.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Maximum Heading Change (degrees)".to_owned(),
+            flags: vec!["--max_angle".to_owned()],
+            description: "Maximum change in the heading of the ground track within a flightline, in degrees.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("45.0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=\"input.las\" -o=\"output.las\" --max_time_diff=5.0 --max_angle=45.0", short_exe, name).replace("*", &sep);
+
+        RecoverFlightlineInfo {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for RecoverFlightlineInfo {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file: String = "".to_string();
+        let mut output_file: String = "".to_string();
+        let mut max_time_diff = 5.0f64;
+        let mut max_angle = 45.0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-max_time_diff" {
+                max_time_diff = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-max_angle" {
+                max_angle = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading input LAS file..."); }
+        let input = match LasFile::new(&input_file, "r") {
+            Ok(lf) => lf,
+            Err(err) => panic!("Error reading file {}: {}", input_file, err),
+        };
+        if input.header.point_format == 0u8 || input.header.point_format == 2u8 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "The input file has a Point Format that does not include GPS time, which is required for the operation of this tool."));
+        }
+
+        let start = time::now();
+
+        let n_points = input.header.number_of_points as usize;
+        let num_points: f64 = (input.header.number_of_points - 1) as f64; // used for progress calculation only
+        let mut progress: i32;
+        let mut old_progress: i32 = -1;
+
+        if verbose { println!("Sorting points by GPS time..."); }
+        let mut gps_times = Vec::with_capacity(n_points);
+        for i in 0..n_points {
+            gps_times.push(input.get_gps_time(i)?);
+        }
+        let mut order: Vec<usize> = (0..n_points).collect();
+        order.sort_by(|a, b| gps_times[*a].partial_cmp(&gps_times[*b]).unwrap());
+
+        // split the points into segments at the gaps in GPS time
+        let mut segments: Vec<(usize, usize)> = vec![]; // ranges within the sorted order
+        let mut segment_start = 0usize;
+        for k in 1..n_points {
+            if gps_times[order[k]] - gps_times[order[k - 1]] > max_time_diff {
+                segments.push((segment_start, k));
+                segment_start = k;
+            }
+        }
+        if n_points > 0 {
+            segments.push((segment_start, n_points));
+        }
+
+        // Further split the segments wherever the heading of the ground track changes. The
+        // ground track is traced by the centroids of the scan lines, i.e. the runs of points
+        // sharing the same scan direction flag.
+        let mut uses_scan_direction = false;
+        if n_points > 0 {
+            let first_flag = input.get_point_info(order[0]).bit_field.scan_direction_flag();
+            uses_scan_direction = order.iter().any(|&i| input.get_point_info(i).bit_field.scan_direction_flag() != first_flag);
+        }
+        let mut flightlines: Vec<(usize, usize)> = vec![];
+        if uses_scan_direction {
+            // the number of scan lines on either side used to estimate the heading
+            let window = 10usize;
+            let max_angle = max_angle.to_radians();
+            for &(seg_start, seg_end) in &segments {
+                let mut scan_lines: Vec<(usize, f64, f64)> = vec![]; // start position, centroid x, y
+                let mut line_start = seg_start;
+                let (mut sum_x, mut sum_y) = (0f64, 0f64);
+                for k in seg_start..seg_end {
+                    let p: PointData = input.get_point_info(order[k]);
+                    if k > line_start && p.bit_field.scan_direction_flag() != input.get_point_info(order[k - 1]).bit_field.scan_direction_flag() {
+                        let n = (k - line_start) as f64;
+                        scan_lines.push((line_start, sum_x / n, sum_y / n));
+                        line_start = k;
+                        sum_x = 0f64;
+                        sum_y = 0f64;
+                    }
+                    sum_x += p.x;
+                    sum_y += p.y;
+                }
+                let n = (seg_end - line_start) as f64;
+                scan_lines.push((line_start, sum_x / n, sum_y / n));
+
+                let num_lines = scan_lines.len();
+                if num_lines < 2 * window + 1 {
+                    flightlines.push((seg_start, seg_end));
+                    continue;
+                }
+                let heading = |j: usize| -> f64 {
+                    let a = scan_lines[if j >= window { j - window } else { 0 }];
+                    let b = scan_lines[(j + window).min(num_lines - 1)];
+                    (b.2 - a.2).atan2(b.1 - a.1)
+                };
+                let mut line_start = seg_start;
+                let mut reference = heading(window);
+                let mut j = window;
+                while j < num_lines - window {
+                    let mut diff = (heading(j) - reference).abs();
+                    if diff > PI {
+                        diff = 2f64 * PI - diff;
+                    }
+                    if diff > max_angle {
+                        flightlines.push((line_start, scan_lines[j].0));
+                        line_start = scan_lines[j].0;
+                        // skip ahead until the aircraft has completed its turn
+                        j = (j + 2 * window).min(num_lines - 1);
+                        reference = heading(j);
+                    }
+                    j += 1;
+                }
+                flightlines.push((line_start, seg_end));
+            }
+        } else {
+            flightlines = segments;
+        }
+
+        if flightlines.len() > u16::max_value() as usize {
+            return Err(Error::new(ErrorKind::InvalidData,
+                "The number of flightlines exceeds the maximum point source ID; try increasing the maximum time difference."));
+        }
+
+        let mut flightline_id = vec![0u16; n_points];
+        for f in 0..flightlines.len() {
+            for k in flightlines[f].0..flightlines[f].1 {
+                flightline_id[order[k]] = (f + 1) as u16;
+            }
+        }
+
+        // now output the data
+        let mut output = LasFile::initialize_using_file(&output_file, &input);
+        output.header.system_id = "EXTRACTION".to_string();
+        for i in 0..n_points {
+            let pr2: LidarPointRecord;
+            match input.get_record(i) {
+                LidarPointRecord::PointRecord1 { mut point_data, gps_data } => {
+                    point_data.point_source_id = flightline_id[i];
+                    pr2 = LidarPointRecord::PointRecord1 { point_data: point_data, gps_data: gps_data };
+                },
+                LidarPointRecord::PointRecord3 { mut point_data, gps_data, rgb_data } => {
+                    point_data.point_source_id = flightline_id[i];
+                    pr2 = LidarPointRecord::PointRecord3 { point_data: point_data,
+                        gps_data: gps_data, rgb_data: rgb_data};
+                },
+                pr => pr2 = pr, // formats without GPS time were rejected above
+            }
+            output.add_point_record(pr2);
+            if verbose {
+                progress = (100.0_f64 * i as f64 / num_points) as i32;
+                if progress != old_progress {
+                    println!("Saving data: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        if verbose {
+            println!("Number of flightlines: {}", flightlines.len());
+            println!("ID\tPoints\tStart Time\tEnd Time");
+            for f in 0..flightlines.len() {
+                let (s, e) = flightlines[f];
+                println!("{}\t{}\t{:.3}\t{:.3}", f + 1, e - s, gps_times[order[s]], gps_times[order[e - 1]]);
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Writing output LAS file..."); }
+        let _ = match output.write() {
+            Ok(_) => println!("Complete!"),
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,555 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 16, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use structures::Point2D;
+use tools::*;
+use vector::*;
+
+/// Calculates statistics of the values of a raster within each polygon of a vector of zones.
+/// Each polygon is treated independently, such that zones may overlap, and polygon holes are
+/// excluded from their zones. By default, the cells whose centres lie within a polygon make up
+/// its zone. With `--weighted`, every cell that overlaps a polygon is included and weighted by
+/// the fraction of its area that is covered by the polygon, such that small zones are not
+/// dominated by cells that only touch them.
+///
+/// The statistics are the COUNT of cells (the sum of the weights, for weighted statistics), the
+/// MIN, MAX, MEAN and standard deviation (STDEV) of the values, the MEDIAN, the MAJORITY value
+/// (the value with the greatest total weight) and a list of percentiles (`--percentiles`), which
+/// are named P followed by the percentile, e.g. P25. Statistics whose names are already used by
+/// fields of the zones are given a numbered suffix, e.g. MEAN_2. Weighted percentiles are the smallest value
+/// at which the cumulative weight of the sorted values reaches the percentage of the total weight.
+/// Nodata cells are excluded and zones that contain no valid cells have null statistics.
+///
+/// The statistics are appended, as new attribute fields, to a copy of the zones vector
+/// (`--output`) and/or written to a CSV table (`--out_table`), in which each zone is identified
+/// by the value of an attribute field (`--field`), or by default its feature ID (FID).
+pub struct ZonalStatistics {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ZonalStatistics {
+    pub fn new() -> ZonalStatistics {
+        // public constructor
+        let name = "ZonalStatistics".to_string();
+        let toolbox = "Math and Stats Tools".to_string();
+        let description = "Calculates statistics of the values of a raster within each polygon of a vector of zones.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Data File".to_owned(),
+            flags: vec!["-i".to_owned(), "--input".to_owned()],
+            description: "Input data raster file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input Zones File".to_owned(),
+            flags: vec!["--zones".to_owned()],
+            description: "Input polygon vector zones file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output Vector File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output polygon vector file, a copy of the zones with the statistics as attributes.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output CSV Table File".to_owned(),
+            flags: vec!["--out_table".to_owned()],
+            description: "Output CSV table file.".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Text),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Zone ID Field".to_owned(),
+            flags: vec!["--field".to_owned()],
+            description: "Attribute field identifying the zones in the CSV table; 'FID' for the feature ID.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("FID".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Percentiles".to_owned(),
+            flags: vec!["--percentiles".to_owned()],
+            description: "Comma-separated list of percentiles to calculate.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: Some("25,75".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Weight cells by polygon coverage?".to_owned(),
+            flags: vec!["--weighted".to_owned()],
+            description: "Weight each cell by the fraction of its area covered by the polygon.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: Some("false".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=dem.tif --zones=watersheds.shp -o=watershed_stats.shp --weighted
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=dem.tif --zones=fields.geojson --out_table=field_stats.csv --field=FIELD_ID --percentiles='10,50,90'", short_exe, name).replace("*", &sep);
+
+        ZonalStatistics {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ZonalStatistics {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file = String::new();
+        let mut zones_file = String::new();
+        let mut output_file = String::new();
+        let mut table_file = String::new();
+        let mut field_name = String::from("FID");
+        let mut percentiles_str = String::from("25,75");
+        let mut weighted = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-zones" {
+                zones_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-out_table" {
+                table_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-field" {
+                field_name = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-percentiles" {
+                percentiles_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-weighted" {
+                weighted = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !zones_file.contains(sep) {
+            zones_file = format!("{}{}", working_directory, zones_file);
+        }
+        if !output_file.is_empty() && !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+        if !table_file.is_empty() && !table_file.contains(sep) {
+            table_file = format!("{}{}", working_directory, table_file);
+        }
+        if output_file.is_empty() && table_file.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                "At least one of --output or --out_table must be specified."));
+        }
+
+        let mut percentiles: Vec<f64> = vec![];
+        for s in percentiles_str.split(",") {
+            if s.trim().is_empty() {
+                continue;
+            }
+            match s.trim().parse::<f64>() {
+                Ok(p) if p >= 0f64 && p <= 100f64 => percentiles.push(p),
+                _ => return Err(Error::new(ErrorKind::InvalidInput, format!("The percentile '{}' is not a number between 0 and 100.", s.trim()))),
+            }
+        }
+
+        if verbose { println!("Reading data...") };
+        let input = Raster::new(&input_file, "r")?;
+        let zones = Shapefile::new(&zones_file, "r")?;
+        if zones.header.shape_type.base_shape_type() != ShapeType::Polygon {
+            return Err(Error::new(ErrorKind::InvalidInput, "The input zones vector must contain polygons."));
+        }
+
+        let id_field = if table_file.is_empty() {
+            None
+        } else {
+            zones.attributes.get_field_or_fid(&field_name, false)?
+        };
+
+        let start = time::now();
+
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+        let (west, north) = (input.configs.west, input.configs.north);
+        let (res_x, res_y) = (input.configs.resolution_x, input.configs.resolution_y);
+        let cell_area = res_x * res_y;
+        let grid = Grid::new(&input.configs);
+
+        let num_zones = zones.num_records;
+        let mut zone_stats: Vec<Option<ZoneStats>> = Vec::with_capacity(num_zones);
+        let mut values: Vec<(f64, f64)> = vec![]; // (value, weight)
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for record_num in 0..num_zones {
+            let record = zones.get_record(record_num);
+            values.clear();
+            if record.shape_type != ShapeType::Null && record.num_points > 0 {
+                if weighted {
+                    let row_start = (((north - record.y_max) / res_y).floor() as isize).max(0);
+                    let row_end = (((north - record.y_min) / res_y).floor() as isize).min(rows - 1);
+                    let col_start = (((record.x_min - west) / res_x).floor() as isize).max(0);
+                    let col_end = (((record.x_max - west) / res_x).floor() as isize).min(columns - 1);
+                    let rings: Vec<&[Point2D]> = (0..record.num_parts as usize).map(|part| {
+                        let (start, end) = record.part_range(part);
+                        &record.points[start..end]
+                    }).collect();
+                    for row in row_start..row_end + 1 {
+                        let y_top = north - row as f64 * res_y;
+                        let y_bottom = y_top - res_y;
+                        // clip the polygon's rings to the row, then to each cell; the signed areas of
+                        // the clipped rings are positive for outer rings and negative for holes
+                        let strips: Vec<Vec<Point2D>> = rings.iter().map(|ring| {
+                            let strip = clip_ring(ring, |p| p.y >= y_bottom, |p, q| intersect_y(p, q, y_bottom));
+                            clip_ring(&strip, |p| p.y <= y_top, |p, q| intersect_y(p, q, y_top))
+                        }).filter(|strip| strip.len() > 2).collect();
+                        if strips.is_empty() {
+                            continue;
+                        }
+                        for col in col_start..col_end + 1 {
+                            let value = input.get_value(row, col);
+                            if value == nodata {
+                                continue;
+                            }
+                            let x_left = west + col as f64 * res_x;
+                            let x_right = x_left + res_x;
+                            let mut area = 0f64;
+                            for strip in &strips {
+                                let cell = clip_ring(strip, |p| p.x >= x_left, |p, q| intersect_x(p, q, x_left));
+                                let cell = clip_ring(&cell, |p| p.x <= x_right, |p, q| intersect_x(p, q, x_right));
+                                area += signed_area(&cell);
+                            }
+                            let weight = (area.abs() / cell_area).min(1f64);
+                            if weight > 1e-9 {
+                                values.push((value, weight));
+                            }
+                        }
+                    }
+                } else {
+                    // the cells with centres inside of the polygon, using the even-odd rule
+                    fill_polygon(&grid, record, &mut |row, col| {
+                        let value = input.get_value(row, col);
+                        if value != nodata {
+                            values.push((value, 1f64));
+                        }
+                    });
+                }
+            }
+            zone_stats.push(calculate_statistics(&mut values, &percentiles));
+
+            if verbose {
+                progress = (100.0_f64 * (record_num + 1) as f64 / num_zones as f64) as usize;
+                if progress != old_progress {
+                    println!("Calculating statistics: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // the names of the statistics, which must be unique among the output fields, including
+        // those copied from the zones, i.e. COUNT, MIN, MAX, MEAN, STDEV, MEDIAN, MAJORITY and the percentiles
+        let mut stat_names: Vec<String> = vec![];
+        let base_names = ["COUNT", "MIN", "MAX", "MEAN", "STDEV", "MEDIAN", "MAJORITY"].iter().map(|s| s.to_string())
+            .chain(percentiles.iter().map(|p| format!("P{}", p).replace(".", "_")));
+        for base in base_names {
+            let name = zones.attributes.get_unique_field_name(&base, &stat_names);
+            stat_names.push(name);
+        }
+
+        if !output_file.is_empty() {
+            let mut output = Shapefile::initialize_using_file(&output_file, &zones, zones.header.shape_type, true)?;
+            if weighted {
+                output.attributes.add_field(&AttributeField::new(&stat_names[0], FieldDataType::Real, 19, 3));
+            } else {
+                output.attributes.add_field(&AttributeField::new(&stat_names[0], FieldDataType::Int, 10, 0));
+            }
+            for name in &stat_names[1..] {
+                output.attributes.add_field(&AttributeField::new(name, FieldDataType::Real, 19, 6));
+            }
+            for record_num in 0..num_zones {
+                output.add_record(zones.get_record(record_num).clone());
+                let mut rec = zones.attributes.get_record(record_num).clone();
+                match zone_stats[record_num] {
+                    Some(ref s) => {
+                        rec.push(if weighted { FieldData::Real(s.count) } else { FieldData::Int(s.count as i32) });
+                        for v in [s.min, s.max, s.mean, s.stdev, s.median, s.majority].iter() {
+                            rec.push(FieldData::Real(*v));
+                        }
+                        for v in &s.percentiles {
+                            rec.push(FieldData::Real(*v));
+                        }
+                    },
+                    None => {
+                        rec.push(if weighted { FieldData::Real(0f64) } else { FieldData::Int(0) });
+                        for _ in 0..6 + percentiles.len() {
+                            rec.push(FieldData::Null);
+                        }
+                    },
+                }
+                output.attributes.add_record(rec);
+            }
+
+            if verbose { println!("Saving data...") };
+            let _ = match output.write() {
+                Ok(_) => if verbose { println!("Output file written") },
+                Err(e) => return Err(e),
+            };
+        }
+
+        if !table_file.is_empty() {
+            let f = File::create(&table_file)?;
+            let mut writer = BufWriter::new(f);
+            let id_name = match id_field {
+                Some(f) => zones.attributes.fields[f].name.clone(),
+                None => "FID".to_string(),
+            };
+            let header = format!("{},{}", csv_value(&id_name), stat_names.join(","));
+            writer.write_all(format!("{}\n", header).as_bytes())?;
+            for record_num in 0..num_zones {
+                let id = match id_field {
+                    Some(f) => match *zones.attributes.get_value(record_num, f) {
+                        FieldData::Null => String::new(),
+                        ref v => csv_value(&format!("{}", v)),
+                    },
+                    None => format!("{}", record_num + 1),
+                };
+                let s = match zone_stats[record_num] {
+                    Some(ref s) => {
+                        let mut line = format!("{},{},{},{},{},{},{},{}", id, s.count, s.min, s.max, s.mean, s.stdev, s.median, s.majority);
+                        for v in &s.percentiles {
+                            line.push_str(&format!(",{}", v));
+                        }
+                        line
+                    },
+                    None => format!("{},0{}", id, ",".repeat(6 + percentiles.len())),
+                };
+                writer.write_all(format!("{}\n", s).as_bytes())?;
+            }
+            writer.flush()?;
+            if verbose { println!("Table written") };
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose {
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+struct ZoneStats {
+    count: f64,
+    min: f64,
+    max: f64,
+    mean: f64,
+    stdev: f64,
+    median: f64,
+    majority: f64,
+    percentiles: Vec<f64>,
+}
+
+/// Calculates the weighted statistics of a zone's (value, weight) pairs, which are sorted
+/// by value in the process. Returns `None` if the zone has no values.
+fn calculate_statistics(values: &mut Vec<(f64, f64)>, percentiles: &[f64]) -> Option<ZoneStats> {
+    let total_weight: f64 = values.iter().map(|v| v.1).sum();
+    if values.is_empty() || total_weight <= 0f64 {
+        return None;
+    }
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mean = values.iter().map(|v| v.0 * v.1).sum::<f64>() / total_weight;
+    let variance = values.iter().map(|v| v.1 * (v.0 - mean) * (v.0 - mean)).sum::<f64>() / total_weight;
+
+    // the value with the greatest total weight; ties go to the smaller value
+    let mut majority = values[0].0;
+    let mut majority_weight = 0f64;
+    let mut i = 0;
+    while i < values.len() {
+        let mut w = 0f64;
+        let mut j = i;
+        while j < values.len() && values[j].0 == values[i].0 {
+            w += values[j].1;
+            j += 1;
+        }
+        if w > majority_weight {
+            majority_weight = w;
+            majority = values[i].0;
+        }
+        i = j;
+    }
+
+    let percentile = |p: f64| -> f64 {
+        let target = p / 100f64 * total_weight - 1e-9 * total_weight;
+        let mut cumulative = 0f64;
+        for v in values.iter() {
+            cumulative += v.1;
+            if cumulative >= target {
+                return v.0;
+            }
+        }
+        values[values.len() - 1].0
+    };
+
+    Some(ZoneStats {
+        count: total_weight,
+        min: values[0].0,
+        max: values[values.len() - 1].0,
+        mean: mean,
+        stdev: variance.sqrt(),
+        median: percentile(50f64),
+        majority: majority,
+        percentiles: percentiles.iter().map(|p| percentile(*p)).collect(),
+    })
+}
+
+/// Clips a ring to a half-plane using the Sutherland-Hodgman algorithm, retaining the
+/// orientation of the ring.
+fn clip_ring<F, G>(ring: &[Point2D], inside: F, intersect: G) -> Vec<Point2D>
+    where F: Fn(&Point2D) -> bool, G: Fn(&Point2D, &Point2D) -> Point2D {
+    let mut clipped = Vec::with_capacity(ring.len() + 2);
+    let n = ring.len();
+    for i in 0..n {
+        let (p, q) = (ring[i], ring[(i + 1) % n]);
+        match (inside(&p), inside(&q)) {
+            (true, true) => clipped.push(q),
+            (true, false) => clipped.push(intersect(&p, &q)),
+            (false, true) => {
+                clipped.push(intersect(&p, &q));
+                clipped.push(q);
+            },
+            (false, false) => {},
+        }
+    }
+    clipped
+}
+
+fn intersect_x(p: &Point2D, q: &Point2D, x: f64) -> Point2D {
+    Point2D::new(x, p.y + (x - p.x) / (q.x - p.x) * (q.y - p.y))
+}
+
+fn intersect_y(p: &Point2D, q: &Point2D, y: f64) -> Point2D {
+    Point2D::new(p.x + (y - p.y) / (q.y - p.y) * (q.x - p.x), y)
+}
@@ -28,7 +28,9 @@ impl ToolManager {
         tool_names.push("ConvertNodataToZero".to_string());
         tool_names.push("ConvertRasterFormat".to_string());
         tool_names.push("NewRasterFromBase".to_string());
+        tool_names.push("RasterToVectorPolygons".to_string());
         tool_names.push("SetNodataValue".to_string());
+        tool_names.push("VectorToRaster".to_string());
 
         // gis_analysis
         tool_names.push("AggregateRaster".to_string());
@@ -43,6 +45,7 @@ impl ToolManager {
         tool_names.push("EdgeProportion".to_string());
         tool_names.push("EuclideanAllocation".to_string());
         tool_names.push("EuclideanDistance".to_string());
+        tool_names.push("ExtractRasterValuesAtPoints".to_string());
         tool_names.push("FindPatchOrClassEdgeCells".to_string());
         tool_names.push("HighestPosition".to_string());
         tool_names.push("LowestPosition".to_string());
@@ -157,14 +160,23 @@ impl ToolManager {
         tool_names.push("WriteFunctionMemoryInsertion".to_string());
 
         // lidar_analysis
+        tool_names.push("AsciiToLas".to_string());
         tool_names.push("BlockMaximum".to_string());
         tool_names.push("BlockMinimum".to_string());
         tool_names.push("FilterLidarScanAngles".to_string());
         tool_names.push("FindFlightlineEdgePoints".to_string());
         tool_names.push("FlightlineOverlap".to_string());
+        tool_names.push("IndividualTreeDetection".to_string());
         tool_names.push("LasToAscii".to_string());
+        tool_names.push("LasToPly".to_string());
+        tool_names.push("LidarBlockStatistics".to_string());
+        tool_names.push("LidarBuildingVegetationClassification".to_string());
+        tool_names.push("LidarClip".to_string());
+        tool_names.push("LidarChangeDetection".to_string());
         tool_names.push("LidarElevationSlice".to_string());
+        tool_names.push("LidarForestMetrics".to_string());
         tool_names.push("LidarGroundPointFilter".to_string());
+        tool_names.push("LidarHeightNormalization".to_string());
         tool_names.push("LidarHillshade".to_string());
         tool_names.push("LidarHistogram".to_string());
         tool_names.push("LidarIdwInterpolation".to_string());
@@ -172,12 +184,19 @@ impl ToolManager {
         tool_names.push("LidarJoin".to_string());
         tool_names.push("LidarKappaIndex".to_string());
         tool_names.push("LidarNearestNeighbourGridding".to_string());
+        tool_names.push("LidarNoiseFilter".to_string());
         tool_names.push("LidarPointDensity".to_string());
         tool_names.push("LidarSegmentation".to_string());
         tool_names.push("LidarSegmentationBasedFilter".to_string());
+        tool_names.push("LidarThin".to_string());
         tool_names.push("LidarTile".to_string());
+        tool_names.push("LidarTinGridding".to_string());
         tool_names.push("LidarTophatTransform".to_string());
         tool_names.push("NormalVectors".to_string());
+        tool_names.push("NormalizeLidarIntensity".to_string());
+        tool_names.push("PitFreeChm".to_string());
+        tool_names.push("PlyToLas".to_string());
+        tool_names.push("RecoverFlightlineInfo".to_string());
 
         // mathematical and statistical_analysis
         tool_names.push("AbsoluteValue".to_string());
@@ -245,6 +264,7 @@ impl ToolManager {
         tool_names.push("TurningBandsSimulation".to_string());
         tool_names.push("Xor".to_string());
         tool_names.push("ZScores".to_string());
+        tool_names.push("ZonalStatistics".to_string());
 
         // stream_network_analysis
         tool_names.push("DistanceToOutlet".to_string());
@@ -255,6 +275,7 @@ impl ToolManager {
         tool_names.push("HackStreamOrder".to_string());
         tool_names.push("HortonStreamOrder".to_string());
         tool_names.push("LengthOfUpstreamChannels".to_string());
+        tool_names.push("RasterStreamsToVector".to_string());
         tool_names.push("RemoveShortStreams".to_string());
         tool_names.push("ShreveStreamMagnitude".to_string());
         tool_names.push("StrahlerStreamOrder".to_string());
@@ -268,6 +289,7 @@ impl ToolManager {
 
         // terrain_analysis
         tool_names.push("Aspect".to_string());
+        tool_names.push("ContoursFromRaster".to_string());
         tool_names.push("FeaturePreservingDenoise".to_string());
         tool_names.push("DevFromMeanElev".to_string());
         tool_names.push("DiffFromMeanElev".to_string());
@@ -320,7 +342,9 @@ impl ToolManager {
             "convertnodatatozero" => Some(Box::new(tools::data_tools::ConvertNodataToZero::new())),
             "convertrasterformat" => Some(Box::new(tools::data_tools::ConvertRasterFormat::new())),
             "newrasterfrombase" => Some(Box::new(tools::data_tools::NewRasterFromBase::new())),
+            "rastertovectorpolygons" => Some(Box::new(tools::data_tools::RasterToVectorPolygons::new())),
             "setnodatavalue" => Some(Box::new(tools::data_tools::SetNodataValue::new())),
+            "vectortoraster" => Some(Box::new(tools::data_tools::VectorToRaster::new())),
 
             // gis_analysis
             "aggregateraster" => Some(Box::new(tools::gis_analysis::AggregateRaster::new())),
@@ -337,6 +361,7 @@ impl ToolManager {
                 Some(Box::new(tools::gis_analysis::EuclideanAllocation::new()))
             }
             "euclideandistance" => Some(Box::new(tools::gis_analysis::EuclideanDistance::new())),
+            "extractrastervaluesatpoints" => Some(Box::new(tools::gis_analysis::ExtractRasterValuesAtPoints::new())),
             "findpatchorclassedgecells" => Some(Box::new(tools::gis_analysis::FindPatchOrClassEdgeCells::new())),
             "highestposition" => Some(Box::new(tools::gis_analysis::HighestPosition::new())),
             "lowestposition" => Some(Box::new(tools::gis_analysis::LowestPosition::new())),
@@ -491,18 +516,29 @@ impl ToolManager {
             "writefunctionmemoryinsertion" => Some(Box::new(tools::image_analysis::WriteFunctionMemoryInsertion::new())),
 
             // lidar_analysis
+            "asciitolas" => Some(Box::new(tools::lidar_analysis::AsciiToLas::new())),
             "blockmaximum" => Some(Box::new(tools::lidar_analysis::BlockMaximum::new())),
             "blockminimum" => Some(Box::new(tools::lidar_analysis::BlockMinimum::new())),
             "filterlidarscanangles" => Some(Box::new(tools::lidar_analysis::FilterLidarScanAngles::new())),
             "findflightlineedgepoints" => Some(Box::new(tools::lidar_analysis::FindFlightlineEdgePoints::new())),
             "flightlineoverlap" => Some(Box::new(tools::lidar_analysis::FlightlineOverlap::new())),
+            "individualtreedetection" => Some(Box::new(tools::lidar_analysis::IndividualTreeDetection::new())),
             "lastoascii" => Some(Box::new(tools::lidar_analysis::LasToAscii::new())),
+            "lastoply" => Some(Box::new(tools::lidar_analysis::LasToPly::new())),
+            "lidarblockstatistics" => Some(Box::new(tools::lidar_analysis::LidarBlockStatistics::new())),
+            "lidarbuildingvegetationclassification" => Some(Box::new(tools::lidar_analysis::LidarBuildingVegetationClassification::new())),
+            "lidarclip" => Some(Box::new(tools::lidar_analysis::LidarClip::new())),
+            "lidarchangedetection" => Some(Box::new(tools::lidar_analysis::LidarChangeDetection::new())),
             "lidarelevationslice" => {
                 Some(Box::new(tools::lidar_analysis::LidarElevationSlice::new()))
             }
+            "lidarforestmetrics" => Some(Box::new(tools::lidar_analysis::LidarForestMetrics::new())),
             "lidargroundpointfilter" => {
                 Some(Box::new(tools::lidar_analysis::LidarGroundPointFilter::new()))
             }
+            "lidarheightnormalization" => {
+                Some(Box::new(tools::lidar_analysis::LidarHeightNormalization::new()))
+            }
             "lidarhillshade" => Some(Box::new(tools::lidar_analysis::LidarHillshade::new())),
             "lidarhistogram" => Some(Box::new(tools::lidar_analysis::LidarHistogram::new())),
             "lidaridwinterpolation" => {
@@ -514,14 +550,21 @@ impl ToolManager {
             "lidarnearestneighbourgridding" => {
                 Some(Box::new(tools::lidar_analysis::LidarNearestNeighbourGridding::new()))
             }
+            "lidarnoisefilter" => Some(Box::new(tools::lidar_analysis::LidarNoiseFilter::new())),
             "lidarpointdensity" => Some(Box::new(tools::lidar_analysis::LidarPointDensity::new())),
             "lidarsegmentation" => Some(Box::new(tools::lidar_analysis::LidarSegmentation::new())),
             "lidarsegmentationbasedfilter" => Some(Box::new(tools::lidar_analysis::LidarSegmentationBasedFilter::new())),
+            "lidarthin" => Some(Box::new(tools::lidar_analysis::LidarThin::new())),
             "lidartile" => Some(Box::new(tools::lidar_analysis::LidarTile::new())),
+            "lidartingridding" => Some(Box::new(tools::lidar_analysis::LidarTinGridding::new())),
             "lidartophattransform" => {
                 Some(Box::new(tools::lidar_analysis::LidarTophatTransform::new()))
             }
             "normalvectors" => Some(Box::new(tools::lidar_analysis::NormalVectors::new())),
+            "normalizelidarintensity" => Some(Box::new(tools::lidar_analysis::NormalizeLidarIntensity::new())),
+            "pitfreechm" => Some(Box::new(tools::lidar_analysis::PitFreeChm::new())),
+            "plytolas" => Some(Box::new(tools::lidar_analysis::PlyToLas::new())),
+            "recoverflightlineinfo" => Some(Box::new(tools::lidar_analysis::RecoverFlightlineInfo::new())),
 
             // mathematical and statistical_analysis
             "absolutevalue" => Some(Box::new(tools::math_stat_analysis::AbsoluteValue::new())),
@@ -589,6 +632,7 @@ impl ToolManager {
             "turningbandssimulation" => Some(Box::new(tools::math_stat_analysis::TurningBandsSimulation::new())),
             "xor" => Some(Box::new(tools::math_stat_analysis::Xor::new())),
             "zscores" => Some(Box::new(tools::math_stat_analysis::ZScores::new())),
+            "zonalstatistics" => Some(Box::new(tools::math_stat_analysis::ZonalStatistics::new())),
 
             // stream_network_analysis
             "distancetooutlet" => {
@@ -611,6 +655,7 @@ impl ToolManager {
             "lengthofupstreamchannels" => {
                 Some(Box::new(tools::stream_network_analysis::LengthOfUpstreamChannels::new()))
             }
+            "rasterstreamstovector" => Some(Box::new(tools::stream_network_analysis::RasterStreamsToVector::new())),
             "removeshortstreams" => {
                 Some(Box::new(tools::stream_network_analysis::RemoveShortStreams::new()))
             }
@@ -644,6 +689,7 @@ impl ToolManager {
 
             // terrain_analysis
             "aspect" => Some(Box::new(tools::terrain_analysis::Aspect::new())),
+            "contoursfromraster" => Some(Box::new(tools::terrain_analysis::ContoursFromRaster::new())),
             "featurepreservingdenoise" => Some(Box::new(tools::terrain_analysis::FeaturePreservingDenoise::new())),
             "devfrommeanelev" => Some(Box::new(tools::terrain_analysis::DevFromMeanElev::new())),
             "difffrommeanelev" => Some(Box::new(tools::terrain_analysis::DiffFromMeanElev::new())),
@@ -0,0 +1,491 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 10, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::collections::VecDeque;
+use std::path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use structures::Point2D;
+use tools::*;
+use vector::*;
+
+/// Converts a raster stream network into vector polylines, with one polyline for each stream
+/// link. A link is the section of stream between a channel head or confluence and the next
+/// confluence or outlet downstream. Links are traced by following the D8 flow pointer along
+/// the stream cells, and each polyline connects the centres of the link's cells, ordered from
+/// upstream to downstream, ending at the centre of the confluence cell where the link joins
+/// its downstream link. The polylines are written in topological order, such that each link
+/// follows the links that drain into it.
+///
+/// The output attributes are the LINK_ID, the Strahler order (STRAHLER) and Shreve magnitude
+/// (SHREVE) of the link, its LENGTH in map units, the LINK_ID of the downstream link (DS_LINK_ID,
+/// which is zero for links that end at an outlet) and, where a DEM is specified, the average
+/// SLOPE of the link as a percent.
+pub struct RasterStreamsToVector {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl RasterStreamsToVector {
+    pub fn new() -> RasterStreamsToVector {
+        // public constructor
+        let name = "RasterStreamsToVector".to_string();
+        let toolbox = "Stream Network Analysis".to_string();
+        let description = "Converts a raster stream network into vector polylines, one per stream link, with topology attributes.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input Streams File".to_owned(),
+            flags: vec!["--streams".to_owned()],
+            description: "Input raster streams file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input D8 Pointer File".to_owned(),
+            flags: vec!["--d8_pntr".to_owned()],
+            description: "Input raster D8 pointer file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Input DEM File".to_owned(),
+            flags: vec!["--dem".to_owned()],
+            description: "Optional input raster DEM file, used to calculate link slopes.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output polyline vector file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Does the pointer file use the ESRI pointer scheme?".to_owned(),
+            flags: vec!["--esri_pntr".to_owned()],
+            description: "D8 pointer uses the ESRI style scheme.".to_owned(),
+            parameter_type: ParameterType::Boolean,
+            default_value: Some("false".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" --streams=streams.tif --d8_pntr=D8.tif --dem=dem.tif -o=streams.shp
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" --streams=streams.flt --d8_pntr=D8.flt -o=streams.geojson --esri_pntr", short_exe, name).replace("*", &sep);
+
+        RasterStreamsToVector {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for RasterStreamsToVector {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut streams_file = String::new();
+        let mut d8_file = String::new();
+        let mut dem_file = String::new();
+        let mut output_file = String::new();
+        let mut esri_style = false;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-streams" {
+                streams_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-d8_pntr" {
+                d8_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-dem" {
+                dem_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-esri_pntr" || flag_val == "-esri_style" {
+                esri_style = true;
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !streams_file.contains(sep) {
+            streams_file = format!("{}{}", working_directory, streams_file);
+        }
+        if !d8_file.contains(sep) {
+            d8_file = format!("{}{}", working_directory, d8_file);
+        }
+        if !dem_file.is_empty() && !dem_file.contains(sep) {
+            dem_file = format!("{}{}", working_directory, dem_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading streams data...") };
+        let streams = Raster::new(&streams_file, "r")?;
+        if verbose { println!("Reading pointer data...") };
+        let pntr = Raster::new(&d8_file, "r")?;
+        let dem = if !dem_file.is_empty() {
+            if verbose { println!("Reading DEM data...") };
+            Some(Raster::new(&dem_file, "r")?)
+        } else {
+            None
+        };
+
+        let start = time::now();
+
+        let rows = streams.configs.rows as isize;
+        let columns = streams.configs.columns as isize;
+        let streams_nodata = streams.configs.nodata;
+        let pntr_nodata = pntr.configs.nodata;
+
+        // make sure the input files have the same size
+        if streams.configs.rows != pntr.configs.rows || streams.configs.columns != pntr.configs.columns {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                "The input files must have the same number of rows and columns and spatial extent."));
+        }
+        if let Some(ref dem) = dem {
+            if dem.configs.rows != streams.configs.rows || dem.configs.columns != streams.configs.columns {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                    "The input files must have the same number of rows and columns and spatial extent."));
+            }
+        }
+
+        let d_x = [ 1, 1, 1, 0, -1, -1, -1, 0 ];
+        let d_y = [ -1, 0, 1, 1, 1, 0, -1, -1 ];
+        let cell_size_x = streams.configs.resolution_x;
+        let cell_size_y = streams.configs.resolution_y;
+        let diag_cell_size = (cell_size_x * cell_size_x + cell_size_y * cell_size_y).sqrt();
+        let grid_lengths = [diag_cell_size, cell_size_x, diag_cell_size, cell_size_y, diag_cell_size, cell_size_x, diag_cell_size, cell_size_y];
+
+        // Create a mapping from the pointer values to cells offsets.
+        let mut pntr_matches: [usize; 129] = [999usize; 129];
+        if !esri_style {
+            // This maps Whitebox-style D8 pointer values
+            // onto the cell offsets in d_x and d_y.
+            pntr_matches[1] = 0usize;
+            pntr_matches[2] = 1usize;
+            pntr_matches[4] = 2usize;
+            pntr_matches[8] = 3usize;
+            pntr_matches[16] = 4usize;
+            pntr_matches[32] = 5usize;
+            pntr_matches[64] = 6usize;
+            pntr_matches[128] = 7usize;
+        } else {
+            // This maps Esri-style D8 pointer values
+            // onto the cell offsets in d_x and d_y.
+            pntr_matches[1] = 1usize;
+            pntr_matches[2] = 2usize;
+            pntr_matches[4] = 3usize;
+            pntr_matches[8] = 4usize;
+            pntr_matches[16] = 5usize;
+            pntr_matches[32] = 6usize;
+            pntr_matches[64] = 7usize;
+            pntr_matches[128] = 0usize;
+        }
+
+        let is_stream = |row: isize, col: isize| -> bool {
+            let z = streams.get_value(row, col);
+            z > 0f64 && z != streams_nodata
+        };
+
+        // the flow direction (index into d_x and d_y) of each stream cell; None at outlets
+        let mut flow_dir: Vec<Option<usize>> = vec![None; (rows * columns) as usize];
+        let mut num_inflowing = vec![0u8; (rows * columns) as usize];
+        for row in 0..rows {
+            for col in 0..columns {
+                if !is_stream(row, col) {
+                    continue;
+                }
+                let z = pntr.get_value(row, col);
+                if z > 0f64 && z != pntr_nodata {
+                    let dir = z as usize;
+                    if dir > 128 || pntr_matches[dir] == 999 {
+                        return Err(Error::new(ErrorKind::InvalidInput,
+                            "An unexpected value has been identified in the pointer image. This tool requires a pointer grid that has been created using either the D8 or Rho8 tools."));
+                    }
+                    let (row_n, col_n) = (row + d_y[pntr_matches[dir]], col + d_x[pntr_matches[dir]]);
+                    if is_stream(row_n, col_n) {
+                        flow_dir[(row * columns + col) as usize] = Some(pntr_matches[dir]);
+                        num_inflowing[(row_n * columns + col_n) as usize] += 1;
+                    }
+                }
+            }
+        }
+
+        // Trace the links downstream from each channel head and confluence. A link
+        // continues until it reaches an outlet or a confluence cell, which starts
+        // another link.
+        let mut link_of_start_cell = vec![0usize; (rows * columns) as usize]; // link index + 1
+        let mut links: Vec<Link> = vec![];
+        for row in 0..rows {
+            for col in 0..columns {
+                let idx = (row * columns + col) as usize;
+                if is_stream(row, col) && num_inflowing[idx] != 1 {
+                    link_of_start_cell[idx] = links.len() + 1;
+                    let mut link = Link { cells: vec![(row, col)], length: 0f64, ..Default::default() };
+                    let (mut r, mut c) = (row, col);
+                    while let Some(dir) = flow_dir[(r * columns + c) as usize] {
+                        r += d_y[dir];
+                        c += d_x[dir];
+                        link.cells.push((r, c));
+                        link.length += grid_lengths[dir];
+                        if num_inflowing[(r * columns + c) as usize] > 1 {
+                            // the cell is a confluence
+                            link.ends_at_confluence = true;
+                            break;
+                        }
+                        if link.cells.len() > (rows * columns) as usize {
+                            return Err(Error::new(ErrorKind::InvalidInput, "The D8 pointer contains a loop along the stream network."));
+                        }
+                    }
+                    if link.cells.len() == 1 {
+                        // a confluence that is also an outlet; extend the link half a cell
+                        // towards its flow direction so that it forms a line
+                        let z = pntr.get_value(row, col);
+                        if z > 0f64 && z != pntr_nodata && pntr_matches[z as usize] != 999 {
+                            let dir = pntr_matches[z as usize];
+                            link.outlet_dir = Some(dir);
+                            link.length = grid_lengths[dir] / 2f64;
+                        }
+                    }
+                    links.push(link);
+                }
+            }
+        }
+
+        // the downstream link of each link and the number of upstream links
+        let num_links = links.len();
+        let mut num_upstream = vec![0usize; num_links];
+        for i in 0..num_links {
+            if links[i].ends_at_confluence {
+                let &(r, c) = links[i].cells.last().unwrap();
+                let ds = link_of_start_cell[(r * columns + c) as usize] - 1;
+                links[i].downstream = Some(ds);
+                num_upstream[ds] += 1;
+            }
+        }
+
+        // Strahler order and Shreve magnitude, in topological order from the channel heads
+        let mut order: Vec<usize> = Vec::with_capacity(num_links);
+        let mut queue: VecDeque<usize> = (0..num_links).filter(|i| num_upstream[*i] == 0).collect();
+        let mut num_remaining = num_upstream.clone();
+        let mut max_upstream_order = vec![0usize; num_links]; // highest order of the upstream links
+        let mut num_max_order = vec![0usize; num_links]; // number of upstream links with that order
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            if num_upstream[i] == 0 {
+                links[i].strahler = 1;
+                links[i].shreve = 1;
+            } else {
+                links[i].strahler = if num_max_order[i] > 1 { max_upstream_order[i] + 1 } else { max_upstream_order[i] };
+            }
+            if let Some(ds) = links[i].downstream {
+                let s = links[i].strahler;
+                if s > max_upstream_order[ds] {
+                    max_upstream_order[ds] = s;
+                    num_max_order[ds] = 1;
+                } else if s == max_upstream_order[ds] {
+                    num_max_order[ds] += 1;
+                }
+                links[ds].shreve += links[i].shreve;
+                num_remaining[ds] -= 1;
+                if num_remaining[ds] == 0 {
+                    queue.push_back(ds);
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * order.len() as f64 / num_links as f64) as usize;
+                if progress != old_progress {
+                    println!("Ordering links: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // link IDs follow the topological order
+        let mut link_id = vec![0usize; num_links];
+        for (k, &i) in order.iter().enumerate() {
+            link_id[i] = k + 1;
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::PolyLine;
+        output.projection = streams.configs.coordinate_ref_system_wkt.clone();
+        output.attributes.add_field(&AttributeField::new("LINK_ID", FieldDataType::Int, 10, 0));
+        output.attributes.add_field(&AttributeField::new("STRAHLER", FieldDataType::Int, 4, 0));
+        output.attributes.add_field(&AttributeField::new("SHREVE", FieldDataType::Int, 10, 0));
+        output.attributes.add_field(&AttributeField::new("LENGTH", FieldDataType::Real, 19, 3));
+        output.attributes.add_field(&AttributeField::new("DS_LINK_ID", FieldDataType::Int, 10, 0));
+        if dem.is_some() {
+            output.attributes.add_field(&AttributeField::new("SLOPE", FieldDataType::Real, 19, 6));
+        }
+
+        for &i in &order {
+            let link = &links[i];
+            let mut points: Vec<Point2D> = link.cells.iter().map(|&(r, c)| {
+                Point2D::new(streams.get_x_from_column_centre(c), streams.get_y_from_row_centre(r))
+            }).collect();
+            if points.len() == 1 {
+                let p = points[0];
+                points.push(match link.outlet_dir {
+                    Some(dir) => Point2D::new(p.x + d_x[dir] as f64 * cell_size_x / 2f64, p.y - d_y[dir] as f64 * cell_size_y / 2f64),
+                    None => p,
+                });
+            }
+            let mut sfg = ShapefileGeometry::new(ShapeType::PolyLine);
+            sfg.add_part(&points);
+            output.add_record(sfg);
+
+            let ds_link_id = match link.downstream {
+                Some(ds) => link_id[ds] as i32,
+                None => 0i32,
+            };
+            let mut rec = vec![
+                FieldData::Int(link_id[i] as i32),
+                FieldData::Int(link.strahler as i32),
+                FieldData::Int(link.shreve as i32),
+                FieldData::Real(link.length),
+                FieldData::Int(ds_link_id),
+            ];
+            if let Some(ref dem) = dem {
+                let (r1, c1) = link.cells[0];
+                let (r2, c2) = *link.cells.last().unwrap();
+                let (z1, z2) = (dem.get_value(r1, c1), dem.get_value(r2, c2));
+                if link.cells.len() > 1 && z1 != dem.configs.nodata && z2 != dem.configs.nodata {
+                    rec.push(FieldData::Real((z1 - z2) / link.length * 100f64));
+                } else {
+                    rec.push(FieldData::Null);
+                }
+            }
+            output.attributes.add_record(rec);
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { println!("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{} stream links were created.", num_links);
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Link {
+    cells: Vec<(isize, isize)>,
+    length: f64,
+    ends_at_confluence: bool,
+    outlet_dir: Option<usize>,
+    downstream: Option<usize>,
+    strahler: usize,
+    shreve: usize,
+}
@@ -0,0 +1,515 @@
+/*
+This tool is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 12, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use std::env;
+use std::f64;
+use std::collections::HashMap;
+use std::path;
+use std::io::{Error, ErrorKind};
+use raster::*;
+use structures::Point2D;
+use tools::*;
+use vector::*;
+
+/// Creates contour lines from a raster digital elevation model (DEM), using the marching
+/// squares algorithm. The squares are formed by the centres of each 2 x 2 block of grid cells
+/// and the contour crossings are linearly interpolated along the square edges. Saddle squares
+/// are resolved using the average of the four corner elevations. The contour levels are either
+/// every `--interval` units from `--base` or an explicit comma-separated list of `--levels`.
+///
+/// Squares with a nodata corner are not contoured, such that contour lines end at the edges
+/// of nodata areas (as well as at the grid edges). The lines may optionally be smoothed, using
+/// a number of iterations of Chaikin's corner-cutting algorithm (`--smooth`), and lines shorter
+/// than a minimum length (`--min_length`, in map units) are removed. The output attributes are
+/// FID and ELEV, the contour level.
+pub struct ContoursFromRaster {
+    name: String,
+    description: String,
+    toolbox: String,
+    parameters: Vec<ToolParameter>,
+    example_usage: String,
+}
+
+impl ContoursFromRaster {
+    pub fn new() -> ContoursFromRaster {
+        // public constructor
+        let name = "ContoursFromRaster".to_string();
+        let toolbox = "Geomorphometric Analysis".to_string();
+        let description = "Creates contour lines from a raster DEM.".to_string();
+
+        let mut parameters = vec![];
+        parameters.push(ToolParameter{
+            name: "Input DEM File".to_owned(),
+            flags: vec!["-i".to_owned(), "--dem".to_owned()],
+            description: "Input raster DEM file.".to_owned(),
+            parameter_type: ParameterType::ExistingFile(ParameterFileType::Raster),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Output File".to_owned(),
+            flags: vec!["-o".to_owned(), "--output".to_owned()],
+            description: "Output polyline vector file (Shapefile or GeoJSON).".to_owned(),
+            parameter_type: ParameterType::NewFile(ParameterFileType::Vector),
+            default_value: None,
+            optional: false
+        });
+
+        parameters.push(ToolParameter{
+            name: "Contour Interval".to_owned(),
+            flags: vec!["--interval".to_owned()],
+            description: "Contour interval.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("10.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Base Contour".to_owned(),
+            flags: vec!["--base".to_owned()],
+            description: "Base contour level, from which the contour interval is measured.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Contour Levels".to_owned(),
+            flags: vec!["--levels".to_owned()],
+            description: "Optional comma-separated list of contour levels, used instead of the interval and base.".to_owned(),
+            parameter_type: ParameterType::String,
+            default_value: None,
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Smoothing Iterations".to_owned(),
+            flags: vec!["--smooth".to_owned()],
+            description: "Optional number of iterations of line smoothing; zero for no smoothing.".to_owned(),
+            parameter_type: ParameterType::Integer,
+            default_value: Some("0".to_owned()),
+            optional: true
+        });
+
+        parameters.push(ToolParameter{
+            name: "Minimum Line Length".to_owned(),
+            flags: vec!["--min_length".to_owned()],
+            description: "Optional minimum contour line length, in map units.".to_owned(),
+            parameter_type: ParameterType::Float,
+            default_value: Some("0.0".to_owned()),
+            optional: true
+        });
+
+        let sep: String = path::MAIN_SEPARATOR.to_string();
+        let p = format!("{}", env::current_dir().unwrap().display());
+        let e = format!("{}", env::current_exe().unwrap().display());
+        let mut short_exe = e.replace(&p, "")
+            .replace(".exe", "")
+            .replace(".", "")
+            .replace(&sep, "");
+        if e.contains(".exe") {
+            short_exe += ".exe";
+        }
+        let usage = format!(">>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=DEM.tif -o=contours.shp --interval=5.0 --base=0.0 --smooth=2 --min_length=50.0
+>>.*{0} -r={1} -v --wd=\"*path*to*data*\" -i=DEM.tif -o=contours.geojson --levels='100.0,150.0,200.0'", short_exe, name).replace("*", &sep);
+
+        ContoursFromRaster {
+            name: name,
+            description: description,
+            toolbox: toolbox,
+            parameters: parameters,
+            example_usage: usage
+        }
+    }
+}
+
+impl WhiteboxTool for ContoursFromRaster {
+    fn get_source_file(&self) -> String {
+        String::from(file!())
+    }
+
+    fn get_tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_tool_description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn get_tool_parameters(&self) -> String {
+        let mut s = String::from("{\"parameters\": [");
+        for i in 0..self.parameters.len() {
+            if i < self.parameters.len() - 1 {
+                s.push_str(&(self.parameters[i].to_string()));
+                s.push_str(",");
+            } else {
+                s.push_str(&(self.parameters[i].to_string()));
+            }
+        }
+        s.push_str("]}");
+        s
+    }
+
+    fn get_example_usage(&self) -> String {
+        self.example_usage.clone()
+    }
+
+    fn get_toolbox(&self) -> String {
+        self.toolbox.clone()
+    }
+
+    fn run<'a>(&self, args: Vec<String>, working_directory: &'a str, verbose: bool) -> Result<(), Error> {
+        let mut input_file = String::new();
+        let mut output_file = String::new();
+        let mut interval = 10f64;
+        let mut base = 0f64;
+        let mut levels_str = String::new();
+        let mut smooth_iterations = 0usize;
+        let mut min_length = 0f64;
+
+        // read the arguments
+        if args.len() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Tool run with no paramters."));
+        }
+        for i in 0..args.len() {
+            let mut arg = args[i].replace("\"", "");
+            arg = arg.replace("\'", "");
+            let cmd = arg.split("="); // in case an equals sign was used
+            let vec = cmd.collect::<Vec<&str>>();
+            let mut keyval = false;
+            if vec.len() > 1 {
+                keyval = true;
+            }
+            let flag_val = vec[0].to_lowercase().replace("--", "-");
+            if flag_val == "-i" || flag_val == "-input" || flag_val == "-dem" {
+                input_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-o" || flag_val == "-output" {
+                output_file = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-interval" {
+                interval = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-base" {
+                base = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            } else if flag_val == "-levels" {
+                levels_str = if keyval {
+                    vec[1].to_string()
+                } else {
+                    args[i + 1].to_string()
+                };
+            } else if flag_val == "-smooth" {
+                smooth_iterations = if keyval {
+                    vec[1].to_string().parse::<usize>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<usize>().unwrap()
+                };
+            } else if flag_val == "-min_length" {
+                min_length = if keyval {
+                    vec[1].to_string().parse::<f64>().unwrap()
+                } else {
+                    args[i + 1].to_string().parse::<f64>().unwrap()
+                };
+            }
+        }
+
+        if verbose {
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+            println!("* Welcome to {} *", self.get_tool_name());
+            println!("***************{}", "*".repeat(self.get_tool_name().len()));
+        }
+
+        let sep = path::MAIN_SEPARATOR;
+        if !input_file.contains(sep) {
+            input_file = format!("{}{}", working_directory, input_file);
+        }
+        if !output_file.contains(sep) {
+            output_file = format!("{}{}", working_directory, output_file);
+        }
+
+        if verbose { println!("Reading data...") };
+        let input = Raster::new(&input_file, "r")?;
+
+        let start = time::now();
+
+        let rows = input.configs.rows as isize;
+        let columns = input.configs.columns as isize;
+        let nodata = input.configs.nodata;
+
+        // the contour levels, in ascending order
+        let mut levels: Vec<f64> = vec![];
+        if !levels_str.trim().is_empty() {
+            for s in levels_str.split(",") {
+                if s.trim().is_empty() {
+                    continue;
+                }
+                match s.trim().parse::<f64>() {
+                    Ok(v) => levels.push(v),
+                    Err(_) => return Err(Error::new(ErrorKind::InvalidInput, format!("Could not parse the contour level '{}'.", s.trim()))),
+                }
+            }
+            levels.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            levels.dedup();
+        } else {
+            if interval <= 0f64 {
+                return Err(Error::new(ErrorKind::InvalidInput, "The contour interval must be greater than zero."));
+            }
+            let mut min_val = f64::INFINITY;
+            let mut max_val = f64::NEG_INFINITY;
+            for row in 0..rows {
+                for col in 0..columns {
+                    let z = input.get_value(row, col);
+                    if z != nodata {
+                        if z < min_val { min_val = z; }
+                        if z > max_val { max_val = z; }
+                    }
+                }
+            }
+            let mut k = ((min_val - base) / interval).ceil();
+            while base + k * interval <= max_val {
+                levels.push(base + k * interval);
+                k += 1f64;
+            }
+        }
+        if levels.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "There are no contour levels within the range of the DEM."));
+        }
+
+        // The crossing points on the edges of each square. Horizontal edges, between the
+        // centres of cells (row, col) and (row, col + 1), have even keys and vertical edges,
+        // between (row, col) and (row + 1, col), have odd keys.
+        let h_edge = |r: isize, c: isize| 2 * (r * columns + c) as usize;
+        let v_edge = |r: isize, c: isize| 2 * (r * columns + c) as usize + 1;
+        let mut segments: Vec<Vec<Segment>> = vec![vec![]; levels.len()];
+        let mut progress: usize;
+        let mut old_progress: usize = 1;
+        for row in 0..rows - 1 {
+            for col in 0..columns - 1 {
+                // corners are ordered top-left, top-right, bottom-right, bottom-left
+                let corners = [(row, col), (row, col + 1), (row + 1, col + 1), (row + 1, col)];
+                let z = [
+                    input.get_value(row, col),
+                    input.get_value(row, col + 1),
+                    input.get_value(row + 1, col + 1),
+                    input.get_value(row + 1, col),
+                ];
+                if z.iter().any(|v| *v == nodata) {
+                    continue;
+                }
+                let z_min = z.iter().cloned().fold(f64::INFINITY, f64::min);
+                let z_max = z.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                // the edges are ordered top, right, bottom and left, i.e. edge k joins corners k and (k + 1) % 4
+                let edge_keys = [h_edge(row, col), v_edge(row, col + 1), h_edge(row + 1, col), v_edge(row, col)];
+                let first_level = match levels.iter().position(|l| *l > z_min) {
+                    Some(i) => i,
+                    None => continue,
+                };
+                for l in first_level..levels.len() {
+                    let level = levels[l];
+                    if level > z_max {
+                        break;
+                    }
+                    let above = [z[0] >= level, z[1] >= level, z[2] >= level, z[3] >= level];
+                    let crossings: Vec<usize> = (0..4).filter(|k| above[*k] != above[(*k + 1) % 4]).collect();
+                    let crossing_point = |k: usize| {
+                        let (k2, (r1, c1), (r2, c2)) = ((k + 1) % 4, corners[k], corners[(k + 1) % 4]);
+                        let t = (level - z[k]) / (z[k2] - z[k]);
+                        let (x1, y1) = (input.get_x_from_column_centre(c1), input.get_y_from_row_centre(r1));
+                        let (x2, y2) = (input.get_x_from_column_centre(c2), input.get_y_from_row_centre(r2));
+                        Point2D::new(x1 + t * (x2 - x1), y1 + t * (y2 - y1))
+                    };
+                    let mut add_segment = |k1: usize, k2: usize| {
+                        segments[l].push(Segment {
+                            keys: [edge_keys[k1], edge_keys[k2]],
+                            points: [crossing_point(k1), crossing_point(k2)],
+                        });
+                    };
+                    if crossings.len() == 2 {
+                        add_segment(crossings[0], crossings[1]);
+                    } else if crossings.len() == 4 {
+                        // a saddle; the corners on the other side of the level from the
+                        // centre of the square are cut off from the others
+                        let centre_above = (z[0] + z[1] + z[2] + z[3]) / 4f64 >= level;
+                        for k in 0..4 {
+                            if above[k] != centre_above {
+                                // corner k lies between edges (k + 3) % 4 and k
+                                add_segment((k + 3) % 4, k);
+                            }
+                        }
+                    }
+                }
+            }
+            if verbose {
+                progress = (100.0_f64 * row as f64 / (rows - 2).max(1) as f64) as usize;
+                if progress != old_progress {
+                    println!("Contouring: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        // create the output file
+        let mut output = Shapefile::new(&output_file, "w")?;
+        output.header.shape_type = ShapeType::PolyLine;
+        output.projection = input.configs.coordinate_ref_system_wkt.clone();
+        output.attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 10, 0));
+        output.attributes.add_field(&AttributeField::new("ELEV", FieldDataType::Real, 19, 6));
+
+        let mut num_lines = 0usize;
+        for l in 0..levels.len() {
+            for line in join_segments(&segments[l]) {
+                let mut line = line;
+                for _ in 0..smooth_iterations {
+                    line = smooth_line(&line);
+                }
+                let length = line_length(&line);
+                if length == 0f64 || length < min_length {
+                    continue;
+                }
+                let mut sfg = ShapefileGeometry::new(ShapeType::PolyLine);
+                sfg.add_part(&line);
+                output.add_record(sfg);
+                num_lines += 1;
+                output.attributes.add_record(vec![FieldData::Int(num_lines as i32), FieldData::Real(levels[l])]);
+            }
+            if verbose {
+                progress = (100.0_f64 * (l + 1) as f64 / levels.len() as f64) as usize;
+                if progress != old_progress {
+                    println!("Creating lines: {}%", progress);
+                    old_progress = progress;
+                }
+            }
+        }
+
+        let end = time::now();
+        let elapsed_time = end - start;
+
+        if verbose { println!("Saving data...") };
+        let _ = match output.write() {
+            Ok(_) => if verbose { println!("Output file written") },
+            Err(e) => return Err(e),
+        };
+
+        if verbose {
+            println!("{} contour lines were created.", num_lines);
+            println!("{}", &format!("Elapsed Time (excluding I/O): {}", elapsed_time).replace("PT", ""));
+        }
+
+        Ok(())
+    }
+}
+
+/// A contour line segment crossing a square, joining the crossing points on two of its edges.
+#[derive(Clone)]
+struct Segment {
+    keys: [usize; 2],
+    points: [Point2D; 2],
+}
+
+/// Joins the segments of a contour level into lines, using the edge keys that neighbouring
+/// segments share. Open lines, which end at the grid edges or at nodata areas, are traced
+/// first, followed by the closed lines, whose first and last points are equal.
+fn join_segments(segments: &[Segment]) -> Vec<Vec<Point2D>> {
+    let mut segments_at_edge: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, s) in segments.iter().enumerate() {
+        for key in s.keys.iter() {
+            segments_at_edge.entry(*key).or_insert(vec![]).push(i);
+        }
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    for open_lines in [true, false].iter() {
+        for i in 0..segments.len() {
+            if used[i] {
+                continue;
+            }
+            // open lines start at a segment end that isn't shared with another segment
+            let start_end = if segments_at_edge[&segments[i].keys[0]].len() == 1 {
+                0
+            } else if segments_at_edge[&segments[i].keys[1]].len() == 1 {
+                1
+            } else if *open_lines {
+                continue;
+            } else {
+                0
+            };
+            used[i] = true;
+            let mut line = vec![segments[i].points[start_end], segments[i].points[1 - start_end]];
+            let start_key = segments[i].keys[start_end];
+            let mut key = segments[i].keys[1 - start_end];
+            while let Some(&j) = segments_at_edge[&key].iter().find(|j| !used[**j]) {
+                used[j] = true;
+                let next_end = if segments[j].keys[0] == key { 1 } else { 0 };
+                key = segments[j].keys[next_end];
+                if key == start_key {
+                    // a closed line
+                    let first = line[0];
+                    line.push(first);
+                } else {
+                    line.push(segments[j].points[next_end]);
+                }
+            }
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Smooths a line using one iteration of Chaikin's corner-cutting algorithm. The end points
+/// of open lines are retained and closed lines remain closed.
+fn smooth_line(line: &[Point2D]) -> Vec<Point2D> {
+    let n = line.len();
+    if n < 3 {
+        return line.to_vec();
+    }
+    let closed = line[0] == line[n - 1];
+    let mut smoothed = Vec::with_capacity(2 * n);
+    if !closed {
+        smoothed.push(line[0]);
+    }
+    for i in 0..n - 1 {
+        let (p, q) = (line[i], line[i + 1]);
+        if closed || i > 0 {
+            smoothed.push(Point2D::new(0.75 * p.x + 0.25 * q.x, 0.75 * p.y + 0.25 * q.y));
+        }
+        if closed || i < n - 2 {
+            smoothed.push(Point2D::new(0.25 * p.x + 0.75 * q.x, 0.25 * p.y + 0.75 * q.y));
+        }
+    }
+    if closed {
+        let first = smoothed[0];
+        smoothed.push(first);
+    } else {
+        smoothed.push(line[n - 1]);
+    }
+    smoothed
+}
+
+fn line_length(line: &[Point2D]) -> f64 {
+    let mut length = 0f64;
+    for i in 1..line.len() {
+        length += ((line[i].x - line[i - 1].x) * (line[i].x - line[i - 1].x) + (line[i].y - line[i - 1].y) * (line[i].y - line[i - 1].y)).sqrt();
+    }
+    length
+}
@@ -0,0 +1,625 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 2, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+extern crate time;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error, ErrorKind};
+use io_utils::{ByteOrderReader, Endianness};
+
+/// A field (column) in the attribute table (.dbf) of a Shapefile. The `field_type`
+/// is one of the dBase type codes: 'C' (character), 'N' (numeric), 'F' (float),
+/// 'D' (date, YYYYMMDD) or 'L' (logical).
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct AttributeField {
+    pub name: String,
+    pub field_type: char,
+    pub field_length: u8,
+    pub decimal_count: u8,
+}
+
+impl AttributeField {
+    /// Creates a new field. Note that names longer than the ten characters allowed by
+    /// the dBase format are truncated when written to a .dbf file.
+    pub fn new<'a>(name: &'a str, field_type: FieldDataType, field_length: u8, decimal_count: u8) -> AttributeField {
+        let name = name.to_string();
+        let field_type = match field_type {
+            FieldDataType::Int => 'N',
+            FieldDataType::Real => if decimal_count > 0 { 'N' } else { 'F' },
+            FieldDataType::Text => 'C',
+            FieldDataType::Date => 'D',
+            FieldDataType::Bool => 'L',
+        };
+        let field_length = match field_type {
+            'D' => 8,
+            'L' => 1,
+            _ => field_length,
+        };
+        AttributeField {
+            name: name,
+            field_type: field_type,
+            field_length: field_length,
+            decimal_count: decimal_count,
+        }
+    }
+
+    pub fn get_data_type(&self) -> FieldDataType {
+        match self.field_type {
+            'N' | 'F' => if self.decimal_count == 0 && self.field_type == 'N' { FieldDataType::Int } else { FieldDataType::Real },
+            'D' => FieldDataType::Date,
+            'L' => FieldDataType::Bool,
+            _ => FieldDataType::Text,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldDataType {
+    Int,
+    Real,
+    Text,
+    Date,
+    Bool,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct DateData {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for DateData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A single value in the attribute table of a Shapefile.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldData {
+    Int(i32),
+    Real(f64),
+    Text(String),
+    Date(DateData),
+    Bool(bool),
+    Null,
+}
+
+impl FieldData {
+    /// Returns the value as a floating-point number, where it is numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            FieldData::Int(v) => Some(v as f64),
+            FieldData::Real(v) => Some(v),
+            FieldData::Bool(v) => Some(if v { 1f64 } else { 0f64 }),
+            FieldData::Text(ref s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldData::Int(v) => write!(f, "{}", v),
+            FieldData::Real(v) => write!(f, "{}", v),
+            FieldData::Text(ref s) => write!(f, "{}", s),
+            FieldData::Date(d) => write!(f, "{}", d),
+            FieldData::Bool(v) => write!(f, "{}", v),
+            FieldData::Null => write!(f, ""),
+        }
+    }
+}
+
+/// The attribute table of a Shapefile, stored in a dBase (.dbf) file. There is one
+/// record for each shape, in the same order as the shapes.
+#[derive(Default, Clone, Debug)]
+pub struct ShapefileAttributes {
+    pub fields: Vec<AttributeField>,
+    data: Vec<Vec<FieldData>>,
+}
+
+impl ShapefileAttributes {
+    pub fn get_num_fields(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn get_num_records(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Adds a field to the table. Existing records are given a null value for the field.
+    pub fn add_field(&mut self, field: &AttributeField) {
+        self.fields.push(field.clone());
+        for rec in &mut self.data {
+            rec.push(FieldData::Null);
+        }
+    }
+
+    /// Returns the index of the field with a given name (case insensitive).
+    pub fn get_field_num<'a>(&self, name: &'a str) -> Option<usize> {
+        let name = name.to_lowercase();
+        self.fields.iter().position(|f| f.name.to_lowercase() == name)
+    }
+
+    /// Returns the index of the field named by a tool's field parameter, or `None` where the
+    /// name is 'FID', i.e. the feature ID (the record number, starting at one). Where `numeric`
+    /// is `true`, the field must hold integer, real or logical values.
+    pub fn get_field_or_fid<'a>(&self, field_name: &'a str, numeric: bool) -> Result<Option<usize>, Error> {
+        if field_name.to_lowercase() == "fid" {
+            return Ok(None);
+        }
+        let field = match self.get_field_num(field_name) {
+            Some(f) => f,
+            None => return Err(Error::new(ErrorKind::InvalidInput, format!("The attribute field '{}' does not exist.", field_name))),
+        };
+        if numeric {
+            match self.fields[field].get_data_type() {
+                FieldDataType::Int | FieldDataType::Real | FieldDataType::Bool => {},
+                _ => return Err(Error::new(ErrorKind::InvalidInput, format!("The attribute field '{}' is not numeric.", field_name))),
+            }
+        }
+        Ok(Some(field))
+    }
+
+    /// Returns a field name, based on `name` and no longer than the ten characters allowed
+    /// by the dBase format, that is used neither by the table's fields nor in `reserved`.
+    /// A numbered suffix, e.g. `_2`, is appended where necessary.
+    pub fn get_unique_field_name<'a>(&self, name: &'a str, reserved: &[String]) -> String {
+        let base: String = name.chars().take(10).collect();
+        let mut unique_name = base.clone();
+        let mut n = 1;
+        while reserved.iter().any(|r| r.to_lowercase() == unique_name.to_lowercase()) || self.get_field_num(&unique_name).is_some() {
+            n += 1;
+            let suffix = format!("_{}", n);
+            unique_name = format!("{}{}", base.chars().take(10 - suffix.len()).collect::<String>(), suffix);
+        }
+        unique_name
+    }
+
+    /// Adds a record to the table. The record must have a value for each field.
+    pub fn add_record(&mut self, rec: Vec<FieldData>) {
+        if rec.len() != self.fields.len() {
+            panic!("Error adding attribute record: the record has {} values but the table has {} fields.", rec.len(), self.fields.len());
+        }
+        self.data.push(rec);
+    }
+
+    pub fn get_record(&self, index: usize) -> &Vec<FieldData> {
+        &self.data[index]
+    }
+
+    pub fn get_value(&self, record: usize, field: usize) -> &FieldData {
+        &self.data[record][field]
+    }
+
+    pub fn set_value(&mut self, record: usize, field: usize, value: FieldData) {
+        self.data[record][field] = value;
+    }
+
+    /// Reads a dBase (.dbf) file.
+    pub fn read<'a>(file_name: &'a str) -> Result<ShapefileAttributes, Error> {
+        let mut f = File::open(file_name)?;
+        let mut buffer = vec![];
+        f.read_to_end(&mut buffer)?;
+        if buffer.len() < 32 {
+            return Err(Error::new(ErrorKind::InvalidData, "The attribute (.dbf) file is too short."));
+        }
+        let mut bor = ByteOrderReader::new(buffer, Endianness::LittleEndian);
+        bor.seek(4);
+        let num_records = bor.read_u32() as usize;
+        let header_length = bor.read_u16() as usize;
+        let record_length = bor.read_u16() as usize;
+        if header_length > bor.buffer.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "The attribute (.dbf) file is shorter than its header length."));
+        }
+
+        let mut attributes = ShapefileAttributes::default();
+        let mut pos = 32;
+        while pos + 32 <= header_length && bor.buffer[pos] != 0x0D {
+            let name_bytes: Vec<u8> = bor.buffer[pos..pos + 11].iter().cloned().take_while(|b| *b != 0).collect();
+            let name = String::from_utf8_lossy(&name_bytes).trim().to_string();
+            attributes.fields.push(AttributeField {
+                name: name,
+                field_type: bor.buffer[pos + 11] as char,
+                field_length: bor.buffer[pos + 16],
+                decimal_count: bor.buffer[pos + 17],
+            });
+            pos += 32;
+        }
+        // the deletion flag and the field values must fit within a record
+        if 1 + attributes.fields.iter().map(|f| f.field_length as usize).sum::<usize>() > record_length {
+            return Err(Error::new(ErrorKind::InvalidData, "The attribute (.dbf) field lengths exceed the record length."));
+        }
+
+        for i in 0..num_records {
+            let start = header_length + i * record_length;
+            if start + record_length > bor.buffer.len() {
+                return Err(Error::new(ErrorKind::InvalidData, "The attribute (.dbf) file is shorter than its header indicates."));
+            }
+            // the first byte of each record is the deletion flag
+            let mut p = start + 1;
+            let mut rec = Vec::with_capacity(attributes.fields.len());
+            for field in &attributes.fields {
+                let len = field.field_length as usize;
+                let s = String::from_utf8_lossy(&bor.buffer[p..p + len]).trim().to_string();
+                rec.push(parse_field_value(&s, field));
+                p += len;
+            }
+            attributes.data.push(rec);
+        }
+
+        Ok(attributes)
+    }
+
+    /// Writes the table to a dBase (.dbf) file.
+    pub fn write<'a>(&self, file_name: &'a str) -> Result<(), Error> {
+        let f = File::create(file_name)?;
+        let mut writer = BufWriter::new(f);
+
+        let header_length = 32 + 32 * self.fields.len() + 1;
+        let record_length = 1 + self.fields.iter().map(|f| f.field_length as usize).sum::<usize>();
+        let now = time::now();
+
+        writer.write_u8(0x03)?; // dBase III without memo
+        writer.write_u8(now.tm_year as u8)?; // years since 1900
+        writer.write_u8((now.tm_mon + 1) as u8)?;
+        writer.write_u8(now.tm_mday as u8)?;
+        writer.write_u32::<LittleEndian>(self.data.len() as u32)?;
+        writer.write_u16::<LittleEndian>(header_length as u16)?;
+        writer.write_u16::<LittleEndian>(record_length as u16)?;
+        writer.write_all(&[0u8; 20])?;
+
+        for field in &self.fields {
+            let mut descriptor = vec![0u8; 32];
+            for (i, b) in field.name.bytes().take(10).enumerate() {
+                descriptor[i] = b;
+            }
+            descriptor[11] = field.field_type as u8;
+            descriptor[16] = field.field_length;
+            descriptor[17] = field.decimal_count;
+            writer.write_all(&descriptor)?;
+        }
+        writer.write_all(&[0x0Du8])?;
+
+        for rec in &self.data {
+            writer.write_all(&[0x20u8])?; // not deleted
+            for (field, value) in self.fields.iter().zip(rec.iter()) {
+                writer.write_all(&format_field_value(value, field)?)?;
+            }
+        }
+        writer.write_all(&[0x1Au8])?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Returns a value formatted for a CSV file, quoted where it contains a comma or a quote.
+pub fn csv_value(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace("\"", "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn parse_field_value(s: &str, field: &AttributeField) -> FieldData {
+    if s.is_empty() {
+        return FieldData::Null;
+    }
+    match field.field_type {
+        'N' | 'F' => {
+            if field.decimal_count == 0 && field.field_type == 'N' {
+                match s.parse::<i32>() {
+                    Ok(v) => FieldData::Int(v),
+                    // integers too large for an i32 or using an exponent
+                    Err(_) => match s.parse::<f64>() {
+                        Ok(v) => FieldData::Real(v),
+                        Err(_) => FieldData::Null,
+                    },
+                }
+            } else {
+                match s.parse::<f64>() {
+                    Ok(v) => FieldData::Real(v),
+                    Err(_) => FieldData::Null,
+                }
+            }
+        },
+        'D' => {
+            if s.len() == 8 {
+                match (s[0..4].parse::<u16>(), s[4..6].parse::<u8>(), s[6..8].parse::<u8>()) {
+                    (Ok(year), Ok(month), Ok(day)) => FieldData::Date(DateData { year: year, month: month, day: day }),
+                    _ => FieldData::Null,
+                }
+            } else {
+                FieldData::Null
+            }
+        },
+        'L' => match s {
+            "T" | "t" | "Y" | "y" => FieldData::Bool(true),
+            "F" | "f" | "N" | "n" => FieldData::Bool(false),
+            _ => FieldData::Null, // '?' is uninitialized
+        },
+        _ => FieldData::Text(s.to_string()),
+    }
+}
+
+/// Formats a value for a field of the table. Numbers, dates and logical values that are too
+/// wide for the field are an error; text is truncated at a character boundary.
+fn format_field_value(value: &FieldData, field: &AttributeField) -> Result<Vec<u8>, Error> {
+    let len = field.field_length as usize;
+    let s = match *value {
+        FieldData::Int(v) => format!("{:>width$}", v, width = len),
+        FieldData::Real(v) => {
+            let s = if field.field_type == 'F' && field.decimal_count == 0 {
+                // a float field without a fixed number of decimals
+                format!("{:>width$}", v, width = len)
+            } else {
+                format!("{:>width$.prec$}", v, width = len, prec = field.decimal_count as usize)
+            };
+            if s.len() > len {
+                // too wide for the field; fall back on scientific notation
+                format!("{:>width$e}", v, width = len)
+            } else {
+                s
+            }
+        },
+        FieldData::Text(ref t) => format!("{:<width$}", t, width = len),
+        FieldData::Date(d) => format!("{}", d),
+        FieldData::Bool(v) => if v { "T".to_string() } else { "F".to_string() },
+        FieldData::Null => match field.field_type {
+            'L' => "?".to_string(),
+            _ => String::new(),
+        },
+    };
+    let mut bytes: Vec<u8> = match *value {
+        FieldData::Text(_) => {
+            let mut end = s.len().min(len);
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            s[..end].as_bytes().to_vec()
+        },
+        _ => {
+            if s.len() > len {
+                return Err(Error::new(ErrorKind::InvalidData, format!("The value {} is too wide for the {}-character attribute field '{}'.", s.trim(), len, field.name)));
+            }
+            s.into_bytes()
+        },
+    };
+    while bytes.len() < len {
+        bytes.push(0x20u8);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::time;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn temp_file_name(name: &str) -> String {
+        env::temp_dir().join(format!("wbt_attributes_{}_{}", process::id(), name)).to_str().unwrap().to_string()
+    }
+
+    fn field_descriptor(name: &str, field_type: char, length: u8, decimals: u8) -> Vec<u8> {
+        let mut descriptor = vec![0u8; 32];
+        descriptor[..name.len()].copy_from_slice(name.as_bytes());
+        descriptor[11] = field_type as u8;
+        descriptor[16] = length;
+        descriptor[17] = decimals;
+        descriptor
+    }
+
+    /// A dBase III table with C, N (with and without decimals), F, D and L fields and
+    /// three records, the second of which is entirely null.
+    fn hand_built_dbf() -> Vec<u8> {
+        let fields = vec![
+            field_descriptor("NAME", 'C', 8, 0),
+            field_descriptor("COUNT", 'N', 5, 0),
+            field_descriptor("AREA", 'N', 8, 2),
+            field_descriptor("RATIO", 'F', 10, 3),
+            field_descriptor("SURVEYED", 'D', 8, 0),
+            field_descriptor("ACTIVE", 'L', 1, 0),
+        ];
+        let records = vec![
+            " Lake       42  123.45     0.12520180222T",
+            "                                        ?",
+            " River      -7   -0.50  1500.00019991231F",
+        ];
+        let header_length = 32 + 32 * fields.len() + 1;
+        let record_length = 41;
+        let mut bytes = vec![0x03u8, 118, 2, 22];
+        bytes.write_u32::<LittleEndian>(records.len() as u32).unwrap();
+        bytes.write_u16::<LittleEndian>(header_length as u16).unwrap();
+        bytes.write_u16::<LittleEndian>(record_length as u16).unwrap();
+        bytes.extend_from_slice(&[0u8; 20]);
+        for f in &fields {
+            bytes.extend_from_slice(f);
+        }
+        bytes.push(0x0D);
+        for r in &records {
+            assert_eq!(r.len(), record_length);
+            bytes.extend_from_slice(r.as_bytes());
+        }
+        bytes.push(0x1A);
+        bytes
+    }
+
+    #[test]
+    fn test_read_field_types_and_nulls() {
+        let file_name = temp_file_name("read.dbf");
+        fs::write(&file_name, hand_built_dbf()).unwrap();
+        let attributes = ShapefileAttributes::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(attributes.get_num_fields(), 6);
+        assert_eq!(attributes.get_num_records(), 3);
+        assert_eq!(attributes.fields[2], AttributeField { name: "AREA".to_string(), field_type: 'N', field_length: 8, decimal_count: 2 });
+        assert_eq!(attributes.get_field_num("surveyed"), Some(4));
+        assert_eq!(attributes.get_record(0), &vec![
+            FieldData::Text("Lake".to_string()),
+            FieldData::Int(42),
+            FieldData::Real(123.45),
+            FieldData::Real(0.125),
+            FieldData::Date(DateData { year: 2018, month: 2, day: 22 }),
+            FieldData::Bool(true),
+        ]);
+        assert!(attributes.get_record(1).iter().all(|v| *v == FieldData::Null));
+        assert_eq!(attributes.get_record(2), &vec![
+            FieldData::Text("River".to_string()),
+            FieldData::Int(-7),
+            FieldData::Real(-0.5),
+            FieldData::Real(1500.0),
+            FieldData::Date(DateData { year: 1999, month: 12, day: 31 }),
+            FieldData::Bool(false),
+        ]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input_file = temp_file_name("round_trip_in.dbf");
+        let output_file = temp_file_name("round_trip_out.dbf");
+        let input_bytes = hand_built_dbf();
+        fs::write(&input_file, &input_bytes).unwrap();
+        let attributes = ShapefileAttributes::read(&input_file).unwrap();
+        attributes.write(&output_file).unwrap();
+        let output_bytes = fs::read(&output_file).unwrap();
+        let output = ShapefileAttributes::read(&output_file).unwrap();
+        fs::remove_file(&input_file).unwrap();
+        fs::remove_file(&output_file).unwrap();
+
+        // only the date of last update differs
+        assert_eq!(&output_bytes[4..], &input_bytes[4..]);
+        assert_eq!(output_bytes[1], time::now().tm_year as u8);
+        assert_eq!(output.fields, attributes.fields);
+        for i in 0..attributes.get_num_records() {
+            assert_eq!(output.get_record(i), attributes.get_record(i));
+        }
+    }
+
+    #[test]
+    fn test_write_new_fields() {
+        let file_name = temp_file_name("new_fields.dbf");
+        let mut attributes = ShapefileAttributes::default();
+        attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 6, 0));
+        attributes.add_field(&AttributeField::new("LENGTH", FieldDataType::Real, 12, 4));
+        attributes.add_field(&AttributeField::new("SCORE", FieldDataType::Real, 12, 0));
+        attributes.add_field(&AttributeField::new("LABEL", FieldDataType::Text, 12, 0));
+        attributes.add_field(&AttributeField::new("DATE", FieldDataType::Date, 20, 0));
+        attributes.add_field(&AttributeField::new("VALID", FieldDataType::Bool, 20, 0));
+        let field_types: Vec<(char, u8)> = attributes.fields.iter().map(|f| (f.field_type, f.field_length)).collect();
+        assert_eq!(field_types, vec![('N', 6), ('N', 12), ('F', 12), ('C', 12), ('D', 8), ('L', 1)]);
+
+        attributes.add_record(vec![
+            FieldData::Int(1),
+            FieldData::Real(2.5),
+            FieldData::Real(-3.25),
+            FieldData::Text("first".to_string()),
+            FieldData::Date(DateData { year: 2018, month: 1, day: 5 }),
+            FieldData::Bool(true),
+        ]);
+        attributes.add_record(vec![FieldData::Null; 6]);
+        attributes.write(&file_name).unwrap();
+        let output = ShapefileAttributes::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(output.fields, attributes.fields);
+        assert_eq!(output.get_record(0), attributes.get_record(0));
+        assert_eq!(output.get_record(1), attributes.get_record(1));
+    }
+
+    #[test]
+    fn test_write_values_too_wide() {
+        let file_name = temp_file_name("too_wide.dbf");
+        let mut attributes = ShapefileAttributes::default();
+        attributes.add_field(&AttributeField::new("COUNT", FieldDataType::Int, 4, 0));
+        attributes.add_record(vec![FieldData::Int(123456)]);
+        let result = attributes.write(&file_name);
+        fs::remove_file(&file_name).unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // text is truncated, but never within a character
+        let mut attributes = ShapefileAttributes::default();
+        attributes.add_field(&AttributeField::new("NAME", FieldDataType::Text, 2, 0));
+        attributes.add_record(vec![FieldData::Text("h\u{e9}llo".to_string())]);
+        attributes.add_record(vec![FieldData::Text("\u{e9}t\u{e9}".to_string())]);
+        attributes.write(&file_name).unwrap();
+        let output = ShapefileAttributes::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+        assert_eq!(output.get_value(0, 0), &FieldData::Text("h".to_string()));
+        assert_eq!(output.get_value(1, 0), &FieldData::Text("\u{e9}".to_string()));
+    }
+
+    #[test]
+    fn test_get_field_or_fid() {
+        let file_name = temp_file_name("field_or_fid.dbf");
+        fs::write(&file_name, hand_built_dbf()).unwrap();
+        let attributes = ShapefileAttributes::read(&file_name).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(attributes.get_field_or_fid("FID", true).unwrap(), None);
+        assert_eq!(attributes.get_field_or_fid("fid", false).unwrap(), None);
+        assert_eq!(attributes.get_field_or_fid("area", true).unwrap(), Some(2));
+        assert_eq!(attributes.get_field_or_fid("ACTIVE", true).unwrap(), Some(5));
+        assert_eq!(attributes.get_field_or_fid("NAME", false).unwrap(), Some(0));
+        assert_eq!(attributes.get_field_or_fid("NAME", true).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(attributes.get_field_or_fid("SURVEYED", true).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(attributes.get_field_or_fid("MISSING", false).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_get_unique_field_name() {
+        let mut attributes = ShapefileAttributes::default();
+        attributes.add_field(&AttributeField::new("MEAN", FieldDataType::Real, 12, 4));
+        attributes.add_field(&AttributeField::new("ELEVATION", FieldDataType::Real, 12, 4));
+        let reserved = vec!["MEAN_2".to_string(), "count".to_string()];
+        assert_eq!(attributes.get_unique_field_name("MAX", &reserved), "MAX");
+        assert_eq!(attributes.get_unique_field_name("mean", &reserved), "mean_3");
+        assert_eq!(attributes.get_unique_field_name("COUNT", &reserved), "COUNT_2");
+        assert_eq!(attributes.get_unique_field_name("ELEVATION", &reserved), "ELEVATIO_2");
+        assert_eq!(attributes.get_unique_field_name("ELEVATION_DIFF", &reserved), "ELEVATION_");
+    }
+
+    #[test]
+    fn test_csv_value() {
+        assert_eq!(csv_value("plain"), "plain");
+        assert_eq!(csv_value("a,b"), "\"a,b\"");
+        assert_eq!(csv_value("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_read_invalid_lengths() {
+        // a header length beyond the end of the file
+        let file_name = temp_file_name("bad_header_length.dbf");
+        let mut bytes = hand_built_dbf();
+        bytes[8] = 0xFF;
+        bytes[9] = 0xFF;
+        fs::write(&file_name, &bytes).unwrap();
+        let result = ShapefileAttributes::read(&file_name);
+        fs::remove_file(&file_name).unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // fields that do not fit within a record
+        let file_name = temp_file_name("bad_record_length.dbf");
+        let mut bytes = hand_built_dbf();
+        bytes[10] = 10;
+        bytes[11] = 0;
+        fs::write(&file_name, &bytes).unwrap();
+        let result = ShapefileAttributes::read(&file_name);
+        fs::remove_file(&file_name).unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
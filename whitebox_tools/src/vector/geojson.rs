@@ -0,0 +1,581 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 4, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+
+use serde_json;
+use serde_json::{Map, Value};
+use std::f64;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error, ErrorKind};
+use structures::Point2D;
+use vector::attributes::{AttributeField, FieldData, FieldDataType, ShapefileAttributes};
+use vector::geometry::{signed_area, ShapefileGeometry, ShapeType};
+
+/// Reads a GeoJSON file containing a FeatureCollection, a single Feature or a bare
+/// geometry, returning the shape type, the geometries and their attributes. All
+/// non-null geometries must share a base type, i.e. points, lines (LineString and
+/// MultiLineString), or polygons (Polygon and MultiPolygon). Multi-part geometries
+/// become multi-part shapes and coordinates with a third value give Z shape types.
+/// Polygon rings are re-ordered to the Shapefile convention of clockwise outer rings
+/// and counter-clockwise holes. The attribute fields are taken from the feature
+/// properties, with the field type inferred from the property values.
+pub fn read_geojson<'a>(file_name: &'a str) -> Result<(ShapeType, Vec<ShapefileGeometry>, ShapefileAttributes), Error> {
+    let mut f = File::open(file_name)?;
+    let mut buffer = vec![];
+    f.read_to_end(&mut buffer)?;
+    let v: Value = match serde_json::from_slice(&buffer) {
+        Ok(v) => v,
+        Err(e) => return Err(Error::new(ErrorKind::InvalidData, format!("Error parsing GeoJSON file: {}", e))),
+    };
+
+    // collect the (geometry, properties) of each feature
+    let null = Value::Null;
+    let mut features: Vec<(&Value, &Value)> = vec![];
+    match v["type"].as_str() {
+        Some("FeatureCollection") => {
+            if let Some(fs) = v["features"].as_array() {
+                for feature in fs {
+                    features.push((&feature["geometry"], &feature["properties"]));
+                }
+            }
+        },
+        Some("Feature") => features.push((&v["geometry"], &v["properties"])),
+        Some(_) => features.push((&v, &null)),
+        None => return Err(Error::new(ErrorKind::InvalidData, "The file does not appear to be a valid GeoJSON file.")),
+    }
+
+    // determine the shape type
+    let mut base_type = ShapeType::Null;
+    let mut has_z = false;
+    for &(geometry, _) in &features {
+        let t = match geometry["type"].as_str() {
+            Some("Point") => ShapeType::Point,
+            Some("MultiPoint") => ShapeType::MultiPoint,
+            Some("LineString") | Some("MultiLineString") => ShapeType::PolyLine,
+            Some("Polygon") | Some("MultiPolygon") => ShapeType::Polygon,
+            Some(t) => return Err(Error::new(ErrorKind::InvalidData, format!("Unsupported GeoJSON geometry type ({}).", t))),
+            None => continue, // a null geometry
+        };
+        if base_type == ShapeType::Null {
+            base_type = t;
+        } else if t != base_type {
+            return Err(Error::new(ErrorKind::InvalidData, "GeoJSON files containing mixed geometry types are not supported."));
+        }
+        if !has_z {
+            has_z = coordinates_have_z(&geometry["coordinates"]);
+        }
+    }
+    let shape_type = if !has_z {
+        base_type
+    } else {
+        match base_type {
+            ShapeType::Point => ShapeType::PointZ,
+            ShapeType::MultiPoint => ShapeType::MultiPointZ,
+            ShapeType::PolyLine => ShapeType::PolyLineZ,
+            ShapeType::Polygon => ShapeType::PolygonZ,
+            _ => ShapeType::Null,
+        }
+    };
+
+    let mut geometries = Vec::with_capacity(features.len());
+    for &(geometry, _) in &features {
+        geometries.push(read_geometry(geometry, shape_type)?);
+    }
+
+    Ok((shape_type, geometries, read_properties(&features)))
+}
+
+/// Writes geometries and their attributes to a GeoJSON FeatureCollection. Polygon
+/// rings are ordered counter-clockwise for outer rings and clockwise for holes, as
+/// recommended by RFC 7946, and polygons with more than one outer ring are written
+/// as MultiPolygons. Measures (m values) are not written.
+pub fn write_geojson<'a>(file_name: &'a str, geometries: &[ShapefileGeometry], attributes: &ShapefileAttributes) -> Result<(), Error> {
+    let mut features = Vec::with_capacity(geometries.len());
+    for (i, sfg) in geometries.iter().enumerate() {
+        let mut properties = Map::new();
+        if i < attributes.get_num_records() {
+            for (j, field) in attributes.fields.iter().enumerate() {
+                let value = match *attributes.get_value(i, j) {
+                    FieldData::Int(v) => Value::from(v),
+                    FieldData::Real(v) => if v.is_finite() { Value::from(v) } else { Value::Null },
+                    FieldData::Text(ref s) => Value::from(s.clone()),
+                    FieldData::Date(d) => Value::from(format!("{:04}-{:02}-{:02}", d.year, d.month, d.day)),
+                    FieldData::Bool(v) => Value::from(v),
+                    FieldData::Null => Value::Null,
+                };
+                properties.insert(field.name.clone(), value);
+            }
+        }
+        let mut feature = Map::new();
+        feature.insert("type".to_string(), Value::from("Feature"));
+        feature.insert("geometry".to_string(), write_geometry(sfg));
+        feature.insert("properties".to_string(), Value::Object(properties));
+        features.push(Value::Object(feature));
+    }
+    let mut collection = Map::new();
+    collection.insert("type".to_string(), Value::from("FeatureCollection"));
+    collection.insert("features".to_string(), Value::Array(features));
+
+    let f = File::create(file_name)?;
+    let mut writer = BufWriter::new(f);
+    match serde_json::to_writer(&mut writer, &Value::Object(collection)) {
+        Ok(_) => {},
+        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error writing GeoJSON file: {}", e))),
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn coordinates_have_z(v: &Value) -> bool {
+    match v.as_array() {
+        Some(a) => {
+            if a.len() > 0 && a[0].is_number() {
+                a.len() > 2
+            } else {
+                a.iter().any(|c| coordinates_have_z(c))
+            }
+        },
+        None => false,
+    }
+}
+
+fn read_position(v: &Value) -> Result<(Point2D, f64), Error> {
+    match v.as_array() {
+        Some(a) if a.len() >= 2 => {
+            let x = a[0].as_f64().unwrap_or(f64::NAN);
+            let y = a[1].as_f64().unwrap_or(f64::NAN);
+            let z = if a.len() > 2 { a[2].as_f64().unwrap_or(0f64) } else { 0f64 };
+            if x.is_nan() || y.is_nan() {
+                return Err(Error::new(ErrorKind::InvalidData, "Invalid GeoJSON position."));
+            }
+            Ok((Point2D::new(x, y), z))
+        },
+        _ => Err(Error::new(ErrorKind::InvalidData, "Invalid GeoJSON position.")),
+    }
+}
+
+fn read_positions(v: &Value) -> Result<(Vec<Point2D>, Vec<f64>), Error> {
+    let mut points = vec![];
+    let mut z_values = vec![];
+    if let Some(a) = v.as_array() {
+        for c in a {
+            let (p, z) = read_position(c)?;
+            points.push(p);
+            z_values.push(z);
+        }
+    }
+    Ok((points, z_values))
+}
+
+fn add_part(sfg: &mut ShapefileGeometry, points: &[Point2D], z_values: &[f64]) {
+    if sfg.shape_type.has_z() {
+        sfg.add_part_zm(points, z_values, &vec![f64::NAN; points.len()]);
+    } else {
+        sfg.add_part(points);
+    }
+}
+
+fn add_polygon(sfg: &mut ShapefileGeometry, rings: &Value) -> Result<(), Error> {
+    if let Some(a) = rings.as_array() {
+        for (i, ring) in a.iter().enumerate() {
+            let (mut points, mut z_values) = read_positions(ring)?;
+            // outer rings are clockwise and holes counter-clockwise in a Shapefile
+            let clockwise = signed_area(&points) > 0f64;
+            if (i == 0) != clockwise {
+                points.reverse();
+                z_values.reverse();
+            }
+            add_part(sfg, &points, &z_values);
+        }
+    }
+    Ok(())
+}
+
+fn read_geometry(geometry: &Value, shape_type: ShapeType) -> Result<ShapefileGeometry, Error> {
+    let coordinates = &geometry["coordinates"];
+    let mut sfg = match geometry["type"].as_str() {
+        Some(_) => ShapefileGeometry::new(shape_type),
+        None => return Ok(ShapefileGeometry::new(ShapeType::Null)),
+    };
+    match geometry["type"].as_str() {
+        Some("Point") => {
+            let (p, z) = read_position(coordinates)?;
+            if shape_type.has_z() {
+                sfg.add_point_zm(p, z, f64::NAN);
+            } else {
+                sfg.add_point(p);
+            }
+        },
+        Some("MultiPoint") => {
+            let (points, z_values) = read_positions(coordinates)?;
+            for i in 0..points.len() {
+                if shape_type.has_z() {
+                    sfg.add_point_zm(points[i], z_values[i], f64::NAN);
+                } else {
+                    sfg.add_point(points[i]);
+                }
+            }
+        },
+        Some("LineString") => {
+            let (points, z_values) = read_positions(coordinates)?;
+            add_part(&mut sfg, &points, &z_values);
+        },
+        Some("MultiLineString") => {
+            if let Some(a) = coordinates.as_array() {
+                for line in a {
+                    let (points, z_values) = read_positions(line)?;
+                    add_part(&mut sfg, &points, &z_values);
+                }
+            }
+        },
+        Some("Polygon") => add_polygon(&mut sfg, coordinates)?,
+        Some("MultiPolygon") => {
+            if let Some(a) = coordinates.as_array() {
+                for polygon in a {
+                    add_polygon(&mut sfg, polygon)?;
+                }
+            }
+        },
+        _ => {},
+    }
+    if sfg.num_points == 0 {
+        // e.g. an empty LineString
+        return Ok(ShapefileGeometry::new(ShapeType::Null));
+    }
+    Ok(sfg)
+}
+
+fn read_properties(features: &[(&Value, &Value)]) -> ShapefileAttributes {
+    // the fields are the union of the feature properties
+    let mut names: Vec<String> = vec![];
+    for &(_, properties) in features {
+        if let Some(m) = properties.as_object() {
+            for key in m.keys() {
+                if !names.contains(key) {
+                    names.push(key.clone());
+                }
+            }
+        }
+    }
+
+    let mut attributes = ShapefileAttributes::default();
+    for name in &names {
+        let values: Vec<&Value> = features.iter().map(|&(_, p)| &p[name.as_str()]).filter(|v| !v.is_null()).collect();
+        let field = if values.len() > 0 && values.iter().all(|v| v.is_i64() && v.as_i64().unwrap().abs() < 1_000_000_000) {
+            let width = values.iter().map(|v| format!("{}", v).len()).max().unwrap_or(1);
+            AttributeField::new(name, FieldDataType::Int, width.max(1) as u8, 0)
+        } else if values.len() > 0 && values.iter().all(|v| v.is_number()) {
+            AttributeField::new(name, FieldDataType::Real, 19, 6)
+        } else if values.len() > 0 && values.iter().all(|v| v.is_boolean()) {
+            AttributeField::new(name, FieldDataType::Bool, 1, 0)
+        } else {
+            let width = values.iter().map(|v| value_to_string(v).len()).max().unwrap_or(1);
+            AttributeField::new(name, FieldDataType::Text, width.max(1).min(254) as u8, 0)
+        };
+        attributes.add_field(&field);
+    }
+
+    for &(_, properties) in features {
+        let mut rec = Vec::with_capacity(names.len());
+        for (j, name) in names.iter().enumerate() {
+            let v = &properties[name.as_str()];
+            let value = if v.is_null() {
+                FieldData::Null
+            } else {
+                match attributes.fields[j].get_data_type() {
+                    FieldDataType::Int => FieldData::Int(v.as_i64().unwrap() as i32),
+                    FieldDataType::Real => FieldData::Real(v.as_f64().unwrap()),
+                    FieldDataType::Bool => FieldData::Bool(v.as_bool().unwrap()),
+                    _ => FieldData::Text(value_to_string(v)),
+                }
+            };
+            rec.push(value);
+        }
+        attributes.add_record(rec);
+    }
+    attributes
+}
+
+fn value_to_string(v: &Value) -> String {
+    match v.as_str() {
+        Some(s) => s.to_string(),
+        None => format!("{}", v),
+    }
+}
+
+fn write_positions(sfg: &ShapefileGeometry, start: usize, end: usize, reverse: bool) -> Value {
+    let mut positions = Vec::with_capacity(end - start);
+    for i in start..end {
+        let k = if reverse { end - 1 - (i - start) } else { i };
+        let mut c = vec![Value::from(sfg.points[k].x), Value::from(sfg.points[k].y)];
+        if sfg.shape_type.has_z() {
+            c.push(Value::from(sfg.z_array[k]));
+        }
+        positions.push(Value::Array(c));
+    }
+    Value::Array(positions)
+}
+
+fn write_geometry(sfg: &ShapefileGeometry) -> Value {
+    if sfg.shape_type == ShapeType::Null || sfg.num_points == 0 {
+        return Value::Null;
+    }
+    let (geometry_type, coordinates) = match sfg.shape_type.base_shape_type() {
+        ShapeType::Point => {
+            let c = write_positions(sfg, 0, 1, false);
+            ("Point", c[0].clone())
+        },
+        ShapeType::MultiPoint => ("MultiPoint", write_positions(sfg, 0, sfg.num_points as usize, false)),
+        ShapeType::PolyLine => {
+            let mut lines = vec![];
+            for part in 0..sfg.num_parts as usize {
+                let (start, end) = sfg.part_range(part);
+                lines.push(write_positions(sfg, start, end, false));
+            }
+            if lines.len() == 1 {
+                ("LineString", lines[0].clone())
+            } else {
+                ("MultiLineString", Value::Array(lines))
+            }
+        },
+        _ => {
+            // group the holes with the outer ring that contains them
+            let mut polygons: Vec<Vec<usize>> = vec![];
+            for part in 0..sfg.num_parts as usize {
+                if !sfg.is_hole(part) || polygons.len() == 0 {
+                    polygons.push(vec![part]);
+                } else {
+                    let (start, _) = sfg.part_range(part);
+                    let p = sfg.points[start];
+                    let k = polygons.iter().position(|rings| sfg.part_contains(rings[0], &p)).unwrap_or(polygons.len() - 1);
+                    polygons[k].push(part);
+                }
+            }
+            let mut polygon_values = vec![];
+            for rings in &polygons {
+                let mut ring_values = vec![];
+                for &part in rings {
+                    let (start, end) = sfg.part_range(part);
+                    // RFC 7946 orders outer rings counter-clockwise and holes clockwise
+                    let clockwise = sfg.part_signed_area(part) > 0f64;
+                    let reverse = (part == rings[0]) == clockwise;
+                    ring_values.push(write_positions(sfg, start, end, reverse));
+                }
+                polygon_values.push(Value::Array(ring_values));
+            }
+            if polygon_values.len() == 1 {
+                ("Polygon", polygon_values[0].clone())
+            } else {
+                ("MultiPolygon", Value::Array(polygon_values))
+            }
+        },
+    };
+    let mut geometry = Map::new();
+    geometry.insert("type".to_string(), Value::from(geometry_type));
+    geometry.insert("coordinates".to_string(), coordinates);
+    Value::Object(geometry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+    use vector::shapefile::Shapefile;
+
+    fn temp_file_name(name: &str) -> String {
+        env::temp_dir().join(format!("wbt_geojson_{}_{}", process::id(), name)).to_str().unwrap().to_string()
+    }
+
+    /// A polygon with a hole, its outer ring ordered counter-clockwise and its hole
+    /// clockwise as in RFC 7946, followed by a feature with a null geometry.
+    const POLYGONS: &'static str = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [
+                        [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [0.0, 0.0]],
+                        [[4.0, 4.0], [4.0, 6.0], [6.0, 6.0], [6.0, 4.0], [4.0, 4.0]]
+                    ]
+                },
+                "properties": {"ID": 1, "NAME": "lake", "AREA": 96.0, "SURVEYED": true}
+            },
+            {
+                "type": "Feature",
+                "geometry": null,
+                "properties": {"ID": 22, "NAME": "unmapped", "AREA": 3, "SURVEYED": null}
+            }
+        ]
+    }"#;
+
+    fn read_string(name: &str, contents: &str) -> Result<(ShapeType, Vec<ShapefileGeometry>, ShapefileAttributes), Error> {
+        let file_name = temp_file_name(name);
+        fs::write(&file_name, contents).unwrap();
+        let result = read_geojson(&file_name);
+        fs::remove_file(&file_name).unwrap();
+        result
+    }
+
+    /// Returns the signed areas of the rings of a GeoJSON Polygon's coordinates.
+    fn ring_areas(coordinates: &Value) -> Vec<f64> {
+        coordinates.as_array().unwrap().iter().map(|ring| signed_area(&read_positions(ring).unwrap().0)).collect()
+    }
+
+    #[test]
+    fn test_feature_collection_round_trip() {
+        let input_file = temp_file_name("round_trip_in.geojson");
+        let output_file = temp_file_name("round_trip_out.geojson");
+        fs::write(&input_file, POLYGONS).unwrap();
+        let input = Shapefile::new(&input_file, "r").unwrap();
+        let mut output = Shapefile::initialize_using_file(&output_file, &input, input.header.shape_type, true).unwrap();
+        for i in 0..input.num_records {
+            output.add_record(input.get_record(i).clone());
+            output.attributes.add_record(input.attributes.get_record(i).clone());
+        }
+        output.write().unwrap();
+        let written: Value = serde_json::from_slice(&fs::read(&output_file).unwrap()).unwrap();
+        let output = Shapefile::new(&output_file, "r").unwrap();
+        fs::remove_file(&input_file).unwrap();
+        fs::remove_file(&output_file).unwrap();
+
+        assert_eq!(input.header.shape_type, ShapeType::Polygon);
+        assert_eq!(input.num_records, 2);
+        assert_eq!(output.header.shape_type, ShapeType::Polygon);
+        assert_eq!(output.num_records, 2);
+        for i in 0..input.num_records {
+            assert_eq!(output.get_record(i).shape_type, input.get_record(i).shape_type);
+            assert_eq!(output.get_record(i).parts, input.get_record(i).parts);
+            assert_eq!(output.get_record(i).points, input.get_record(i).points);
+            assert_eq!(output.attributes.get_record(i), input.attributes.get_record(i));
+        }
+        assert_eq!(output.attributes.fields, input.attributes.fields);
+        // the rings are written in the order in which they were read
+        let input_value: Value = serde_json::from_str(POLYGONS).unwrap();
+        assert_eq!(written["features"][0]["geometry"], input_value["features"][0]["geometry"]);
+    }
+
+    #[test]
+    fn test_read_ring_orientation() {
+        let (_, geometries, _) = read_string("read_orientation.geojson", POLYGONS).unwrap();
+        let polygon = &geometries[0];
+        assert_eq!(polygon.num_parts, 2);
+        // outer rings are clockwise, i.e. have a positive signed area, in a Shapefile
+        assert!(polygon.part_signed_area(0) > 0f64);
+        assert!(polygon.part_signed_area(1) < 0f64);
+        assert!(!polygon.is_hole(0));
+        assert!(polygon.is_hole(1));
+        assert!(polygon.contains(&Point2D::new(1.0, 1.0)));
+        assert!(!polygon.contains(&Point2D::new(5.0, 5.0)));
+    }
+
+    #[test]
+    fn test_write_ring_orientation() {
+        // two polygons, each with a clockwise outer ring, the first with a counter-clockwise hole
+        let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+        sfg.add_part(&[Point2D::new(0.0, 0.0), Point2D::new(0.0, 10.0), Point2D::new(10.0, 10.0), Point2D::new(10.0, 0.0), Point2D::new(0.0, 0.0)]);
+        sfg.add_part(&[Point2D::new(4.0, 4.0), Point2D::new(6.0, 4.0), Point2D::new(6.0, 6.0), Point2D::new(4.0, 6.0), Point2D::new(4.0, 4.0)]);
+        sfg.add_part(&[Point2D::new(20.0, 0.0), Point2D::new(20.0, 5.0), Point2D::new(25.0, 5.0), Point2D::new(25.0, 0.0), Point2D::new(20.0, 0.0)]);
+        let file_name = temp_file_name("write_orientation.geojson");
+        write_geojson(&file_name, &[sfg], &ShapefileAttributes::default()).unwrap();
+        let written: Value = serde_json::from_slice(&fs::read(&file_name).unwrap()).unwrap();
+        fs::remove_file(&file_name).unwrap();
+
+        let geometry = &written["features"][0]["geometry"];
+        assert_eq!(geometry["type"], "MultiPolygon");
+        let polygons = geometry["coordinates"].as_array().unwrap();
+        assert_eq!(polygons.len(), 2);
+        // RFC 7946 outer rings are counter-clockwise and holes clockwise
+        let areas = ring_areas(&polygons[0]);
+        assert_eq!(areas.len(), 2);
+        assert!(areas[0] < 0f64);
+        assert!(areas[1] > 0f64);
+        let areas = ring_areas(&polygons[1]);
+        assert_eq!(areas.len(), 1);
+        assert!(areas[0] < 0f64);
+    }
+
+    #[test]
+    fn test_property_type_inference() {
+        let contents = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1.0, 2.0]},
+                 "properties": {"COUNT": 7, "RATIO": 0.5, "VALID": false, "LABEL": "a,b", "MIXED": 1}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [3.0, 4.0]},
+                 "properties": {"COUNT": -123, "RATIO": 2, "VALID": true, "LABEL": null, "MIXED": "two"}}
+            ]
+        }"#;
+        let (shape_type, geometries, attributes) = read_string("properties.geojson", contents).unwrap();
+        assert_eq!(shape_type, ShapeType::Point);
+        assert_eq!(geometries.len(), 2);
+
+        let field = |name: &str| attributes.get_field_num(name).unwrap();
+        assert_eq!(attributes.fields[field("COUNT")].get_data_type(), FieldDataType::Int);
+        assert_eq!(attributes.fields[field("COUNT")].field_length, 4);
+        assert_eq!(attributes.fields[field("RATIO")].get_data_type(), FieldDataType::Real);
+        assert_eq!(attributes.fields[field("VALID")].get_data_type(), FieldDataType::Bool);
+        assert_eq!(attributes.fields[field("LABEL")].get_data_type(), FieldDataType::Text);
+        assert_eq!(attributes.fields[field("MIXED")].get_data_type(), FieldDataType::Text);
+
+        assert_eq!(attributes.get_value(1, field("COUNT")), &FieldData::Int(-123));
+        assert_eq!(attributes.get_value(1, field("RATIO")), &FieldData::Real(2.0));
+        assert_eq!(attributes.get_value(0, field("VALID")), &FieldData::Bool(false));
+        assert_eq!(attributes.get_value(0, field("LABEL")), &FieldData::Text("a,b".to_string()));
+        assert_eq!(attributes.get_value(1, field("LABEL")), &FieldData::Null);
+        assert_eq!(attributes.get_value(0, field("MIXED")), &FieldData::Text("1".to_string()));
+    }
+
+    #[test]
+    fn test_null_geometries() {
+        let (shape_type, geometries, attributes) = read_string("null_geometries.geojson", POLYGONS).unwrap();
+        assert_eq!(shape_type, ShapeType::Polygon);
+        assert_eq!(geometries[1].shape_type, ShapeType::Null);
+        assert_eq!(geometries[1].num_points, 0);
+        assert_eq!(attributes.get_num_records(), 2);
+
+        // a file holding only null geometries
+        let contents = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "geometry": null, "properties": null}]}"#;
+        let (shape_type, geometries, _) = read_string("only_null.geojson", contents).unwrap();
+        assert_eq!(shape_type, ShapeType::Null);
+        assert_eq!(geometries.len(), 1);
+
+        let file_name = temp_file_name("write_null.geojson");
+        write_geojson(&file_name, &geometries, &ShapefileAttributes::default()).unwrap();
+        let written: Value = serde_json::from_slice(&fs::read(&file_name).unwrap()).unwrap();
+        fs::remove_file(&file_name).unwrap();
+        assert!(written["features"][0]["geometry"].is_null());
+    }
+
+    #[test]
+    fn test_mixed_geometry_types() {
+        let contents = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1.0, 2.0]}, "properties": {}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]]}, "properties": {}}
+            ]
+        }"#;
+        let result = read_string("mixed.geojson", contents);
+        assert_eq!(result.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+
+        // lines and multi-lines share a base type
+        let contents = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]]}, "properties": {}},
+                {"type": "Feature", "geometry": {"type": "MultiLineString", "coordinates": [[[2.0, 2.0], [3.0, 3.0]]]}, "properties": {}}
+            ]
+        }"#;
+        let (shape_type, _, _) = read_string("lines.geojson", contents).unwrap();
+        assert_eq!(shape_type, ShapeType::PolyLine);
+    }
+}
@@ -0,0 +1,400 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 2, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+
+use std::f64;
+use std::fmt;
+use structures::Point2D;
+
+/// The shape types of the ESRI Shapefile specification. Every shape in a Shapefile
+/// shares the same type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeType {
+    Null = 0,
+    Point = 1,
+    PolyLine = 3,
+    Polygon = 5,
+    MultiPoint = 8,
+    PointZ = 11,
+    PolyLineZ = 13,
+    PolygonZ = 15,
+    MultiPointZ = 18,
+    PointM = 21,
+    PolyLineM = 23,
+    PolygonM = 25,
+    MultiPointM = 28,
+}
+
+impl Default for ShapeType {
+    fn default() -> ShapeType {
+        ShapeType::Null
+    }
+}
+
+impl ShapeType {
+    pub fn from_int(value: i32) -> Option<ShapeType> {
+        match value {
+            0 => Some(ShapeType::Null),
+            1 => Some(ShapeType::Point),
+            3 => Some(ShapeType::PolyLine),
+            5 => Some(ShapeType::Polygon),
+            8 => Some(ShapeType::MultiPoint),
+            11 => Some(ShapeType::PointZ),
+            13 => Some(ShapeType::PolyLineZ),
+            15 => Some(ShapeType::PolygonZ),
+            18 => Some(ShapeType::MultiPointZ),
+            21 => Some(ShapeType::PointM),
+            23 => Some(ShapeType::PolyLineM),
+            25 => Some(ShapeType::PolygonM),
+            28 => Some(ShapeType::MultiPointM),
+            _ => None,
+        }
+    }
+
+    /// Returns the two-dimensional shape type underlying a Z or M shape type,
+    /// e.g. `ShapeType::Polygon` for `ShapeType::PolygonZ`.
+    pub fn base_shape_type(&self) -> ShapeType {
+        match *self {
+            ShapeType::Null => ShapeType::Null,
+            ShapeType::Point | ShapeType::PointZ | ShapeType::PointM => ShapeType::Point,
+            ShapeType::PolyLine | ShapeType::PolyLineZ | ShapeType::PolyLineM => ShapeType::PolyLine,
+            ShapeType::Polygon | ShapeType::PolygonZ | ShapeType::PolygonM => ShapeType::Polygon,
+            ShapeType::MultiPoint | ShapeType::MultiPointZ | ShapeType::MultiPointM => ShapeType::MultiPoint,
+        }
+    }
+
+    /// Returns `true` for shape types with z values. Note that the Z types also contain
+    /// measures (m values).
+    pub fn has_z(&self) -> bool {
+        match *self {
+            ShapeType::PointZ | ShapeType::PolyLineZ | ShapeType::PolygonZ | ShapeType::MultiPointZ => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` for shape types with measures (m values).
+    pub fn has_m(&self) -> bool {
+        match *self {
+            ShapeType::PointM | ShapeType::PolyLineM | ShapeType::PolygonM | ShapeType::MultiPointM => true,
+            _ => self.has_z(),
+        }
+    }
+}
+
+impl fmt::Display for ShapeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A single shape, i.e. a point, multipoint, polyline or polygon and their Z and M variants.
+/// Polylines and polygons are made up of one or more parts, each a sequence of points, and
+/// `parts` holds the index of the first point of each part. Polygon outer rings are ordered
+/// clockwise and holes counter-clockwise. The `z_array` and `m_array` hold the z values and
+/// measures of the points for shape types that have them.
+#[derive(Default, Clone, Debug)]
+pub struct ShapefileGeometry {
+    pub shape_type: ShapeType,
+    pub num_parts: i32,
+    pub num_points: i32,
+    pub parts: Vec<i32>,
+    pub points: Vec<Point2D>,
+    pub z_array: Vec<f64>,
+    pub m_array: Vec<f64>,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub m_min: f64,
+    pub m_max: f64,
+}
+
+impl ShapefileGeometry {
+    pub fn new(shape_type: ShapeType) -> ShapefileGeometry {
+        ShapefileGeometry {
+            shape_type: shape_type,
+            x_min: f64::INFINITY,
+            x_max: f64::NEG_INFINITY,
+            y_min: f64::INFINITY,
+            y_max: f64::NEG_INFINITY,
+            z_min: f64::INFINITY,
+            z_max: f64::NEG_INFINITY,
+            m_min: f64::INFINITY,
+            m_max: f64::NEG_INFINITY,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a point to a point or multipoint shape.
+    pub fn add_point(&mut self, p: Point2D) {
+        self.update_xy_extent(&p);
+        self.points.push(p);
+        self.num_points += 1;
+    }
+
+    /// Adds a point, with its z value and measure, to a point or multipoint shape. The
+    /// z value is ignored for M shape types.
+    pub fn add_point_zm(&mut self, p: Point2D, z: f64, m: f64) {
+        self.add_point(p);
+        if self.shape_type.has_z() {
+            self.z_array.push(z);
+            self.update_z_extent(z);
+        }
+        self.m_array.push(m);
+        self.update_m_extent(m);
+    }
+
+    /// Adds a part (a line or a polygon ring) to a polyline or polygon shape.
+    pub fn add_part(&mut self, points: &[Point2D]) {
+        self.parts.push(self.num_points);
+        self.num_parts += 1;
+        for p in points {
+            self.add_point(*p);
+        }
+    }
+
+    /// Adds a part, with the z values and measures of its points, to a polyline or polygon
+    /// shape. The z values are ignored for M shape types.
+    pub fn add_part_zm(&mut self, points: &[Point2D], z_values: &[f64], m_values: &[f64]) {
+        self.parts.push(self.num_points);
+        self.num_parts += 1;
+        for i in 0..points.len() {
+            self.add_point_zm(points[i], z_values[i], m_values[i]);
+        }
+    }
+
+    /// Returns the index range, within `points`, of a part.
+    pub fn part_range(&self, part: usize) -> (usize, usize) {
+        let start = self.parts[part] as usize;
+        let end = if part < self.parts.len() - 1 {
+            self.parts[part + 1] as usize
+        } else {
+            self.num_points as usize
+        };
+        (start, end)
+    }
+
+    /// Returns the signed area of a part, which is positive for clockwise rings.
+    pub fn part_signed_area(&self, part: usize) -> f64 {
+        let (start, end) = self.part_range(part);
+        signed_area(&self.points[start..end])
+    }
+
+    /// Returns `true` if a part of a polygon is a hole, i.e. its points are ordered counter-clockwise.
+    pub fn is_hole(&self, part: usize) -> bool {
+        self.part_signed_area(part) < 0f64
+    }
+
+    /// Returns `true` if a location lies within a polygon shape, using the even-odd rule.
+    pub fn contains(&self, p: &Point2D) -> bool {
+        if self.shape_type.base_shape_type() != ShapeType::Polygon {
+            return false;
+        }
+        if p.x < self.x_min || p.x > self.x_max || p.y < self.y_min || p.y > self.y_max {
+            return false;
+        }
+        let mut inside = false;
+        for part in 0..self.parts.len() {
+            if self.part_contains(part, p) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// Returns `true` if a location lies within the ring formed by a part, regardless of
+    /// whether the part is a hole.
+    pub fn part_contains(&self, part: usize, p: &Point2D) -> bool {
+        let (start, end) = self.part_range(part);
+        if end - start < 3 {
+            return false;
+        }
+        let mut inside = false;
+        let mut j = end - 1;
+        for i in start..end {
+            let (pi, pj) = (self.points[i], self.points[j]);
+            if (pi.y > p.y) != (pj.y > p.y) && p.x < (pj.x - pi.x) * (p.y - pi.y) / (pj.y - pi.y) + pi.x {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+
+    /// Returns the length, in bytes, of the shape's record content within a Shapefile.
+    pub fn get_length(&self) -> i32 {
+        let n = self.num_points;
+        match self.shape_type {
+            ShapeType::Null => 4,
+            ShapeType::Point => 20,
+            ShapeType::PointM => 28,
+            ShapeType::PointZ => 36,
+            ShapeType::MultiPoint => 40 + 16 * n,
+            ShapeType::MultiPointM => 40 + 16 * n + 16 + 8 * n,
+            ShapeType::MultiPointZ => 40 + 16 * n + 2 * (16 + 8 * n),
+            ShapeType::PolyLine | ShapeType::Polygon => 44 + 4 * self.num_parts + 16 * n,
+            ShapeType::PolyLineM | ShapeType::PolygonM => 44 + 4 * self.num_parts + 16 * n + 16 + 8 * n,
+            ShapeType::PolyLineZ | ShapeType::PolygonZ => 44 + 4 * self.num_parts + 16 * n + 2 * (16 + 8 * n),
+        }
+    }
+
+    fn update_xy_extent(&mut self, p: &Point2D) {
+        if p.x < self.x_min { self.x_min = p.x; }
+        if p.x > self.x_max { self.x_max = p.x; }
+        if p.y < self.y_min { self.y_min = p.y; }
+        if p.y > self.y_max { self.y_max = p.y; }
+    }
+
+    fn update_z_extent(&mut self, z: f64) {
+        if z < self.z_min { self.z_min = z; }
+        if z > self.z_max { self.z_max = z; }
+    }
+
+    fn update_m_extent(&mut self, m: f64) {
+        if m < self.m_min { self.m_min = m; }
+        if m > self.m_max { self.m_max = m; }
+    }
+}
+
+/// Returns the signed area of a ring, which is positive for clockwise rings. The ring
+/// need not be closed, i.e. its last point need not repeat its first.
+pub fn signed_area(ring: &[Point2D]) -> f64 {
+    let n = ring.len();
+    let mut area = 0f64;
+    for i in 0..n {
+        let j = (i + 1) % n;
+        area += ring[j].x * ring[i].y - ring[i].x * ring[j].y;
+    }
+    area / 2f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64, clockwise: bool) -> Vec<Point2D> {
+        let mut points = vec![
+            Point2D::new(x, y),
+            Point2D::new(x, y + size),
+            Point2D::new(x + size, y + size),
+            Point2D::new(x + size, y),
+            Point2D::new(x, y),
+        ];
+        if !clockwise {
+            points.reverse();
+        }
+        points
+    }
+
+    /// A two-part polygon: a 10 x 10 square containing a 2 x 2 hole, and a separate 5 x 5 square.
+    fn polygon_with_hole() -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+        sfg.add_part(&square(0.0, 0.0, 10.0, true));
+        sfg.add_part(&square(4.0, 4.0, 2.0, false));
+        sfg.add_part(&square(20.0, 0.0, 5.0, true));
+        sfg
+    }
+
+    #[test]
+    fn test_shape_type_codes() {
+        let types = [
+            ShapeType::Null, ShapeType::Point, ShapeType::PolyLine, ShapeType::Polygon,
+            ShapeType::MultiPoint, ShapeType::PointZ, ShapeType::PolyLineZ, ShapeType::PolygonZ,
+            ShapeType::MultiPointZ, ShapeType::PointM, ShapeType::PolyLineM, ShapeType::PolygonM,
+            ShapeType::MultiPointM,
+        ];
+        for t in types.iter() {
+            assert_eq!(ShapeType::from_int(*t as i32), Some(*t));
+        }
+        assert_eq!(ShapeType::from_int(2), None);
+        assert_eq!(ShapeType::PolygonZ.base_shape_type(), ShapeType::Polygon);
+        assert_eq!(ShapeType::MultiPointM.base_shape_type(), ShapeType::MultiPoint);
+        assert!(ShapeType::PolyLineZ.has_z() && ShapeType::PolyLineZ.has_m());
+        assert!(!ShapeType::PolyLineM.has_z() && ShapeType::PolyLineM.has_m());
+        assert!(!ShapeType::PolyLine.has_z() && !ShapeType::PolyLine.has_m());
+    }
+
+    #[test]
+    fn test_parts() {
+        let sfg = polygon_with_hole();
+        assert_eq!(sfg.num_parts, 3);
+        assert_eq!(sfg.num_points, 15);
+        assert_eq!(sfg.parts, vec![0, 5, 10]);
+        assert_eq!(sfg.part_range(1), (5, 10));
+        assert_eq!(sfg.part_range(2), (10, 15));
+        assert_eq!((sfg.x_min, sfg.y_min, sfg.x_max, sfg.y_max), (0.0, 0.0, 25.0, 10.0));
+        assert_eq!(sfg.part_signed_area(0), 100.0);
+        assert_eq!(sfg.part_signed_area(1), -4.0);
+        // an unclosed ring has the same area as the closed ring
+        assert_eq!(signed_area(&square(4.0, 4.0, 2.0, false)[..4]), -4.0);
+        assert!(!sfg.is_hole(0));
+        assert!(sfg.is_hole(1));
+        assert!(!sfg.is_hole(2));
+    }
+
+    #[test]
+    fn test_contains() {
+        let sfg = polygon_with_hole();
+        assert!(sfg.contains(&Point2D::new(1.0, 1.0)));
+        assert!(!sfg.contains(&Point2D::new(5.0, 5.0))); // within the hole
+        assert!(sfg.contains(&Point2D::new(22.0, 2.0))); // within the second part
+        assert!(!sfg.contains(&Point2D::new(15.0, 5.0)));
+        assert!(!sfg.contains(&Point2D::new(-1.0, 5.0)));
+        assert!(sfg.part_contains(1, &Point2D::new(5.0, 5.0)));
+
+        // only polygons contain locations
+        let mut line = ShapefileGeometry::new(ShapeType::PolyLine);
+        line.add_part(&square(0.0, 0.0, 10.0, true));
+        assert!(!line.contains(&Point2D::new(1.0, 1.0)));
+    }
+
+    #[test]
+    fn test_z_and_m_values() {
+        let mut sfg = ShapefileGeometry::new(ShapeType::PolyLineZ);
+        sfg.add_part_zm(&[Point2D::new(0.0, 0.0), Point2D::new(3.0, 4.0)], &[10.0, 12.0], &[0.0, 5.0]);
+        assert_eq!(sfg.z_array, vec![10.0, 12.0]);
+        assert_eq!(sfg.m_array, vec![0.0, 5.0]);
+        assert_eq!((sfg.z_min, sfg.z_max, sfg.m_min, sfg.m_max), (10.0, 12.0, 0.0, 5.0));
+
+        // z values are ignored for M shapes
+        let mut sfg = ShapefileGeometry::new(ShapeType::PointM);
+        sfg.add_point_zm(Point2D::new(1.0, 2.0), 3.0, 4.0);
+        assert!(sfg.z_array.is_empty());
+        assert_eq!(sfg.m_array, vec![4.0]);
+    }
+
+    #[test]
+    fn test_get_length() {
+        let points = [Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(2.0, 0.0)];
+        let zm = [0.0; 3];
+        assert_eq!(ShapefileGeometry::new(ShapeType::Null).get_length(), 4);
+        assert_eq!(ShapefileGeometry::new(ShapeType::Point).get_length(), 20);
+        assert_eq!(ShapefileGeometry::new(ShapeType::PointM).get_length(), 28);
+        assert_eq!(ShapefileGeometry::new(ShapeType::PointZ).get_length(), 36);
+        let expected = [
+            (ShapeType::MultiPoint, 40 + 48),
+            (ShapeType::MultiPointM, 40 + 48 + 16 + 24),
+            (ShapeType::MultiPointZ, 40 + 48 + 2 * (16 + 24)),
+            (ShapeType::PolyLine, 44 + 4 + 48),
+            (ShapeType::PolygonM, 44 + 4 + 48 + 16 + 24),
+            (ShapeType::PolyLineZ, 44 + 4 + 48 + 2 * (16 + 24)),
+        ];
+        for &(shape_type, length) in expected.iter() {
+            let mut sfg = ShapefileGeometry::new(shape_type);
+            if shape_type.base_shape_type() == ShapeType::MultiPoint {
+                for p in points.iter() {
+                    sfg.add_point_zm(*p, 0.0, 0.0);
+                }
+            } else {
+                sfg.add_part_zm(&points, &zm, &zm);
+            }
+            assert_eq!(sfg.get_length(), length, "{}", shape_type);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// private sub-module defined in other files
+mod attributes;
+mod geojson;
+mod geometry;
+mod rasterize;
+mod shapefile;
+
+// exports identifiers from private sub-modules in the current module namespace
+pub use self::attributes::csv_value;
+pub use self::attributes::AttributeField;
+pub use self::attributes::DateData;
+pub use self::attributes::FieldData;
+pub use self::attributes::FieldDataType;
+pub use self::attributes::ShapefileAttributes;
+pub use self::geometry::signed_area;
+pub use self::geometry::ShapeType;
+pub use self::geometry::ShapefileGeometry;
+pub use self::rasterize::fill_polygon;
+pub use self::rasterize::trace_segment;
+pub use self::rasterize::Grid;
+pub use self::shapefile::is_vector_file;
+pub use self::shapefile::Shapefile;
+pub use self::shapefile::ShapefileHeader;
@@ -0,0 +1,214 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 22, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+
+use std::f64;
+use raster::RasterConfigs;
+use structures::Point2D;
+use vector::geometry::ShapefileGeometry;
+
+/// The geometry of a raster grid onto which vector features are rasterized, used to convert
+/// between map and grid coordinates. Grid coordinates are fractional column and row numbers,
+/// with (0, 0) at the top-left corner.
+#[derive(Clone, Copy, Debug)]
+pub struct Grid {
+    pub rows: isize,
+    pub columns: isize,
+    pub west: f64,
+    pub north: f64,
+    pub resolution_x: f64,
+    pub resolution_y: f64,
+}
+
+impl Grid {
+    pub fn new(configs: &RasterConfigs) -> Grid {
+        Grid {
+            rows: configs.rows as isize,
+            columns: configs.columns as isize,
+            west: configs.west,
+            north: configs.north,
+            resolution_x: configs.resolution_x,
+            resolution_y: configs.resolution_y,
+        }
+    }
+
+    pub fn get_column(&self, x: f64) -> f64 {
+        (x - self.west) / self.resolution_x
+    }
+
+    pub fn get_row(&self, y: f64) -> f64 {
+        (self.north - y) / self.resolution_y
+    }
+}
+
+/// Visits the cells along a line segment. With `all_touched`, every cell that the segment
+/// passes through is visited (a 4-connected path); otherwise the cells form an 8-connected
+/// path between the cells containing the end points.
+pub fn trace_segment<F: FnMut(isize, isize)>(grid: &Grid, p1: &Point2D, p2: &Point2D, all_touched: bool, visit: &mut F) {
+    let (gx1, gy1) = (grid.get_column(p1.x), grid.get_row(p1.y));
+    let (gx2, gy2) = (grid.get_column(p2.x), grid.get_row(p2.y));
+    let (mut col, mut row) = (gx1.floor() as isize, gy1.floor() as isize);
+    let (end_col, end_row) = (gx2.floor() as isize, gy2.floor() as isize);
+    let step_col = if end_col > col { 1 } else { -1 };
+    let step_row = if end_row > row { 1 } else { -1 };
+    let num_col_steps = (end_col - col).abs();
+    let num_row_steps = (end_row - row).abs();
+
+    if all_touched {
+        // step to whichever cell boundary, vertical or horizontal, the segment crosses first
+        let (dx, dy) = (gx2 - gx1, gy2 - gy1);
+        let t_delta_col = if dx != 0f64 { 1f64 / dx.abs() } else { f64::INFINITY };
+        let t_delta_row = if dy != 0f64 { 1f64 / dy.abs() } else { f64::INFINITY };
+        let mut t_max_col = if dx > 0f64 {
+            (col as f64 + 1f64 - gx1) / dx
+        } else if dx < 0f64 {
+            (gx1 - col as f64) / -dx
+        } else {
+            f64::INFINITY
+        };
+        let mut t_max_row = if dy > 0f64 {
+            (row as f64 + 1f64 - gy1) / dy
+        } else if dy < 0f64 {
+            (gy1 - row as f64) / -dy
+        } else {
+            f64::INFINITY
+        };
+        let (mut col_steps, mut row_steps) = (0, 0);
+        visit(row, col);
+        while col_steps < num_col_steps || row_steps < num_row_steps {
+            if (t_max_col < t_max_row && col_steps < num_col_steps) || row_steps == num_row_steps {
+                t_max_col += t_delta_col;
+                col += step_col;
+                col_steps += 1;
+            } else {
+                t_max_row += t_delta_row;
+                row += step_row;
+                row_steps += 1;
+            }
+            visit(row, col);
+        }
+    } else {
+        // Bresenham's line algorithm
+        let (dx, dy) = (num_col_steps, -num_row_steps);
+        let mut err = dx + dy;
+        loop {
+            visit(row, col);
+            if col == end_col && row == end_row {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                col += step_col;
+            }
+            if e2 <= dx {
+                err += dx;
+                row += step_row;
+            }
+        }
+    }
+}
+
+/// Visits the cells with centres inside of a polygon, using a scan-line fill and the
+/// even-odd rule, such that holes are excluded.
+pub fn fill_polygon<F: FnMut(isize, isize)>(grid: &Grid, polygon: &ShapefileGeometry, visit: &mut F) {
+    let start_row = (grid.get_row(polygon.y_max) - 0.5).floor().max(0f64) as isize;
+    let end_row = (grid.get_row(polygon.y_min) - 0.5).ceil().min((grid.rows - 1) as f64) as isize;
+    let mut crossings = vec![];
+    for row in start_row..end_row + 1 {
+        // the crossings are found in map coordinates, such that cell centres lying on the
+        // polygon's edges are treated as they are by `ShapefileGeometry::contains`
+        let y = grid.north - (row as f64 + 0.5) * grid.resolution_y;
+        crossings.clear();
+        for part in 0..polygon.num_parts as usize {
+            let (start, end) = polygon.part_range(part);
+            if end - start < 3 {
+                continue;
+            }
+            let mut j = end - 1;
+            for i in start..end {
+                let (p, q) = (polygon.points[i], polygon.points[j]);
+                if (p.y > y) != (q.y > y) {
+                    crossings.push(grid.get_column(p.x + (y - p.y) * (q.x - p.x) / (q.y - p.y)));
+                }
+                j = i;
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for k in 0..crossings.len() / 2 {
+            // the columns with centres between a pair of crossings
+            let first_col = (crossings[2 * k] - 0.5).ceil().max(0f64) as isize;
+            let last_col = ((crossings[2 * k + 1] - 0.5).ceil() as isize - 1).min(grid.columns - 1);
+            for col in first_col..last_col + 1 {
+                visit(row, col);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vector::geometry::ShapeType;
+
+    fn grid() -> Grid {
+        Grid { rows: 10, columns: 10, west: 0.0, north: 10.0, resolution_x: 1.0, resolution_y: 1.0 }
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        // a square covering cells (1..8, 1..8) with a hole covering cells (4..5, 4..5)
+        let mut polygon = ShapefileGeometry::new(ShapeType::Polygon);
+        polygon.add_part(&[Point2D::new(1.0, 1.0), Point2D::new(1.0, 9.0), Point2D::new(9.0, 9.0), Point2D::new(9.0, 1.0), Point2D::new(1.0, 1.0)]);
+        polygon.add_part(&[Point2D::new(4.0, 4.0), Point2D::new(6.0, 4.0), Point2D::new(6.0, 6.0), Point2D::new(4.0, 6.0), Point2D::new(4.0, 4.0)]);
+        let mut cells = vec![];
+        fill_polygon(&grid(), &polygon, &mut |row, col| cells.push((row, col)));
+        assert_eq!(cells.len(), 64 - 4);
+        assert!(cells.contains(&(1, 1)) && cells.contains(&(8, 8)));
+        assert!(!cells.contains(&(0, 0)) && !cells.contains(&(9, 9)));
+        assert!(!cells.contains(&(4, 4)) && !cells.contains(&(5, 5)));
+    }
+
+    #[test]
+    fn test_fill_polygon_edges() {
+        // cell centres on the edges of a polygon are treated as they are by `contains`
+        let mut polygon = ShapefileGeometry::new(ShapeType::Polygon);
+        polygon.add_part(&[Point2D::new(0.5, 0.5), Point2D::new(0.5, 9.5), Point2D::new(9.5, 9.5), Point2D::new(9.5, 0.5), Point2D::new(0.5, 0.5)]);
+        let g = grid();
+        let mut cells = vec![];
+        fill_polygon(&g, &polygon, &mut |row, col| cells.push((row, col)));
+        for row in 0..g.rows {
+            for col in 0..g.columns {
+                let p = Point2D::new(g.west + (col as f64 + 0.5) * g.resolution_x, g.north - (row as f64 + 0.5) * g.resolution_y);
+                assert_eq!(cells.contains(&(row, col)), polygon.contains(&p), "cell ({}, {})", row, col);
+            }
+        }
+        assert_eq!(cells.len(), 81);
+    }
+
+    #[test]
+    fn test_fill_polygon_cell_centres() {
+        // a triangle covering the centre of cell (0, 0) but not that of its neighbours
+        let mut polygon = ShapefileGeometry::new(ShapeType::Polygon);
+        polygon.add_part(&[Point2D::new(0.0, 9.0), Point2D::new(0.0, 10.0), Point2D::new(1.2, 10.0), Point2D::new(0.0, 9.0)]);
+        let mut cells = vec![];
+        fill_polygon(&grid(), &polygon, &mut |row, col| cells.push((row, col)));
+        assert_eq!(cells, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_trace_segment() {
+        let mut cells = vec![];
+        trace_segment(&grid(), &Point2D::new(0.5, 9.5), &Point2D::new(3.5, 6.5), false, &mut |row, col| cells.push((row, col)));
+        assert_eq!(cells, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+
+        // all touched cells form a 4-connected path
+        let mut cells = vec![];
+        trace_segment(&grid(), &Point2D::new(0.5, 9.5), &Point2D::new(2.5, 8.7), true, &mut |row, col| cells.push((row, col)));
+        assert_eq!(cells, vec![(0, 0), (0, 1), (1, 1), (1, 2)]);
+    }
+}
@@ -0,0 +1,817 @@
+/*
+This code is part of the WhiteboxTools geospatial analysis library.
+Authors: Dr. John Lindsay
+Created: February 2, 2018
+Last Modified: February 22, 2018
+License: MIT
+*/
+
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use std::f64;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::Path;
+use io_utils::{ByteOrderReader, Endianness};
+use structures::Point2D;
+use vector::attributes::{FieldData, ShapefileAttributes};
+use vector::geojson::{read_geojson, write_geojson};
+use vector::geometry::{ShapefileGeometry, ShapeType};
+
+/// The 100-byte header shared by the main (.shp) and index (.shx) files of a Shapefile.
+#[derive(Default, Clone, Debug)]
+pub struct ShapefileHeader {
+    pub file_code: i32, // BigEndian; value is 9994
+    pub file_length: i32, // BigEndian; in 16-bit words, including the header
+    pub version: i32, // LittleEndian; value is 1000
+    pub shape_type: ShapeType, // LittleEndian
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub m_min: f64,
+    pub m_max: f64,
+}
+
+impl fmt::Display for ShapefileHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = format!("\nFile Code: {}", self.file_code);
+        s = s + &format!("\nFile Length (words): {}", self.file_length);
+        s = s + &format!("\nVersion: {}", self.version);
+        s = s + &format!("\nShape Type: {}", self.shape_type);
+        s = s + &format!("\nX Min: {}", self.x_min);
+        s = s + &format!("\nY Min: {}", self.y_min);
+        s = s + &format!("\nX Max: {}", self.x_max);
+        s = s + &format!("\nY Max: {}", self.y_max);
+        s = s + &format!("\nZ Min: {}", self.z_min);
+        s = s + &format!("\nZ Max: {}", self.z_max);
+        s = s + &format!("\nM Min: {}", self.m_min);
+        s = s + &format!("\nM Max: {}", self.m_max);
+        write!(f, "{}", s)
+    }
+}
+
+/// Returns `true` if a file name has the extension of a supported vector format, i.e.
+/// a Shapefile (.shp) or GeoJSON (.geojson or .json), which allows tools to accept
+/// either raster or vector data through the same parameter.
+pub fn is_vector_file<'a>(file_name: &'a str) -> bool {
+    let lc = file_name.to_lowercase();
+    lc.ends_with(".shp") || lc.ends_with(".geojson") || lc.ends_with(".json")
+}
+
+/// An ESRI Shapefile, made up of the main (.shp) file holding the geometries, the
+/// index (.shx) file, the attribute table (.dbf) and, optionally, the projection (.prj).
+/// Only the name of the .shp file needs to be specified; the other files share its name.
+/// Files with a .geojson or .json extension are instead read and written as GeoJSON,
+/// which allows tools to accept either format through the same interface.
+///
+/// A Shapefile is either opened for reading (`file_mode` "r") or created for writing ("w"):
+///
+/// ```ignore
+/// let mut output = Shapefile::new(&output_file, "w")?;
+/// output.header.shape_type = ShapeType::Polygon;
+/// output.attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 7, 0));
+/// let mut sfg = ShapefileGeometry::new(ShapeType::Polygon);
+/// sfg.add_part(&points);
+/// output.add_record(sfg);
+/// output.attributes.add_record(vec![FieldData::Int(1)]);
+/// output.write()?;
+/// ```
+#[derive(Default, Clone)]
+pub struct Shapefile {
+    pub file_name: String,
+    pub file_mode: String,
+    pub header: ShapefileHeader,
+    pub num_records: usize,
+    pub records: Vec<ShapefileGeometry>,
+    pub attributes: ShapefileAttributes,
+    pub projection: String,
+}
+
+impl Shapefile {
+    pub fn new<'a>(file_name: &'a str, file_mode: &'a str) -> Result<Shapefile, Error> {
+        let mut sf = Shapefile { file_name: file_name.to_string(), ..Default::default() };
+        sf.file_mode = file_mode.to_lowercase();
+        if sf.file_mode == "r" {
+            sf.read()?;
+        } else {
+            sf.file_mode = "w".to_string();
+            sf.header.file_code = 9994;
+            sf.header.version = 1000;
+        }
+        Ok(sf)
+    }
+
+    /// Returns a new Shapefile, for writing, with the shape type and projection of an
+    /// existing Shapefile. The attribute fields are copied, but not the records.
+    pub fn initialize_using_file<'a>(file_name: &'a str, input: &'a Shapefile, shape_type: ShapeType, copy_fields: bool) -> Result<Shapefile, Error> {
+        let mut output = Shapefile::new(file_name, "w")?;
+        output.header.shape_type = shape_type;
+        output.projection = input.projection.clone();
+        if copy_fields {
+            for field in &input.attributes.fields {
+                output.attributes.add_field(field);
+            }
+        }
+        Ok(output)
+    }
+
+    pub fn get_record(&self, index: usize) -> &ShapefileGeometry {
+        &self.records[index]
+    }
+
+    /// Adds a shape to the file. Its shape type must match the type in the file header.
+    pub fn add_record(&mut self, geometry: ShapefileGeometry) {
+        if self.file_mode == "r" {
+            panic!("Error adding record: the Shapefile was opened in read-only mode.");
+        }
+        if geometry.shape_type != self.header.shape_type && geometry.shape_type != ShapeType::Null {
+            panic!("Error adding record: the shape type ({}) does not match the Shapefile's shape type ({}).", geometry.shape_type, self.header.shape_type);
+        }
+        self.records.push(geometry);
+        self.num_records += 1;
+    }
+
+    fn get_sidecar_file_name<'a>(&self, extension: &'a str) -> String {
+        Path::new(&self.file_name).with_extension(extension).to_str().unwrap_or("").to_string()
+    }
+
+    /// Returns `true` if the file is GeoJSON rather than a Shapefile, based on its extension.
+    pub fn is_geojson(&self) -> bool {
+        let lc = self.file_name.to_lowercase();
+        lc.ends_with(".geojson") || lc.ends_with(".json")
+    }
+
+    fn read(&mut self) -> Result<(), Error> {
+        if self.is_geojson() {
+            let (shape_type, records, attributes) = read_geojson(&self.file_name)?;
+            self.header.file_code = 9994;
+            self.header.version = 1000;
+            self.header.shape_type = shape_type;
+            self.records = records;
+            self.num_records = self.records.len();
+            self.attributes = attributes;
+            self.update_header_extent();
+            return Ok(());
+        }
+
+        let mut f = File::open(&self.file_name)?;
+        let mut buffer = vec![];
+        f.read_to_end(&mut buffer)?;
+        if buffer.len() < 100 {
+            return Err(Error::new(ErrorKind::InvalidData, "The Shapefile (.shp) is too short to contain a header."));
+        }
+        let mut bor = ByteOrderReader::new(buffer, Endianness::BigEndian);
+
+        // the file code and length are big-endian and the remainder of the header is little-endian
+        self.header.file_code = bor.read_i32();
+        if self.header.file_code != 9994 {
+            return Err(Error::new(ErrorKind::InvalidData, "The file does not appear to be a valid Shapefile; the file code is not 9994."));
+        }
+        bor.seek(24);
+        self.header.file_length = bor.read_i32();
+        bor.byte_order = Endianness::LittleEndian;
+        self.header.version = bor.read_i32();
+        let st = bor.read_i32();
+        self.header.shape_type = match ShapeType::from_int(st) {
+            Some(t) => t,
+            None => return Err(Error::new(ErrorKind::InvalidData, format!("Unrecognized shape type ({}).", st))),
+        };
+        self.header.x_min = bor.read_f64();
+        self.header.y_min = bor.read_f64();
+        self.header.x_max = bor.read_f64();
+        self.header.y_max = bor.read_f64();
+        self.header.z_min = bor.read_f64();
+        self.header.z_max = bor.read_f64();
+        self.header.m_min = bor.read_f64();
+        self.header.m_max = bor.read_f64();
+
+        // read the records
+        let file_end = (self.header.file_length as usize * 2).min(bor.len());
+        let mut pos = 100;
+        while pos + 8 <= file_end {
+            bor.seek(pos);
+            bor.byte_order = Endianness::BigEndian;
+            let _record_number = bor.read_i32();
+            let content_length = bor.read_i32();
+            bor.byte_order = Endianness::LittleEndian;
+            let content_start = pos + 8;
+            if content_length < 2 || content_start + content_length as usize * 2 > bor.len() {
+                return Err(Error::new(ErrorKind::InvalidData, "The Shapefile (.shp) is shorter than its records indicate."));
+            }
+            let content_length = content_length as usize * 2;
+            let st = bor.read_i32();
+            let shape_type = ShapeType::from_int(st).unwrap_or(ShapeType::Null);
+            let malformed = || Error::new(ErrorKind::InvalidData, format!("Record {} of the Shapefile (.shp) is malformed.", self.records.len() + 1));
+            let mut sfg = ShapefileGeometry::new(shape_type);
+            match shape_type.base_shape_type() {
+                ShapeType::Null => {},
+                ShapeType::Point => {
+                    let size = if shape_type.has_z() || shape_type.has_m() { 28 } else { 20 };
+                    if size > content_length {
+                        return Err(malformed());
+                    }
+                    let p = Point2D::new(bor.read_f64(), bor.read_f64());
+                    if shape_type.has_z() {
+                        let z = bor.read_f64();
+                        // the measure is optional
+                        let m = if content_length >= 36 { no_data_measure(bor.read_f64()) } else { f64::NAN };
+                        sfg.add_point_zm(p, z, m);
+                    } else if shape_type.has_m() {
+                        let m = no_data_measure(bor.read_f64());
+                        sfg.add_point_zm(p, 0f64, m);
+                    } else {
+                        sfg.add_point(p);
+                    }
+                },
+                ShapeType::MultiPoint | ShapeType::PolyLine | ShapeType::Polygon => {
+                    let has_parts = shape_type.base_shape_type() != ShapeType::MultiPoint;
+                    let mut size = if has_parts { 44 } else { 40 };
+                    if size > content_length {
+                        return Err(malformed());
+                    }
+                    bor.seek(content_start + 36); // skip the shape type and bounding box
+                    let num_parts = if has_parts { bor.read_i32() } else { 0 };
+                    let num_points = bor.read_i32();
+                    if num_parts < 0 || num_points < 0 {
+                        return Err(malformed());
+                    }
+                    let (num_parts, num_points) = (num_parts as usize, num_points as usize);
+                    size += 4 * num_parts + 16 * num_points;
+                    if shape_type.has_z() {
+                        size += 16 + 8 * num_points;
+                    }
+                    if size > content_length {
+                        return Err(malformed());
+                    }
+                    let mut parts = Vec::with_capacity(num_parts);
+                    for a in 0..num_parts {
+                        let start = bor.read_i32();
+                        // part offsets must increase and each part must hold at least one point
+                        if start < 0 || start as usize >= num_points || (a > 0 && start as usize <= parts[a - 1]) {
+                            return Err(malformed());
+                        }
+                        parts.push(start as usize);
+                    }
+                    let mut points = Vec::with_capacity(num_points);
+                    for _ in 0..num_points {
+                        points.push(Point2D::new(bor.read_f64(), bor.read_f64()));
+                    }
+                    let mut z_values = vec![0f64; num_points];
+                    let mut m_values = vec![f64::NAN; num_points];
+                    if shape_type.has_z() {
+                        bor.seek(bor.pos + 16); // z range
+                        for i in 0..num_points {
+                            z_values[i] = bor.read_f64();
+                        }
+                    }
+                    // the measures are optional, both in M and Z shapes
+                    if shape_type.has_m() && bor.pos + 16 + 8 * num_points <= content_start + content_length {
+                        bor.seek(bor.pos + 16); // m range
+                        for i in 0..num_points {
+                            m_values[i] = no_data_measure(bor.read_f64());
+                        }
+                    }
+
+                    if has_parts {
+                        for a in 0..num_parts {
+                            let start = parts[a];
+                            let end = if a < num_parts - 1 { parts[a + 1] } else { num_points };
+                            if shape_type.has_m() {
+                                sfg.add_part_zm(&points[start..end], &z_values[start..end], &m_values[start..end]);
+                            } else {
+                                sfg.add_part(&points[start..end]);
+                            }
+                        }
+                    } else {
+                        for i in 0..num_points {
+                            if shape_type.has_m() {
+                                sfg.add_point_zm(points[i], z_values[i], m_values[i]);
+                            } else {
+                                sfg.add_point(points[i]);
+                            }
+                        }
+                    }
+                },
+                _ => unreachable!(),
+            }
+            self.records.push(sfg);
+            pos = content_start + content_length;
+        }
+        self.num_records = self.records.len();
+
+        // read the attribute table
+        let dbf_file = self.get_sidecar_file_name("dbf");
+        if Path::new(&dbf_file).exists() {
+            self.attributes = ShapefileAttributes::read(&dbf_file)?;
+        }
+        // a missing or short table is padded with null records, so that each shape has a record
+        let num_fields = self.attributes.get_num_fields();
+        while self.attributes.get_num_records() < self.num_records {
+            self.attributes.add_record(vec![FieldData::Null; num_fields]);
+        }
+
+        // read the projection, if there is one
+        let prj_file = self.get_sidecar_file_name("prj");
+        if Path::new(&prj_file).exists() {
+            self.projection = fs::read_to_string(&prj_file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the .shp, .shx and .dbf files, and the .prj file where there is a projection.
+    pub fn write(&mut self) -> Result<(), Error> {
+        if self.file_mode == "r" {
+            return Err(Error::new(ErrorKind::Other, "The file was opened in read-only mode"));
+        }
+        if self.attributes.get_num_fields() > 0 && self.attributes.get_num_records() != self.num_records {
+            return Err(Error::new(ErrorKind::Other, "The number of attribute records does not match the number of shapes."));
+        }
+        if self.is_geojson() {
+            return write_geojson(&self.file_name, &self.records, &self.attributes);
+        }
+
+        self.update_header_extent();
+        let mut file_length = 50i32; // in 16-bit words
+        for rec in &self.records {
+            file_length += 4 + rec.get_length() / 2;
+        }
+        self.header.file_length = file_length;
+
+        // the main file
+        let f = File::create(&self.file_name)?;
+        let mut writer = BufWriter::new(f);
+        self.write_header(&mut writer, file_length)?;
+        for (i, rec) in self.records.iter().enumerate() {
+            writer.write_i32::<BigEndian>(i as i32 + 1)?;
+            writer.write_i32::<BigEndian>(rec.get_length() / 2)?;
+            write_geometry(&mut writer, rec)?;
+        }
+        writer.flush()?;
+
+        // the index file
+        let f = File::create(&self.get_sidecar_file_name("shx"))?;
+        let mut writer = BufWriter::new(f);
+        self.write_header(&mut writer, 50 + 4 * self.records.len() as i32)?;
+        let mut offset = 50i32;
+        for rec in &self.records {
+            writer.write_i32::<BigEndian>(offset)?;
+            writer.write_i32::<BigEndian>(rec.get_length() / 2)?;
+            offset += 4 + rec.get_length() / 2;
+        }
+        writer.flush()?;
+
+        // the attribute table; a Shapefile requires one, even if there are no fields
+        self.attributes.write(&self.get_sidecar_file_name("dbf"))?;
+
+        if !self.projection.is_empty() {
+            let mut f = File::create(&self.get_sidecar_file_name("prj"))?;
+            f.write_all(self.projection.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn update_header_extent(&mut self) {
+        self.header.x_min = f64::INFINITY;
+        self.header.y_min = f64::INFINITY;
+        self.header.z_min = f64::INFINITY;
+        self.header.m_min = f64::INFINITY;
+        self.header.x_max = f64::NEG_INFINITY;
+        self.header.y_max = f64::NEG_INFINITY;
+        self.header.z_max = f64::NEG_INFINITY;
+        self.header.m_max = f64::NEG_INFINITY;
+        for rec in &self.records {
+            if rec.num_points == 0 {
+                continue;
+            }
+            self.header.x_min = self.header.x_min.min(rec.x_min);
+            self.header.y_min = self.header.y_min.min(rec.y_min);
+            self.header.x_max = self.header.x_max.max(rec.x_max);
+            self.header.y_max = self.header.y_max.max(rec.y_max);
+            if rec.shape_type.has_z() {
+                self.header.z_min = self.header.z_min.min(rec.z_min);
+                self.header.z_max = self.header.z_max.max(rec.z_max);
+            }
+            if rec.shape_type.has_m() && rec.m_min.is_finite() {
+                self.header.m_min = self.header.m_min.min(rec.m_min);
+                self.header.m_max = self.header.m_max.max(rec.m_max);
+            }
+        }
+        if self.header.x_min > self.header.x_max {
+            // there are no non-null shapes
+            self.header.x_min = 0f64;
+            self.header.y_min = 0f64;
+            self.header.x_max = 0f64;
+            self.header.y_max = 0f64;
+        }
+        if self.header.z_min > self.header.z_max {
+            self.header.z_min = 0f64;
+            self.header.z_max = 0f64;
+        }
+        if self.header.m_min > self.header.m_max {
+            self.header.m_min = 0f64;
+            self.header.m_max = 0f64;
+        }
+    }
+
+    fn write_header<W: Write>(&self, writer: &mut W, file_length: i32) -> Result<(), Error> {
+        writer.write_i32::<BigEndian>(9994)?;
+        for _ in 0..5 {
+            writer.write_i32::<BigEndian>(0)?;
+        }
+        writer.write_i32::<BigEndian>(file_length)?;
+        writer.write_i32::<LittleEndian>(1000)?;
+        writer.write_i32::<LittleEndian>(self.header.shape_type as i32)?;
+        writer.write_f64::<LittleEndian>(self.header.x_min)?;
+        writer.write_f64::<LittleEndian>(self.header.y_min)?;
+        writer.write_f64::<LittleEndian>(self.header.x_max)?;
+        writer.write_f64::<LittleEndian>(self.header.y_max)?;
+        writer.write_f64::<LittleEndian>(self.header.z_min)?;
+        writer.write_f64::<LittleEndian>(self.header.z_max)?;
+        writer.write_f64::<LittleEndian>(self.header.m_min)?;
+        writer.write_f64::<LittleEndian>(self.header.m_max)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Shapefile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = format!("File Name: {}", self.file_name);
+        s = s + &format!("{}", self.header);
+        s = s + &format!("\nNum. of Records: {}", self.num_records);
+        s = s + &format!("\nNum. of Attribute Fields: {}", self.attributes.get_num_fields());
+        write!(f, "{}", s)
+    }
+}
+
+/// Measures less than -10^38 indicate 'no data' and are read as NaN.
+fn no_data_measure(m: f64) -> f64 {
+    if m < -1e38f64 { f64::NAN } else { m }
+}
+
+fn write_geometry<W: Write>(writer: &mut W, rec: &ShapefileGeometry) -> Result<(), Error> {
+    writer.write_i32::<LittleEndian>(rec.shape_type as i32)?;
+    // shapes without a measure use a value less than -10^38 to indicate 'no data'
+    let m_value = |m: f64| if m.is_finite() { m } else { -1e39f64 };
+    match rec.shape_type.base_shape_type() {
+        ShapeType::Null => {},
+        ShapeType::Point => {
+            writer.write_f64::<LittleEndian>(rec.points[0].x)?;
+            writer.write_f64::<LittleEndian>(rec.points[0].y)?;
+            if rec.shape_type.has_z() {
+                writer.write_f64::<LittleEndian>(rec.z_array[0])?;
+            }
+            if rec.shape_type.has_m() {
+                writer.write_f64::<LittleEndian>(m_value(rec.m_array[0]))?;
+            }
+        },
+        _ => {
+            writer.write_f64::<LittleEndian>(rec.x_min)?;
+            writer.write_f64::<LittleEndian>(rec.y_min)?;
+            writer.write_f64::<LittleEndian>(rec.x_max)?;
+            writer.write_f64::<LittleEndian>(rec.y_max)?;
+            if rec.shape_type.base_shape_type() != ShapeType::MultiPoint {
+                writer.write_i32::<LittleEndian>(rec.num_parts)?;
+            }
+            writer.write_i32::<LittleEndian>(rec.num_points)?;
+            if rec.shape_type.base_shape_type() != ShapeType::MultiPoint {
+                for part in &rec.parts {
+                    writer.write_i32::<LittleEndian>(*part)?;
+                }
+            }
+            for p in &rec.points {
+                writer.write_f64::<LittleEndian>(p.x)?;
+                writer.write_f64::<LittleEndian>(p.y)?;
+            }
+            if rec.shape_type.has_z() {
+                writer.write_f64::<LittleEndian>(rec.z_min)?;
+                writer.write_f64::<LittleEndian>(rec.z_max)?;
+                for z in &rec.z_array {
+                    writer.write_f64::<LittleEndian>(*z)?;
+                }
+            }
+            if rec.shape_type.has_m() {
+                let (m_min, m_max) = if rec.m_min.is_finite() { (rec.m_min, rec.m_max) } else { (-1e39f64, -1e39f64) };
+                writer.write_f64::<LittleEndian>(m_min)?;
+                writer.write_f64::<LittleEndian>(m_max)?;
+                for m in &rec.m_array {
+                    writer.write_f64::<LittleEndian>(m_value(*m))?;
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use vector::attributes::{AttributeField, FieldData, FieldDataType};
+
+    fn temp_file_name(name: &str) -> String {
+        env::temp_dir().join(format!("wbt_shapefile_{}_{}", process::id(), name)).to_str().unwrap().to_string()
+    }
+
+    fn remove_files(file_name: &str) {
+        for ext in ["shp", "shx", "dbf", "prj"].iter() {
+            let _ = fs::remove_file(Path::new(file_name).with_extension(ext));
+        }
+    }
+
+    fn write_header(bytes: &mut Vec<u8>, file_length: i32, shape_type: ShapeType, extent: [f64; 4]) {
+        bytes.write_i32::<BigEndian>(9994).unwrap();
+        for _ in 0..5 {
+            bytes.write_i32::<BigEndian>(0).unwrap();
+        }
+        bytes.write_i32::<BigEndian>(file_length).unwrap();
+        bytes.write_i32::<LittleEndian>(1000).unwrap();
+        bytes.write_i32::<LittleEndian>(shape_type as i32).unwrap();
+        for v in extent.iter() {
+            bytes.write_f64::<LittleEndian>(*v).unwrap();
+        }
+        for _ in 0..4 {
+            bytes.write_f64::<LittleEndian>(0f64).unwrap(); // z and m ranges
+        }
+    }
+
+    /// The rings of a polygon with a hole, the hole ordered counter-clockwise.
+    fn rings() -> Vec<Vec<(f64, f64)>> {
+        vec![
+            vec![(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)],
+            vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0), (4.0, 4.0)],
+        ]
+    }
+
+    /// Writes a polygon Shapefile, byte by byte, holding a null record followed by a
+    /// polygon with a hole, along with its index and a table with a single FID field.
+    fn write_hand_built_polygon_file(file_name: &str) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let rings = rings();
+        let num_points: usize = rings.iter().map(|r| r.len()).sum();
+
+        let mut null_content = vec![];
+        null_content.write_i32::<LittleEndian>(0).unwrap();
+        let mut poly_content = vec![];
+        poly_content.write_i32::<LittleEndian>(5).unwrap();
+        for v in [0.0, 0.0, 10.0, 10.0].iter() {
+            poly_content.write_f64::<LittleEndian>(*v).unwrap();
+        }
+        poly_content.write_i32::<LittleEndian>(rings.len() as i32).unwrap();
+        poly_content.write_i32::<LittleEndian>(num_points as i32).unwrap();
+        poly_content.write_i32::<LittleEndian>(0).unwrap();
+        poly_content.write_i32::<LittleEndian>(rings[0].len() as i32).unwrap();
+        for ring in &rings {
+            for &(x, y) in ring {
+                poly_content.write_f64::<LittleEndian>(x).unwrap();
+                poly_content.write_f64::<LittleEndian>(y).unwrap();
+            }
+        }
+
+        let contents = vec![null_content, poly_content];
+        let file_length = 50 + contents.iter().map(|c| 4 + c.len() as i32 / 2).sum::<i32>();
+        let mut shp = vec![];
+        write_header(&mut shp, file_length, ShapeType::Polygon, [0.0, 0.0, 10.0, 10.0]);
+        let mut shx = vec![];
+        write_header(&mut shx, 50 + 4 * contents.len() as i32, ShapeType::Polygon, [0.0, 0.0, 10.0, 10.0]);
+        let mut offset = 50;
+        for (i, c) in contents.iter().enumerate() {
+            shp.write_i32::<BigEndian>(i as i32 + 1).unwrap();
+            shp.write_i32::<BigEndian>(c.len() as i32 / 2).unwrap();
+            shp.extend_from_slice(c);
+            shx.write_i32::<BigEndian>(offset).unwrap();
+            shx.write_i32::<BigEndian>(c.len() as i32 / 2).unwrap();
+            offset += 4 + c.len() as i32 / 2;
+        }
+
+        let mut dbf = vec![0x03u8, 118, 2, 22];
+        dbf.write_u32::<LittleEndian>(2).unwrap();
+        dbf.write_u16::<LittleEndian>(32 + 32 + 1).unwrap();
+        dbf.write_u16::<LittleEndian>(1 + 4).unwrap();
+        dbf.extend_from_slice(&[0u8; 20]);
+        let mut descriptor = vec![0u8; 32];
+        descriptor[..3].copy_from_slice(b"FID");
+        descriptor[11] = b'N';
+        descriptor[16] = 4;
+        dbf.extend_from_slice(&descriptor);
+        dbf.push(0x0D);
+        dbf.extend_from_slice(b"    1    2");
+        dbf.push(0x1A);
+
+        fs::write(file_name, &shp).unwrap();
+        fs::write(Path::new(file_name).with_extension("shx"), &shx).unwrap();
+        fs::write(Path::new(file_name).with_extension("dbf"), &dbf).unwrap();
+        (shp, shx, dbf)
+    }
+
+    #[test]
+    fn test_read_hand_built_file() {
+        let file_name = temp_file_name("hand_built.shp");
+        write_hand_built_polygon_file(&file_name);
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        remove_files(&file_name);
+
+        assert_eq!(input.header.shape_type, ShapeType::Polygon);
+        assert_eq!(input.num_records, 2);
+        assert_eq!(input.get_record(0).shape_type, ShapeType::Null);
+        assert_eq!(input.get_record(0).num_points, 0);
+        let poly = input.get_record(1);
+        assert_eq!(poly.shape_type, ShapeType::Polygon);
+        assert_eq!(poly.parts, vec![0, 5]);
+        let points: Vec<(f64, f64)> = poly.points.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(points, rings().concat());
+        assert!(!poly.is_hole(0));
+        assert!(poly.is_hole(1));
+        assert!(poly.contains(&Point2D::new(1.0, 1.0)));
+        assert!(!poly.contains(&Point2D::new(5.0, 5.0)));
+        assert_eq!(input.attributes.get_value(1, 0), &FieldData::Int(2));
+        assert!(input.projection.is_empty());
+    }
+
+    #[test]
+    fn test_hand_built_file_round_trip() {
+        let input_file = temp_file_name("hand_built_in.shp");
+        let output_file = temp_file_name("hand_built_out.shp");
+        let (shp, shx, dbf) = write_hand_built_polygon_file(&input_file);
+        let input = Shapefile::new(&input_file, "r").unwrap();
+        let mut output = Shapefile::initialize_using_file(&output_file, &input, ShapeType::Polygon, true).unwrap();
+        for i in 0..input.num_records {
+            output.add_record(input.get_record(i).clone());
+            output.attributes.add_record(input.attributes.get_record(i).clone());
+        }
+        output.write().unwrap();
+        let out_shp = fs::read(&output_file).unwrap();
+        let out_shx = fs::read(Path::new(&output_file).with_extension("shx")).unwrap();
+        let out_dbf = fs::read(Path::new(&output_file).with_extension("dbf")).unwrap();
+        let prj_exists = Path::new(&output_file).with_extension("prj").exists();
+        remove_files(&input_file);
+        remove_files(&output_file);
+
+        assert_eq!(out_shp, shp);
+        assert_eq!(out_shx, shx);
+        // only the date of last update differs
+        assert_eq!(&out_dbf[4..], &dbf[4..]);
+        assert!(!prj_exists);
+    }
+
+    #[test]
+    fn test_read_malformed_records() {
+        // the polygon's content begins at byte 120, with the part and point counts at
+        // bytes 156 and 160 and the part offsets at bytes 164 and 168
+        let edits: Vec<(usize, i32)> = vec![
+            (160, -1),   // a negative number of points
+            (156, -1),   // a negative number of parts
+            (160, 1000), // more points than the record holds
+            (156, 1000), // more parts than the record holds
+            (168, 0),    // part offsets that do not increase
+            (168, 10),   // a part offset beyond the last point
+        ];
+        for &(pos, value) in &edits {
+            let file_name = temp_file_name(&format!("malformed_{}_{}.shp", pos, value));
+            let (mut shp, _, _) = write_hand_built_polygon_file(&file_name);
+            (&mut shp[pos..pos + 4]).write_i32::<LittleEndian>(value).unwrap();
+            fs::write(&file_name, &shp).unwrap();
+            let result = Shapefile::new(&file_name, "r");
+            remove_files(&file_name);
+            assert_eq!(result.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+        }
+    }
+
+    #[test]
+    fn test_missing_and_short_attribute_tables() {
+        let file_name = temp_file_name("missing_dbf.shp");
+        write_hand_built_polygon_file(&file_name);
+        fs::remove_file(Path::new(&file_name).with_extension("dbf")).unwrap();
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        remove_files(&file_name);
+        assert_eq!(input.attributes.get_num_records(), 2);
+        assert!(input.attributes.get_record(1).is_empty());
+
+        // a table with a record for the first shape only
+        let file_name = temp_file_name("short_dbf.shp");
+        let (_, _, mut dbf) = write_hand_built_polygon_file(&file_name);
+        dbf[4] = 1;
+        fs::write(Path::new(&file_name).with_extension("dbf"), &dbf).unwrap();
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        remove_files(&file_name);
+        assert_eq!(input.attributes.get_num_records(), 2);
+        assert_eq!(input.attributes.get_value(0, 0), &FieldData::Int(1));
+        assert_eq!(input.attributes.get_value(1, 0), &FieldData::Null);
+    }
+
+    /// Builds a shape of the given type, made up of one or two parts for polylines and
+    /// polygons, with z values and measures where the type has them.
+    fn build_shape(shape_type: ShapeType) -> ShapefileGeometry {
+        let mut sfg = ShapefileGeometry::new(shape_type);
+        let ring = [Point2D::new(0.0, 0.0), Point2D::new(0.0, 8.0), Point2D::new(8.0, 8.0), Point2D::new(8.0, 0.0), Point2D::new(0.0, 0.0)];
+        let hole = [Point2D::new(2.0, 2.0), Point2D::new(4.0, 2.0), Point2D::new(4.0, 4.0), Point2D::new(2.0, 4.0), Point2D::new(2.0, 2.0)];
+        let z = [10.0, 11.0, 12.0, 13.0, 10.0];
+        let m = [0.0, 8.0, 16.0, 24.0, 32.0];
+        match shape_type.base_shape_type() {
+            ShapeType::Point => sfg.add_point_zm(Point2D::new(1.5, -2.5), 7.0, 3.0),
+            ShapeType::MultiPoint => {
+                for i in 0..4 {
+                    sfg.add_point_zm(ring[i], z[i], m[i]);
+                }
+            },
+            _ => {
+                sfg.add_part_zm(&ring, &z, &m);
+                sfg.add_part_zm(&hole, &z, &m);
+            },
+        }
+        if !shape_type.has_m() {
+            // two-dimensional shapes hold neither z values nor measures
+            sfg.z_array.clear();
+            sfg.m_array.clear();
+        }
+        sfg
+    }
+
+    #[test]
+    fn test_round_trip_all_shape_types() {
+        let shape_types = [
+            ShapeType::Point, ShapeType::PointZ, ShapeType::PointM,
+            ShapeType::MultiPoint, ShapeType::MultiPointZ, ShapeType::MultiPointM,
+            ShapeType::PolyLine, ShapeType::PolyLineZ, ShapeType::PolyLineM,
+            ShapeType::Polygon, ShapeType::PolygonZ, ShapeType::PolygonM,
+        ];
+        for shape_type in shape_types.iter() {
+            let file_name = temp_file_name(&format!("{}.shp", shape_type));
+            let mut output = Shapefile::new(&file_name, "w").unwrap();
+            output.header.shape_type = *shape_type;
+            output.attributes.add_field(&AttributeField::new("FID", FieldDataType::Int, 4, 0));
+            output.add_record(build_shape(*shape_type));
+            output.attributes.add_record(vec![FieldData::Int(1)]);
+            output.add_record(ShapefileGeometry::new(ShapeType::Null));
+            output.attributes.add_record(vec![FieldData::Int(2)]);
+            output.write().unwrap();
+            let input = Shapefile::new(&file_name, "r").unwrap();
+            remove_files(&file_name);
+
+            let expected = build_shape(*shape_type);
+            assert_eq!(input.header.shape_type, *shape_type);
+            assert_eq!(input.num_records, 2);
+            let sfg = input.get_record(0);
+            assert_eq!(sfg.shape_type, *shape_type);
+            assert_eq!(sfg.num_parts, expected.num_parts, "{}", shape_type);
+            assert_eq!(sfg.parts, expected.parts, "{}", shape_type);
+            assert_eq!(sfg.points, expected.points, "{}", shape_type);
+            assert_eq!(sfg.z_array, expected.z_array, "{}", shape_type);
+            assert_eq!(sfg.m_array, expected.m_array, "{}", shape_type);
+            assert_eq!((input.header.x_min, input.header.x_max), (expected.x_min, expected.x_max));
+            if shape_type.has_z() {
+                assert_eq!((input.header.z_min, input.header.z_max), (expected.z_min, expected.z_max));
+            }
+            if shape_type.has_m() {
+                assert_eq!((input.header.m_min, input.header.m_max), (expected.m_min, expected.m_max));
+            }
+            assert_eq!(input.get_record(1).shape_type, ShapeType::Null);
+            assert_eq!(input.attributes.get_value(1, 0), &FieldData::Int(2));
+        }
+    }
+
+    #[test]
+    fn test_missing_measures() {
+        let file_name = temp_file_name("missing_measures.shp");
+        let mut output = Shapefile::new(&file_name, "w").unwrap();
+        output.header.shape_type = ShapeType::PolyLineM;
+        let mut sfg = ShapefileGeometry::new(ShapeType::PolyLineM);
+        sfg.add_part_zm(&[Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0)], &[0.0, 0.0], &[f64::NAN, 2.0]);
+        output.add_record(sfg);
+        output.write().unwrap();
+        let input = Shapefile::new(&file_name, "r").unwrap();
+        remove_files(&file_name);
+
+        let m = &input.get_record(0).m_array;
+        assert!(m[0].is_nan());
+        assert_eq!(m[1], 2.0);
+    }
+
+    #[test]
+    fn test_projection_passthrough() {
+        let wkt = "PROJCS[\"NAD_1983_UTM_Zone_17N\",GEOGCS[\"GCS_North_American_1983\"]]";
+        let input_file = temp_file_name("prj_in.shp");
+        let output_file = temp_file_name("prj_out.shp");
+        let mut output = Shapefile::new(&input_file, "w").unwrap();
+        output.header.shape_type = ShapeType::Point;
+        output.projection = wkt.to_string();
+        output.add_record(build_shape(ShapeType::Point));
+        output.write().unwrap();
+        let input = Shapefile::new(&input_file, "r").unwrap();
+        let mut copy = Shapefile::initialize_using_file(&output_file, &input, ShapeType::Point, false).unwrap();
+        copy.add_record(input.get_record(0).clone());
+        copy.write().unwrap();
+        let prj = fs::read_to_string(Path::new(&output_file).with_extension("prj")).unwrap();
+        remove_files(&input_file);
+        remove_files(&output_file);
+
+        assert_eq!(input.projection, wkt);
+        assert_eq!(prj, wkt);
+    }
+}